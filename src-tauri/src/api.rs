@@ -0,0 +1,505 @@
+// 本地 HTTP 接口 - 应用运行时供编辑器插件（VS Code、Vim 等）和脚本获取、渲染和插入提示词
+// 默认关闭；只监听 127.0.0.1，请求需携带 Authorization: Bearer <令牌>
+// Host 须为 127.0.0.1 或 localhost 加配置的端口，防止网页通过 DNS 重绑定访问
+// 令牌随机生成，保存在 config.json 旁的 api-token 文件中
+//
+//   GET  /prompts[?folder=文件夹]           提示词列表（与快速插入弹窗相同：置顶优先，按 frecency 排序）
+//   GET  /prompts/<ID、助记词或相对路径>    提示词详情，含需要填写的输入项
+//   GET  /search?q=关键词[&limit=数量]      搜索
+//   POST /render  {"prompt", "inputs", "selection", "clipboard"}   渲染模板
+//   POST /insert  {"prompt", "inputs"}      渲染并插入到当前焦点窗口（与快速插入相同）
+//
+// 出错时返回 {"error": "..."}
+use crate::{mnemonic, template};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+// 请求体大小上限
+const MAX_BODY: u64 = 1024 * 1024;
+
+// 本地接口配置
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ApiConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_port")]
+    pub port: u16,
+}
+
+fn default_port() -> u16 {
+    7373
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self { enabled: false, port: default_port() }
+    }
+}
+
+// ====== 令牌 ======
+
+// 读取令牌，不存在时生成
+pub fn load_or_create_token(path: &Path) -> Result<String, String> {
+    if let Ok(token) = fs::read_to_string(path) {
+        let token = token.trim();
+        if !token.is_empty() {
+            return Ok(token.to_string());
+        }
+    }
+    regenerate_token(path)
+}
+
+// 生成新令牌（旧令牌立即失效）
+pub fn regenerate_token(path: &Path) -> Result<String, String> {
+    let token = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+    fs::write(path, &token).map_err(|e| format!("保存接口令牌失败: {}", e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(path, fs::Permissions::from_mode(0o600));
+    }
+    Ok(token)
+}
+
+// 比较令牌（耗时与内容无关）
+fn token_matches(given: &str, token: &str) -> bool {
+    given.len() == token.len()
+        && given.bytes().zip(token.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+fn header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request.headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str())
+}
+
+fn authorized(request: &Request, token: &str) -> bool {
+    header(request, "Authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|given| token_matches(given.trim(), token))
+}
+
+// Host 是否指向本机的接口端口（端口为 80 时可省略）
+fn host_allowed(host: Option<&str>, port: u16) -> bool {
+    let Some(host) = host.map(|h| h.trim().to_ascii_lowercase()) else {
+        return false;
+    };
+    let (name, host_port) = match host.rsplit_once(':') {
+        Some((name, host_port)) => (name, host_port.parse::<u16>().ok()),
+        None => (host.as_str(), Some(80)),
+    };
+    matches!(name, "127.0.0.1" | "localhost") && host_port == Some(port)
+}
+
+// ====== 服务 ======
+
+// 运行中的本地接口（Tauri managed state）
+#[derive(Default)]
+pub struct ApiState {
+    server: Mutex<Option<(u16, Arc<Server>)>>,
+}
+
+// 按配置启动、停止或重启本地接口
+pub fn apply(app: &AppHandle, config: &ApiConfig) -> Result<(), String> {
+    let state = app.state::<ApiState>();
+    let mut running = state.server.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((port, server)) = running.as_ref() {
+        if config.enabled && *port == config.port {
+            return Ok(());
+        }
+        server.unblock();
+        *running = None;
+    }
+    if !config.enabled {
+        return Ok(());
+    }
+
+    let server = Server::http(("127.0.0.1", config.port))
+        .map_err(|e| format!("启动本地接口失败（端口 {}）: {}", config.port, e))?;
+    let server = Arc::new(server);
+    let worker = server.clone();
+    let port = config.port;
+    let app = app.clone();
+    std::thread::spawn(move || {
+        for request in worker.incoming_requests() {
+            handle(&app, port, request);
+        }
+    });
+    *running = Some((config.port, server));
+    Ok(())
+}
+
+// 接口错误（HTTP 状态码和说明）
+struct ApiError {
+    status: u16,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: u16, message: impl Into<String>) -> Self {
+        Self { status, message: message.into() }
+    }
+}
+
+impl From<String> for ApiError {
+    fn from(message: String) -> Self {
+        Self::new(500, message)
+    }
+}
+
+type ApiResult = Result<serde_json::Value, ApiError>;
+
+fn json_response(status: u16, value: &serde_json::Value) -> Response<std::io::Cursor<Vec<u8>>> {
+    let header = Header::from_bytes(&b"Content-Type"[..], &b"application/json; charset=utf-8"[..])
+        .expect("valid header");
+    Response::from_string(value.to_string())
+        .with_status_code(status)
+        .with_header(header)
+}
+
+fn handle(app: &AppHandle, port: u16, request: Request) {
+    serve(request, port, crate::get_api_token(), |request| route(app, request));
+}
+
+// 检查 Host 和令牌后交给 route 处理，并返回 JSON 响应
+fn serve(
+    mut request: Request,
+    port: u16,
+    token: Result<String, String>,
+    route: impl FnOnce(&mut Request) -> ApiResult,
+) {
+    let result = if !host_allowed(header(&request, "Host"), port) {
+        Err(ApiError::new(403, "不允许的 Host"))
+    } else {
+        match token {
+            Ok(token) if authorized(&request, &token) => route(&mut request),
+            Ok(_) => Err(ApiError::new(401, "令牌无效")),
+            Err(e) => Err(ApiError::from(e)),
+        }
+    };
+    let response = match result {
+        Ok(value) => json_response(200, &value),
+        Err(e) => json_response(e.status, &serde_json::json!({ "error": e.message })),
+    };
+    if let Err(e) = request.respond(response) {
+        eprintln!("本地接口响应失败: {}", e);
+    }
+}
+
+// 请求对应的接口
+#[derive(Debug, PartialEq)]
+enum Endpoint {
+    List,
+    Search,
+    Get(String),
+    Render,
+    Insert,
+}
+
+fn endpoint(method: &Method, path: &str) -> Option<Endpoint> {
+    match (method, path) {
+        (Method::Get, "/prompts") => Some(Endpoint::List),
+        (Method::Get, "/search") => Some(Endpoint::Search),
+        (Method::Get, _) if path.starts_with("/prompts/") => {
+            Some(Endpoint::Get(decode_path(&path["/prompts/".len()..])))
+        }
+        (Method::Post, "/render") => Some(Endpoint::Render),
+        (Method::Post, "/insert") => Some(Endpoint::Insert),
+        _ => None,
+    }
+}
+
+// 解析请求地址，返回接口和查询参数
+fn resolve(request: &Request) -> Result<(Endpoint, HashMap<String, String>), ApiError> {
+    let url = request.url();
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let endpoint = endpoint(request.method(), path).ok_or_else(|| ApiError::new(404, "接口不存在"))?;
+    Ok((endpoint, parse_query(query)))
+}
+
+fn route(app: &AppHandle, request: &mut Request) -> ApiResult {
+    let (endpoint, params) = resolve(request)?;
+    match endpoint {
+        Endpoint::List => list(app, params.get("folder").map(String::as_str)),
+        Endpoint::Search => search(app, &params),
+        Endpoint::Get(query) => get(app, &query),
+        Endpoint::Render => render(app, read_body(request)?),
+        Endpoint::Insert => insert(app, read_body(request)?),
+    }
+}
+
+// 路径中的 + 是字面量，只解码 %XX
+fn decode_path(text: &str) -> String {
+    percent_encoding::percent_decode_str(text).decode_utf8_lossy().to_string()
+}
+
+// 查询参数中的 + 表示空格
+fn decode(text: &str) -> String {
+    decode_path(&text.replace('+', " "))
+}
+
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(key), decode(value))
+        })
+        .collect()
+}
+
+fn read_body<T: serde::de::DeserializeOwned>(request: &mut Request) -> Result<T, ApiError> {
+    let mut body = String::new();
+    request.as_reader()
+        .take(MAX_BODY)
+        .read_to_string(&mut body)
+        .map_err(|e| ApiError::new(400, format!("读取请求失败: {}", e)))?;
+    serde_json::from_str(&body).map_err(|e| ApiError::new(400, format!("解析请求失败: {}", e)))
+}
+
+fn to_json<T: Serialize>(value: &T) -> ApiResult {
+    serde_json::to_value(value).map_err(|e| ApiError::from(format!("序列化失败: {}", e)))
+}
+
+// 按 ID、助记词或相对路径查找提示词（从内存中的搜索索引读取）
+fn find(app: &AppHandle, query: &str) -> Result<crate::library::ScannedFile, ApiError> {
+    let files = crate::indexed_files(app)?;
+    mnemonic::find_prompt(&files, query)
+        .cloned()
+        .map_err(|e| ApiError::new(404, e))
+}
+
+fn list(app: &AppHandle, folder: Option<&str>) -> ApiResult {
+    let folder = folder.map(|f| f.replace('\\', "/").trim_matches('/').to_string());
+    let items: Vec<crate::MnemonicItem> = crate::get_all_mnemonics(app.clone())?
+        .into_iter()
+        .filter(|item| folder.as_deref().is_none_or(|f| {
+            item.namespace == f || item.namespace.starts_with(&format!("{}/", f))
+        }))
+        .collect();
+    to_json(&items)
+}
+
+fn search(app: &AppHandle, params: &HashMap<String, String>) -> ApiResult {
+    let query = params.get("q").cloned().unwrap_or_default();
+    let limit = match params.get("limit") {
+        Some(limit) => Some(limit.parse().map_err(|_| ApiError::new(400, "limit 应为数字"))?),
+        None => None,
+    };
+    to_json(&crate::search_prompts(app.clone(), query, limit)?)
+}
+
+// 提示词详情
+#[derive(Serialize)]
+struct PromptDetail {
+    #[serde(flatten)]
+    prompt: crate::Prompt,
+    // 模板中需要填写的输入项
+    inputs: Vec<template::TemplateInput>,
+}
+
+fn get(app: &AppHandle, query: &str) -> ApiResult {
+    let file = find(app, query)?;
+    let text = crate::expand_partials(&file.body, || crate::indexed_files(app))?;
+    let inputs = template::required_inputs(&template::parse(&text));
+    to_json(&PromptDetail { prompt: crate::to_prompt(&crate::library_root()?, file), inputs })
+}
+
+// 展开片段并检查输入项是否填写完整
+fn parse_template(
+    app: &AppHandle,
+    body: &str,
+    inputs: &HashMap<String, String>,
+) -> Result<Vec<template::Segment>, ApiError> {
+    let text = crate::expand_partials(body, || crate::indexed_files(app))?;
+    let segments = template::parse(&text);
+    let missing = template::missing_inputs(&segments, inputs);
+    if !missing.is_empty() {
+        return Err(ApiError::new(400, format!("缺少输入项: {}", missing.join(", "))));
+    }
+    Ok(segments)
+}
+
+#[derive(Deserialize)]
+struct RenderRequest {
+    prompt: String,
+    #[serde(default)]
+    inputs: HashMap<String, String>,
+    selection: Option<String>,
+    clipboard: Option<String>,
+}
+
+fn render(app: &AppHandle, req: RenderRequest) -> ApiResult {
+    let file = find(app, &req.prompt)?;
+    let segments = parse_template(app, &file.body, &req.inputs)?;
+    let ctx = template::RenderContext {
+        selection: req.selection,
+        clipboard: req.clipboard,
+        inputs: req.inputs,
+    };
+    let rendered = template::render(&segments, &ctx).map_err(|e| ApiError::new(400, e))?;
+    crate::record_usage(Some(file.id()));
+    to_json(&rendered)
+}
+
+#[derive(Deserialize)]
+struct InsertRequest {
+    prompt: String,
+    #[serde(default)]
+    inputs: HashMap<String, String>,
+}
+
+fn insert(app: &AppHandle, req: InsertRequest) -> ApiResult {
+    let file = find(app, &req.prompt)?;
+    parse_template(app, &file.body, &req.inputs)?;
+    let id = file.id().to_string();
+    crate::type_text(app.clone(), file.body, Some(req.inputs), None, Some(id.clone()))?;
+    Ok(serde_json::json!({ "id": id }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpStream;
+
+    #[test]
+    fn token_matches_only_exact_token() {
+        assert!(token_matches("abc123", "abc123"));
+        for given in ["", "abc12", "abc1234", "ABC123", "abc124", " abc123"] {
+            assert!(!token_matches(given, "abc123"), "{:?}", given);
+        }
+        assert!(!token_matches("x", ""));
+    }
+
+    #[test]
+    fn decodes_query_parameters() {
+        let params = parse_query("q=code+review&folder=work%2Fdaily&empty=&flag&&limit=5&%E4%B8%AD=%E6%96%87&plus=%2B");
+        let expected: HashMap<String, String> = [
+            ("q", "code review"),
+            ("folder", "work/daily"),
+            ("empty", ""),
+            ("flag", ""),
+            ("limit", "5"),
+            ("中", "文"),
+            ("plus", "+"),
+        ].into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        assert_eq!(params, expected);
+        assert!(parse_query("").is_empty());
+        // 无效的 UTF-8 不会报错
+        assert_eq!(decode("%FF"), "\u{FFFD}");
+    }
+
+    #[test]
+    fn routes_requests() {
+        let cases = [
+            (Method::Get, "/prompts", Some(Endpoint::List)),
+            (Method::Get, "/search", Some(Endpoint::Search)),
+            (Method::Get, "/prompts/work%2Fsum", Some(Endpoint::Get("work/sum".to_string()))),
+            // 路径中的 + 不是空格
+            (Method::Get, "/prompts/c++", Some(Endpoint::Get("c++".to_string()))),
+            (Method::Get, "/prompts/%E6%80%BB%E7%BB%93", Some(Endpoint::Get("总结".to_string()))),
+            (Method::Post, "/render", Some(Endpoint::Render)),
+            (Method::Post, "/insert", Some(Endpoint::Insert)),
+            (Method::Post, "/prompts", None),
+            (Method::Get, "/render", None),
+            (Method::Get, "/prompt", None),
+            (Method::Get, "/prompts-x", None),
+            (Method::Delete, "/prompts/x", None),
+            (Method::Get, "/", None),
+        ];
+        for (method, path, expected) in cases {
+            assert_eq!(endpoint(&method, path), expected, "{} {}", method, path);
+        }
+    }
+
+    #[test]
+    fn host_must_be_local_with_port() {
+        for host in ["127.0.0.1:7373", "localhost:7373", "LocalHost:7373", " localhost:7373 "] {
+            assert!(host_allowed(Some(host), 7373), "{:?}", host);
+        }
+        for host in ["", "localhost", "127.0.0.1", "localhost:80", "localhost:7374", "evil.example:7373",
+            "localhost.evil.example:7373", "127.0.0.1.nip.io:7373", "[::1]:7373", "localhost:abc"] {
+            assert!(!host_allowed(Some(host), 7373), "{:?}", host);
+        }
+        assert!(!host_allowed(None, 7373));
+        assert!(host_allowed(Some("localhost"), 80));
+    }
+
+    const TOKEN: &str = "0123456789abcdef";
+
+    // 启动只用于测试的接口：通过 Host 和令牌检查后返回匹配到的接口
+    fn start_server() -> u16 {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let port = server.server_addr().to_ip().unwrap().port();
+        std::thread::spawn(move || {
+            for request in server.incoming_requests() {
+                serve(request, port, Ok(TOKEN.to_string()), |request| {
+                    let (endpoint, params) = resolve(request)?;
+                    Ok(serde_json::json!({ "endpoint": format!("{:?}", endpoint), "params": params }))
+                });
+            }
+        });
+        port
+    }
+
+    // 发送请求，返回状态码和响应体
+    fn send(port: u16, request_line: &str, headers: &[String]) -> (u16, serde_json::Value) {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        let mut request = format!("{}\r\nConnection: close\r\n", request_line);
+        for header in headers {
+            request.push_str(&format!("{}\r\n", header));
+        }
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response.split(' ').nth(1).unwrap().parse().unwrap();
+        let body = response.split_once("\r\n\r\n").unwrap().1;
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    #[test]
+    fn rejects_bad_host_missing_token_and_unknown_routes() {
+        let port = start_server();
+        let host = format!("Host: localhost:{}", port);
+        let auth = format!("Authorization: Bearer {}", TOKEN);
+
+        let (status, body) = send(port, "GET /search?q=a+b HTTP/1.1", &[host.clone(), auth.clone()]);
+        assert_eq!(status, 200);
+        assert_eq!(body["endpoint"], "Search");
+        assert_eq!(body["params"]["q"], "a b");
+
+        // 令牌缺失或错误
+        for headers in [
+            vec![host.clone()],
+            vec![host.clone(), "Authorization: Bearer wrong".to_string()],
+            vec![host.clone(), format!("Authorization: Basic {}", TOKEN)],
+            vec![host.clone(), format!("Authorization: Bearer {}x", TOKEN)],
+        ] {
+            let (status, body) = send(port, "GET /prompts HTTP/1.1", &headers);
+            assert_eq!(status, 401, "{:?}", headers);
+            assert_eq!(body["error"], "令牌无效");
+        }
+        // 未授权时不透露接口是否存在
+        assert_eq!(send(port, "GET /nope HTTP/1.1", std::slice::from_ref(&host)).0, 401);
+
+        let (status, body) = send(port, "GET /nope HTTP/1.1", &[host.clone(), auth.clone()]);
+        assert_eq!(status, 404);
+        assert_eq!(body["error"], "接口不存在");
+        assert_eq!(send(port, "DELETE /prompts/x HTTP/1.1", &[host.clone(), auth.clone()]).0, 404);
+
+        // DNS 重绑定：令牌正确但 Host 不是本机
+        let (status, body) = send(port, "GET /prompts HTTP/1.1", &["Host: evil.example".to_string(), auth.clone()]);
+        assert_eq!(status, 403);
+        assert_eq!(body["error"], "不允许的 Host");
+        assert_eq!(send(port, "GET /prompts HTTP/1.1", &[format!("Host: localhost:{}", port + 1), auth]).0, 403);
+    }
+}
@@ -0,0 +1,4 @@
+// 命令行工具：在终端、脚本和编辑器中使用提示词库，与桌面应用共用配置
+fn main() -> std::process::ExitCode {
+    prompt_manager_lib::run_cli()
+}
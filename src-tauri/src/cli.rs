@@ -0,0 +1,605 @@
+// 命令行工具 - 在终端、脚本和编辑器中使用提示词库（prompt-manager-cli）
+// 与桌面应用共用配置文件、ID 索引和各项操作：修改同样会记录版本历史，Git 模式下自动提交
+// 提示词可以用 ID、助记词（可带文件夹，如 work/sum）或相对路径（可省略扩展名）指定
+use crate::library::{self, ScannedFile};
+use crate::mnemonic;
+use crate::{crypto, metadata, search, sync, template, usage};
+use clap::{Parser, Subcommand};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(name = "prompt-manager-cli", version, about = "提示词管理器命令行工具")]
+struct Cli {
+    /// 以 JSON 格式输出
+    #[arg(long, global = true)]
+    json: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 列出提示词
+    List {
+        /// 只列出该文件夹（相对路径）中的提示词
+        #[arg(long)]
+        folder: Option<String>,
+    },
+    /// 搜索提示词（助记词、标题、拼音首字母和正文）
+    Search {
+        query: String,
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// 输出提示词正文
+    Show {
+        prompt: String,
+        /// 同时输出 front matter
+        #[arg(long)]
+        raw: bool,
+    },
+    /// 渲染提示词模板
+    Render {
+        prompt: String,
+        /// 填写输入项，可重复使用
+        #[arg(short = 's', long = "set", value_name = "名称=值")]
+        set: Vec<String>,
+        /// {{selection}} 的内容（未指定时从标准输入读取）
+        #[arg(long)]
+        selection: Option<String>,
+        /// {{clipboard}} 的内容
+        #[arg(long)]
+        clipboard: Option<String>,
+    },
+    /// 新建提示词，路径为 文件夹/标题，正文从 --content 或标准输入读取
+    Add {
+        path: String,
+        #[arg(short, long)]
+        content: Option<String>,
+        /// 助记词，第一个为主助记词
+        #[arg(short, long)]
+        mnemonic: Vec<String>,
+    },
+    /// 修改提示词正文（未指定 --content 且标准输入为终端时使用 $EDITOR 编辑）
+    Edit {
+        prompt: String,
+        #[arg(short, long)]
+        content: Option<String>,
+    },
+    /// 重命名或移动提示词，目标为 文件夹/标题，以 / 结尾时保留原标题
+    Mv { prompt: String, dest: String },
+    /// 删除提示词（移到回收站）
+    Rm { prompt: String },
+    /// 查看或修改助记词
+    Mnemonic {
+        #[command(subcommand)]
+        action: MnemonicCommand,
+    },
+    /// 与远程同步（Git 模式下拉取后推送）
+    Sync {
+        /// 只列出将要执行的操作
+        #[arg(long)]
+        preview: bool,
+        /// 开启同步加密后，接受远程未加密的文件并加密重新上传（仅在确认这些文件来自自己时使用）
+        #[arg(long)]
+        migrate_plaintext: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum MnemonicCommand {
+    /// 输出全部助记词（主助记词在前）
+    Get { prompt: String },
+    /// 设置全部助记词，第一个为主助记词
+    Set {
+        prompt: String,
+        #[arg(required = true)]
+        mnemonics: Vec<String>,
+    },
+    /// 删除全部助记词
+    Rm { prompt: String },
+}
+
+// 命令行入口
+pub fn run() -> ExitCode {
+    let cli = Cli::parse();
+    match execute(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("错误: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn execute(cli: Cli) -> Result<(), String> {
+    crypto::unlock_from_env(&crate::get_key_path())?;
+
+    let json = cli.json;
+    match cli.command {
+        Command::List { folder } => list(folder.as_deref(), json),
+        Command::Search { query, limit } => search(&query, limit, json),
+        Command::Show { prompt, raw } => show(&prompt, raw, json),
+        Command::Render { prompt, set, selection, clipboard } => {
+            render(&prompt, &set, selection, clipboard, json)
+        }
+        Command::Add { path, content, mnemonic } => add(&path, content, mnemonic),
+        Command::Edit { prompt, content } => edit(&prompt, content),
+        Command::Mv { prompt, dest } => move_prompt(&prompt, &dest),
+        Command::Rm { prompt } => {
+            let file = find_prompt(&prompt)?;
+            crate::delete_prompt(file.path.to_string_lossy().to_string())?;
+            println!("已移到回收站: {}", file.rel_path);
+            Ok(())
+        }
+        Command::Mnemonic { action } => match action {
+            MnemonicCommand::Get { prompt } => {
+                let mnemonics = crate::get_mnemonics(find_prompt(&prompt)?.id().to_string())?;
+                if json {
+                    return print_json(&mnemonics);
+                }
+                mnemonics.iter().for_each(|m| println!("{}", m));
+                Ok(())
+            }
+            MnemonicCommand::Set { prompt, mnemonics } => {
+                crate::set_mnemonics(mnemonics, find_prompt(&prompt)?.id().to_string())
+            }
+            MnemonicCommand::Rm { prompt } => {
+                crate::remove_mnemonic(find_prompt(&prompt)?.id().to_string())
+            }
+        },
+        Command::Sync { preview, migrate_plaintext } => sync(preview, migrate_plaintext, json),
+    }
+}
+
+fn print_json<T: Serialize + ?Sized>(value: &T) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("序列化失败: {}", e))?;
+    println!("{}", content);
+    Ok(())
+}
+
+// 输出文本，确保以换行结尾
+fn print_text(text: &str) {
+    if text.ends_with('\n') {
+        print!("{}", text);
+    } else {
+        println!("{}", text);
+    }
+}
+
+// 读取提示词库（与应用相同：分配缺少的 ID 并更新 ID 索引），修改提示词的命令使用
+fn load_files() -> Result<Vec<ScannedFile>, String> {
+    crate::scan_library(&crate::library_root()?)
+}
+
+// 只读取提示词库（不写入 ID），查看类命令使用
+fn read_files() -> Result<Vec<ScannedFile>, String> {
+    crate::read_library(&crate::library_root()?)
+}
+
+// 按 ID、相对路径或助记词查找提示词
+fn find_prompt(query: &str) -> Result<ScannedFile, String> {
+    let files = load_files()?;
+    mnemonic::find_prompt(&files, query).cloned()
+}
+
+// 只读查找提示词
+fn read_prompt(query: &str) -> Result<ScannedFile, String> {
+    let files = read_files()?;
+    mnemonic::find_prompt(&files, query).cloned()
+}
+
+// 拆分 文件夹/标题（没有文件夹时为根目录）
+fn split_path(path: &str) -> (String, String) {
+    let path = path.replace('\\', "/");
+    match path.trim_matches('/').rsplit_once('/') {
+        Some((folder, title)) => (folder.to_string(), title.to_string()),
+        None => (String::new(), path.trim_matches('/').to_string()),
+    }
+}
+
+// 未通过参数指定时从标准输入读取（标准输入为终端时返回 None）
+fn read_stdin() -> Result<Option<String>, String> {
+    let mut stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return Ok(None);
+    }
+    let mut text = String::new();
+    stdin.read_to_string(&mut text).map_err(|e| format!("读取标准输入失败: {}", e))?;
+    Ok(Some(text))
+}
+
+fn list(folder: Option<&str>, json: bool) -> Result<(), String> {
+    let root = crate::library_root()?;
+    let prefix = folder.map(|f| format!("{}/", f.replace('\\', "/").trim_matches('/')));
+    let prompts: Vec<crate::Prompt> = crate::scan_prompts(root.to_string_lossy().to_string())?
+        .into_iter()
+        .filter(|p| prefix.as_deref().is_none_or(|prefix| p.rel_path.starts_with(prefix)))
+        .collect();
+    if json {
+        return print_json(&prompts);
+    }
+    for p in &prompts {
+        println!("{}\t{}", p.rel_path, p.metadata.mnemonics().join(","));
+    }
+    Ok(())
+}
+
+fn search(query: &str, limit: usize, json: bool) -> Result<(), String> {
+    let files = read_files()?;
+    let usage = usage::UsageLog::load(&crate::get_usage_path());
+    let results = search::search(&files, query, limit, &usage);
+    if json {
+        return print_json(&results);
+    }
+    for r in &results {
+        let preview = r.preview.lines().next().unwrap_or_default();
+        println!("{}\t{}\t{}", r.rel_path, r.mnemonic, preview);
+    }
+    Ok(())
+}
+
+// 提示词详情（JSON 输出）
+#[derive(Serialize)]
+struct PromptDetail<'a> {
+    id: &'a str,
+    title: String,
+    rel_path: &'a str,
+    metadata: &'a metadata::PromptMetadata,
+    content: &'a str,
+}
+
+fn show(query: &str, raw: bool, json: bool) -> Result<(), String> {
+    let file = read_prompt(query)?;
+    if json {
+        return print_json(&PromptDetail {
+            id: file.id(),
+            title: library::file_title(&file.path),
+            rel_path: &file.rel_path,
+            metadata: &file.metadata,
+            content: &file.body,
+        });
+    }
+    if raw {
+        print_text(&metadata::compose(&file.metadata, &file.body)?);
+    } else {
+        print_text(&file.body);
+    }
+    Ok(())
+}
+
+// 解析 --set 名称=值（值中可以包含 =）
+fn parse_inputs(set: &[String]) -> Result<HashMap<String, String>, String> {
+    let mut inputs = HashMap::new();
+    for item in set {
+        let (name, value) = item.split_once('=')
+            .ok_or_else(|| format!("输入项格式应为 名称=值: {}", item))?;
+        inputs.insert(name.trim().to_string(), value.to_string());
+    }
+    Ok(inputs)
+}
+
+fn render(
+    query: &str,
+    set: &[String],
+    selection: Option<String>,
+    clipboard: Option<String>,
+    json: bool,
+) -> Result<(), String> {
+    let file = find_prompt(query)?;
+    let inputs = parse_inputs(set)?;
+
+    let text = crate::expand_partials(&file.body, read_files)?;
+    let segments = template::parse(&text);
+    let missing = template::missing_inputs(&segments, &inputs);
+    if !missing.is_empty() {
+        return Err(format!("缺少输入项: {}（使用 --set 名称=值 填写）", missing.join(", ")));
+    }
+    let selection = match selection {
+        Some(selection) => Some(selection),
+        None if template::uses_selection(&segments) => read_stdin()?,
+        None => None,
+    };
+
+    let ctx = template::RenderContext { selection, clipboard, inputs };
+    let rendered = template::render(&segments, &ctx)?;
+    crate::record_usage(Some(file.id()));
+    if json {
+        return print_json(&rendered);
+    }
+    print_text(&rendered.text);
+    Ok(())
+}
+
+// 规范化新提示词的助记词，并检查在其所在文件夹中是否已被使用
+fn new_mnemonics(files: &[ScannedFile], folder: &str, mnemonics: &[String]) -> Result<Vec<String>, String> {
+    let mut list: Vec<String> = Vec::new();
+    for m in mnemonics {
+        let m = mnemonic::validate(m)?;
+        if let Some(other) = mnemonic::find_conflict(files, folder, &m, "") {
+            return Err(format!("助记词 '{}' 已被同一文件夹中的 '{}' 使用", m, library::file_title(&other.path)));
+        }
+        if !list.contains(&m) {
+            list.push(m);
+        }
+    }
+    Ok(list)
+}
+
+fn add(path: &str, content: Option<String>, mnemonics: Vec<String>) -> Result<(), String> {
+    let root = crate::library_root()?;
+    let (folder, title) = split_path(path);
+    if title.is_empty() {
+        return Err("请指定标题".to_string());
+    }
+    if library::to_absolute(&root, &format!("{}/{}.md", folder, title)).exists() {
+        return Err(format!("提示词已存在: {}", path));
+    }
+    let content = match content {
+        Some(content) => content,
+        None => read_stdin()?.ok_or("请通过 --content 或标准输入提供正文")?,
+    };
+    // 创建文件之前检查助记词，冲突时不留下新建的提示词
+    let mnemonics = if mnemonics.is_empty() {
+        mnemonics
+    } else {
+        new_mnemonics(&load_files()?, &folder, &mnemonics)?
+    };
+
+    let saved = crate::save_prompt(
+        root.to_string_lossy().to_string(),
+        folder,
+        title,
+        content,
+        None,
+        None,
+    )?;
+    if !mnemonics.is_empty() {
+        let (meta, _) = metadata::read_prompt_file(Path::new(&saved))?;
+        crate::set_mnemonics(mnemonics, meta.id.unwrap_or_default())?;
+    }
+    println!("已创建: {}", library::to_relative(&root, Path::new(&saved)).unwrap_or(saved));
+    Ok(())
+}
+
+// 使用 $VISUAL / $EDITOR 编辑正文，返回编辑后的内容
+// 临时文件名随机、仅当前用户可读写（加密的提示词在编辑期间以明文存在），结束后删除
+fn edit_in_editor(file: &ScannedFile) -> Result<String, String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| if cfg!(windows) { "notepad".into() } else { "vi".into() });
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().ok_or("未设置编辑器")?;
+
+    let ext = file.path.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_else(|| "md".into());
+    let temp = create_private_temp(&ext, &file.body)?;
+    let status = std::process::Command::new(program).args(parts).arg(&temp).status();
+    let content = fs::read_to_string(&temp);
+    drop(temp);
+
+    match status {
+        Ok(status) if status.success() => content.map_err(|e| format!("读取临时文件失败: {}", e)),
+        Ok(_) => Err("编辑器异常退出，未保存".to_string()),
+        Err(e) => Err(format!("启动编辑器失败 {}: {}", program, e)),
+    }
+}
+
+// 创建只有当前用户可读写的临时文件并写入内容（关闭文件以便编辑器打开，路径释放时删除）
+fn create_private_temp(ext: &str, content: &str) -> Result<tempfile::TempPath, String> {
+    let suffix = format!(".{}", ext);
+    let mut builder = tempfile::Builder::new();
+    builder.prefix("prompt-manager-").suffix(&suffix);
+    #[cfg(unix)]
+    builder.permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600));
+    let mut temp = builder.tempfile().map_err(|e| format!("创建临时文件失败: {}", e))?;
+    std::io::Write::write_all(&mut temp, content.as_bytes())
+        .map_err(|e| format!("创建临时文件失败: {}", e))?;
+    Ok(temp.into_temp_path())
+}
+
+// 提示词所在文件夹（相对路径）和标题
+fn folder_and_title(file: &ScannedFile) -> (String, String) {
+    let folder = file.rel_path.rsplit_once('/').map(|(dir, _)| dir.to_string()).unwrap_or_default();
+    (folder, library::file_title(&file.path))
+}
+
+fn edit(query: &str, content: Option<String>) -> Result<(), String> {
+    let file = find_prompt(query)?;
+    let content = match content {
+        Some(content) => content,
+        None => match read_stdin()? {
+            Some(content) => content,
+            None => edit_in_editor(&file)?,
+        },
+    };
+    if content == file.body {
+        println!("内容未修改");
+        return Ok(());
+    }
+
+    let (folder, title) = folder_and_title(&file);
+    crate::save_prompt(
+        crate::library_root()?.to_string_lossy().to_string(),
+        folder,
+        title,
+        content,
+        Some(file.rel_path.clone()),
+        None,
+    )?;
+    println!("已保存: {}", file.rel_path);
+    Ok(())
+}
+
+// 移动的目标 (文件夹, 标题)：以 / 结尾时移到该文件夹并保留原标题
+fn move_destination(dest: &str, title: &str) -> (String, String) {
+    let mut dest = dest.replace('\\', "/");
+    if dest.ends_with('/') {
+        dest.push_str(title);
+    }
+    split_path(&dest)
+}
+
+fn move_prompt(query: &str, dest: &str) -> Result<(), String> {
+    let root = crate::library_root()?;
+    let file = find_prompt(query)?;
+    let (folder, title) = move_destination(dest, &library::file_title(&file.path));
+    if title.is_empty() {
+        return Err("请指定目标标题".to_string());
+    }
+
+    let ext = file.path.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_else(|| "md".into());
+    let target: PathBuf = library::to_absolute(&root, &format!("{}/{}.{}", folder, title, ext));
+    if target != file.path && target.exists() {
+        return Err(format!("目标已存在: {}", library::to_relative(&root, &target).unwrap_or_default()));
+    }
+
+    let saved = crate::save_prompt(
+        root.to_string_lossy().to_string(),
+        folder,
+        title,
+        file.body.clone(),
+        Some(file.rel_path.clone()),
+        None,
+    )?;
+    println!("{} -> {}", file.rel_path, library::to_relative(&root, Path::new(&saved)).unwrap_or(saved));
+    Ok(())
+}
+
+fn action_label(kind: sync::ActionKind) -> &'static str {
+    match kind {
+        sync::ActionKind::Upload => "上传",
+        sync::ActionKind::Download => "下载",
+        sync::ActionKind::DeleteLocal => "删除本机",
+        sync::ActionKind::DeleteRemote => "删除远程",
+        sync::ActionKind::Conflict => "冲突",
+        sync::ActionKind::Record => "记录",
+    }
+}
+
+fn sync(preview: bool, migrate_plaintext: bool, json: bool) -> Result<(), String> {
+    if crate::get_config()?.git.enabled {
+        if preview {
+            return Err("Git 模式不支持预览".to_string());
+        }
+        let pulled = crate::git_pull()?;
+        let pushed = crate::git_push()?;
+        if json {
+            return print_json(&[pulled, pushed]);
+        }
+        println!("{}\n{}", pulled.message, pushed.message);
+        return Ok(());
+    }
+
+    if preview {
+        let actions = crate::sync_preview()?;
+        if json {
+            return print_json(&actions);
+        }
+        if actions.is_empty() {
+            println!("已是最新");
+        }
+        for a in &actions {
+            println!("{}\t{}", action_label(a.kind), a.rel_path);
+        }
+        return Ok(());
+    }
+
+    let report = crate::sync_now(Some(migrate_plaintext))?;
+    if json {
+        return print_json(&report);
+    }
+    println!("{}", report.message);
+    for copy in &report.conflicts {
+        println!("冲突副本\t{}", copy);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(rel_path: &str, id: &str, mnemonics: &[&str]) -> ScannedFile {
+        let mut metadata = metadata::PromptMetadata { id: Some(id.to_string()), ..Default::default() };
+        metadata.set_mnemonics(mnemonics.iter().map(|m| m.to_string()).collect());
+        ScannedFile {
+            path: PathBuf::from("/library").join(rel_path),
+            rel_path: rel_path.to_string(),
+            metadata,
+            body: String::new(),
+        }
+    }
+
+    #[test]
+    fn splits_folder_and_title() {
+        let cases = [
+            ("title", ("", "title")),
+            ("work/title", ("work", "title")),
+            ("work/daily/标题 v2", ("work/daily", "标题 v2")),
+            ("work\\daily\\title", ("work/daily", "title")),
+            ("/work/title/", ("work", "title")),
+            ("", ("", "")),
+            ("/", ("", "")),
+        ];
+        for (path, (folder, title)) in cases {
+            assert_eq!(split_path(path), (folder.to_string(), title.to_string()), "{:?}", path);
+        }
+    }
+
+    #[test]
+    fn move_destination_keeps_title_for_folders() {
+        let cases = [
+            ("archive/", ("archive", "总结")),
+            ("archive\\old\\", ("archive/old", "总结")),
+            ("/", ("", "总结")),
+            ("archive/新标题", ("archive", "新标题")),
+            ("新标题", ("", "新标题")),
+        ];
+        for (dest, (folder, title)) in cases {
+            assert_eq!(move_destination(dest, "总结"), (folder.to_string(), title.to_string()), "{:?}", dest);
+        }
+    }
+
+    #[test]
+    fn parses_set_inputs() {
+        let set: Vec<String> = ["lang=Rust", " tone =正式", "expr=a=b", "empty=", "lang=Go"]
+            .iter().map(|s| s.to_string()).collect();
+        let inputs = parse_inputs(&set).unwrap();
+        let expected: HashMap<String, String> = [("lang", "Go"), ("tone", "正式"), ("expr", "a=b"), ("empty", "")]
+            .into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        assert_eq!(inputs, expected);
+        assert_eq!(parse_inputs(&["novalue".to_string()]), Err("输入项格式应为 名称=值: novalue".to_string()));
+        assert!(parse_inputs(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn new_mnemonics_checks_conflicts_in_target_folder() {
+        let files = [file("work/sum.md", "a", &["sum"]), file("top.md", "b", &["top"])];
+        let list = |m: &[&str]| m.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert_eq!(new_mnemonics(&files, "work", &list(&["Rev", "rev", "top"])), Ok(list(&["rev", "top"])));
+        assert_eq!(new_mnemonics(&files, "personal", &list(&["sum"])), Ok(list(&["sum"])));
+        let err = new_mnemonics(&files, "work", &list(&["x", "SUM"])).unwrap_err();
+        assert!(err.contains("'sum'"), "{}", err);
+        assert!(new_mnemonics(&files, "", &list(&["top"])).is_err());
+        assert!(new_mnemonics(&files, "", &list(&["a/b"])).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn editor_temp_file_is_private_and_removed() {
+        use std::os::unix::fs::PermissionsExt;
+        let temp = create_private_temp("md", "明文正文").unwrap();
+        let path = temp.to_path_buf();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "明文正文");
+        assert!(path.file_name().unwrap().to_string_lossy().ends_with(".md"));
+        drop(temp);
+        assert!(!path.exists());
+    }
+}
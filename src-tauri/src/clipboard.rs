@@ -0,0 +1,127 @@
+// 剪贴板快照 - 粘贴插入和获取选中文本都要借用剪贴板，完成后恢复用户原来复制的内容
+// 保存文本，以及支持时的富文本（HTML，附带纯文本）和图片；剪贴板原本为空时恢复为空
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::image::Image;
+use tauri::AppHandle;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+// 粘贴插入时的剪贴板设置
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ClipboardConfig {
+    // 粘贴完成后恢复原来的剪贴板内容
+    #[serde(default = "default_restore")]
+    pub restore: bool,
+    // 写入剪贴板后等待多久再按粘贴键（毫秒）
+    #[serde(default = "default_paste_delay")]
+    pub paste_delay_ms: u64,
+    // 按下粘贴键后等待多久再恢复（毫秒），目标程序读取剪贴板较慢时调大
+    #[serde(default = "default_restore_delay")]
+    pub restore_delay_ms: u64,
+}
+
+fn default_restore() -> bool {
+    true
+}
+
+fn default_paste_delay() -> u64 {
+    50
+}
+
+fn default_restore_delay() -> u64 {
+    300
+}
+
+impl Default for ClipboardConfig {
+    fn default() -> Self {
+        Self {
+            restore: default_restore(),
+            paste_delay_ms: default_paste_delay(),
+            restore_delay_ms: default_restore_delay(),
+        }
+    }
+}
+
+// 剪贴板内容快照
+pub enum Snapshot {
+    Html { html: String, alt_text: Option<String> },
+    Text(String),
+    Image(Image<'static>),
+    Empty,
+}
+
+// 读取 HTML（剪贴板插件不支持读取富文本，直接使用 arboard）
+fn read_html() -> Option<String> {
+    arboard::Clipboard::new().ok()?.get().html().ok().filter(|html| !html.is_empty())
+}
+
+impl Snapshot {
+    pub fn take(app: &AppHandle) -> Self {
+        let text = app.clipboard().read_text().ok().filter(|t| !t.is_empty());
+        if let Some(html) = read_html() {
+            return Self::Html { html, alt_text: text };
+        }
+        if let Some(text) = text {
+            return Self::Text(text);
+        }
+        match app.clipboard().read_image() {
+            Ok(image) => Self::Image(image.to_owned()),
+            Err(_) => Self::Empty,
+        }
+    }
+
+    pub fn restore(&self, app: &AppHandle) -> Result<(), String> {
+        let clipboard = app.clipboard();
+        let result = match self {
+            Self::Html { html, alt_text } => clipboard.write_html(html.as_str(), alt_text.as_deref()),
+            Self::Text(text) => clipboard.write_text(text.as_str()),
+            Self::Image(image) => clipboard.write_image(image),
+            Self::Empty => clipboard.clear(),
+        };
+        result.map_err(|e| format!("恢复剪贴板失败: {}", e))
+    }
+}
+
+// 粘贴后在后台等待目标程序读取剪贴板，再恢复快照
+// 期间剪贴板被用户或其他程序修改（不再是插入的文本）时不恢复
+pub fn restore_later(app: &AppHandle, snapshot: Snapshot, inserted: String, delay: Duration) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        if app.clipboard().read_text().ok().as_deref() != Some(inserted.as_str()) {
+            return;
+        }
+        if let Err(e) = snapshot.restore(&app) {
+            eprintln!("{}", e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config_restores_clipboard() {
+        let config = ClipboardConfig::default();
+        assert_eq!(config, ClipboardConfig { restore: true, paste_delay_ms: 50, restore_delay_ms: 300 });
+        // 旧配置文件中没有 clipboard 字段
+        assert_eq!(serde_json::from_str::<ClipboardConfig>("{}").unwrap(), config);
+    }
+
+    #[test]
+    fn deserializes_partial_config() {
+        let cases = [
+            (r#"{"restore":false}"#, ClipboardConfig { restore: false, ..Default::default() }),
+            (r#"{"restore_delay_ms":1000}"#, ClipboardConfig { restore_delay_ms: 1000, ..Default::default() }),
+            (
+                r#"{"paste_delay_ms":0,"restore_delay_ms":20,"unknown":1}"#,
+                ClipboardConfig { paste_delay_ms: 0, restore_delay_ms: 20, ..Default::default() },
+            ),
+        ];
+        for (json, expected) in cases {
+            assert_eq!(serde_json::from_str::<ClipboardConfig>(json).unwrap(), expected, "{}", json);
+        }
+        assert!(serde_json::from_str::<ClipboardConfig>(r#"{"paste_delay_ms":-1}"#).is_err());
+    }
+}
@@ -0,0 +1,465 @@
+// 加密 - 由密码派生密钥（Argon2id），使用 XChaCha20-Poly1305 认证加密
+// 两种用途：同步时远程只保存密文（EncryptedBackend 包装任意同步后端）；
+// 标记为加密的文件夹中的提示词在磁盘上以密文保存，解锁后在读取时透明解密
+// 解锁后的密钥只保存在内存中（进程级），读写提示词的各处都通过本模块加解密
+use crate::sync::{RemoteFile, SyncBackend};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Mutex;
+
+// 二进制密文：MAGIC + 24 字节随机 nonce + 密文（含认证标签）
+const MAGIC: &[u8] = b"PMENC1";
+const NONCE_LEN: usize = 24;
+// 磁盘上的加密提示词：一行文本，前缀 + base64 编码的二进制密文（便于 Git 等按文本处理）
+const ARMOR_PREFIX: &str = "pm-encrypted:v1:";
+// 放在文件夹中表示其中（含子文件夹）的提示词加密保存
+pub const FOLDER_MARKER: &str = ".encrypted";
+// 用于验证密码是否正确的明文
+const CHECK_TEXT: &[u8] = b"prompt-manager";
+
+// 命令行工具和 MCP 服务器启动时，设置了该环境变量则自动解锁
+pub const PASSPHRASE_ENV: &str = "PROMPT_MANAGER_PASSPHRASE";
+
+const LOCKED: &str = "加密的提示词尚未解锁，请先在设置中输入密码";
+
+// 开启同步加密后读到未加密的远程文件（前端据此询问是否迁移）
+pub const PLAINTEXT_REMOTE: &str = "远程存在未加密的文件";
+
+static KEY: Mutex<Option<Key>> = Mutex::new(None);
+
+fn b64() -> base64::engine::GeneralPurpose {
+    base64::engine::general_purpose::STANDARD
+}
+
+// 密钥文件（保存在配置目录，只包含盐、参数和校验密文，不包含密钥）
+#[derive(Debug, Serialize, Deserialize)]
+struct KeyFile {
+    salt: String,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    check: String,
+}
+
+impl KeyFile {
+    fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("读取密钥文件失败: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("解析密钥文件失败: {}", e))
+    }
+
+    fn derive(&self, passphrase: &str) -> Result<Key, String> {
+        let salt = b64().decode(&self.salt).map_err(|e| format!("解析密钥文件失败: {}", e))?;
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(32))
+            .map_err(|e| format!("解析密钥文件失败: {}", e))?;
+        let mut key = Key::default();
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|e| format!("派生密钥失败: {}", e))?;
+        Ok(key)
+    }
+}
+
+// 加密状态
+#[derive(Debug, Serialize, Clone)]
+pub struct EncryptionStatus {
+    // 已设置密码
+    pub configured: bool,
+    pub unlocked: bool,
+}
+
+fn current_key() -> Option<Key> {
+    *KEY.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn set_key(key: Option<Key>) {
+    *KEY.lock().unwrap_or_else(|e| e.into_inner()) = key;
+}
+
+pub fn status(key_path: &Path) -> EncryptionStatus {
+    EncryptionStatus { configured: key_path.exists(), unlocked: current_key().is_some() }
+}
+
+// 首次设置密码并解锁
+pub fn setup(key_path: &Path, passphrase: &str) -> Result<(), String> {
+    if key_path.exists() {
+        return Err("已设置过加密密码".to_string());
+    }
+    if passphrase.chars().count() < 8 {
+        return Err("密码至少需要 8 个字符".to_string());
+    }
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let params = Params::default();
+    let mut file = KeyFile {
+        salt: b64().encode(salt),
+        m_cost: params.m_cost(),
+        t_cost: params.t_cost(),
+        p_cost: params.p_cost(),
+        check: String::new(),
+    };
+    let key = file.derive(passphrase)?;
+    file.check = b64().encode(seal_with(&key, CHECK_TEXT)?);
+
+    let content = serde_json::to_string_pretty(&file)
+        .map_err(|e| format!("序列化密钥文件失败: {}", e))?;
+    fs::write(key_path, content).map_err(|e| format!("保存密钥文件失败: {}", e))?;
+    set_key(Some(key));
+    Ok(())
+}
+
+// 输入密码解锁
+pub fn unlock(key_path: &Path, passphrase: &str) -> Result<(), String> {
+    if !key_path.exists() {
+        return Err("尚未设置加密密码".to_string());
+    }
+    let file = KeyFile::load(key_path)?;
+    let key = file.derive(passphrase)?;
+    let check = b64().decode(&file.check).map_err(|e| format!("解析密钥文件失败: {}", e))?;
+    match open_with(&key, &check) {
+        Ok(text) if text == CHECK_TEXT => {
+            set_key(Some(key));
+            Ok(())
+        }
+        _ => Err("密码错误".to_string()),
+    }
+}
+
+// 使用环境变量中的密码解锁（未设置环境变量或密码时跳过）
+pub fn unlock_from_env(key_path: &Path) -> Result<(), String> {
+    match std::env::var(PASSPHRASE_ENV) {
+        Ok(passphrase) if key_path.exists() => unlock(key_path, &passphrase),
+        _ => Ok(()),
+    }
+}
+
+// 锁定：清除内存中的密钥
+pub fn lock() {
+    set_key(None);
+}
+
+fn seal_with(key: &Key, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = XChaCha20Poly1305::new(key)
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| "加密失败".to_string())?;
+    Ok([MAGIC, nonce.as_slice(), &ciphertext].concat())
+}
+
+fn open_with(key: &Key, bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let rest = bytes.strip_prefix(MAGIC).filter(|r| r.len() >= NONCE_LEN).ok_or("不是有效的密文")?;
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    XChaCha20Poly1305::new(key)
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "解密失败，密码不正确或文件已损坏".to_string())
+}
+
+// 是否为二进制密文
+pub fn is_sealed(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+// 使用当前密钥加密 / 解密（未解锁时报错）
+pub fn seal(plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    seal_with(&current_key().ok_or(LOCKED)?, plaintext)
+}
+
+pub fn open(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    open_with(&current_key().ok_or(LOCKED)?, bytes)
+}
+
+// ====== 提示词文件加密 ======
+
+// 文件内容是否为加密的提示词
+pub fn is_armored(raw: &str) -> bool {
+    raw.starts_with(ARMOR_PREFIX)
+}
+
+pub fn encrypt_text(plaintext: &str) -> Result<String, String> {
+    Ok(format!("{}{}\n", ARMOR_PREFIX, b64().encode(seal(plaintext.as_bytes())?)))
+}
+
+// 解密提示词文件内容（未加密时原样返回）
+pub fn decrypt_text(raw: &str) -> Result<String, String> {
+    let Some(encoded) = raw.strip_prefix(ARMOR_PREFIX) else {
+        return Ok(raw.to_string());
+    };
+    let bytes = b64().decode(encoded.trim()).map_err(|_| "加密的提示词已损坏".to_string())?;
+    String::from_utf8(open(&bytes)?).map_err(|_| "加密的提示词已损坏".to_string())
+}
+
+// 读取提示词文件并解密，返回 (内容, 是否加密)
+pub fn read_text(path: &Path) -> Result<(String, bool), String> {
+    let raw = fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}", e))?;
+    let encrypted = is_armored(&raw);
+    Ok((decrypt_text(&raw)?, encrypted))
+}
+
+// 文件在磁盘上是否为密文（只读取开头）
+pub fn is_encrypted_file(path: &Path) -> bool {
+    let mut head = [0u8; ARMOR_PREFIX.len()];
+    fs::File::open(path)
+        .and_then(|mut f| f.read_exact(&mut head))
+        .is_ok_and(|_| head == ARMOR_PREFIX.as_bytes())
+}
+
+// 路径是否位于标记为加密的文件夹中
+pub fn in_encrypted_folder(path: &Path) -> bool {
+    path.ancestors().skip(1).any(|dir| dir.join(FOLDER_MARKER).is_file())
+}
+
+// 写入时是否需要加密：位于加密文件夹中，或文件原本就是密文（如从其他设备同步而来）
+pub fn should_encrypt(path: &Path) -> bool {
+    in_encrypted_folder(path) || is_encrypted_file(path)
+}
+
+// ====== 同步加密 ======
+
+// 包装同步后端：上传前加密、下载后解密，远程只能看到密文（文件名和文件夹结构不加密）
+// 未加密的远程文件可能是服务器写入的，默认拒绝；用户确认后才作为一次性迁移接受并重新加密上传
+pub struct EncryptedBackend {
+    inner: Box<dyn SyncBackend>,
+    // 接受未加密的远程文件（开启同步加密之前上传的）
+    migrate_plaintext: bool,
+    // 读取时发现的未加密远程文件，需要重新上传
+    plaintext: RefCell<HashSet<String>>,
+}
+
+impl EncryptedBackend {
+    pub fn new(inner: Box<dyn SyncBackend>, migrate_plaintext: bool) -> Result<Self, String> {
+        if current_key().is_none() {
+            return Err(LOCKED.to_string());
+        }
+        Ok(Self { inner, migrate_plaintext, plaintext: RefCell::new(HashSet::new()) })
+    }
+}
+
+impl SyncBackend for EncryptedBackend {
+    // 与未加密时区分同步记录，开启或关闭加密后重新比较两端
+    fn identity(&self) -> String {
+        format!("encrypted+{}", self.inner.identity())
+    }
+
+    // 远程只有密文的哈希，无法与本机内容比较
+    fn list(&mut self) -> Result<BTreeMap<String, RemoteFile>, String> {
+        Ok(self.inner.list()?
+            .into_iter()
+            .map(|(rel, file)| (rel, RemoteFile { token: file.token, hash: None }))
+            .collect())
+    }
+
+    fn read(&self, rel: &str) -> Result<Vec<u8>, String> {
+        let bytes = self.inner.read(rel)?;
+        if !is_sealed(&bytes) {
+            if !self.migrate_plaintext {
+                return Err(format!("{}: {}", PLAINTEXT_REMOTE, rel));
+            }
+            self.plaintext.borrow_mut().insert(rel.to_string());
+            return Ok(bytes);
+        }
+        open(&bytes).map_err(|e| format!("{}: {}", rel, e))
+    }
+
+    fn write(
+        &mut self,
+        uploads: &[(String, Vec<u8>)],
+        deletes: &[String],
+    ) -> Result<BTreeMap<String, String>, String> {
+        let sealed = uploads.iter()
+            .map(|(rel, bytes)| Ok((rel.clone(), seal(bytes)?)))
+            .collect::<Result<Vec<_>, String>>()?;
+        self.inner.write(&sealed, deletes)
+    }
+
+    fn needs_reupload(&self, rel: &str) -> bool {
+        self.plaintext.borrow().contains(rel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 密钥为进程级状态，用到当前密钥的测试依次执行
+    static GLOBAL: Mutex<()> = Mutex::new(());
+
+    fn with_key<T>(key: Option<Key>, f: impl FnOnce() -> T) -> T {
+        let _guard = GLOBAL.lock().unwrap_or_else(|e| e.into_inner());
+        set_key(key);
+        let result = f();
+        set_key(None);
+        result
+    }
+
+    fn test_key(byte: u8) -> Key {
+        Key::from([byte; 32])
+    }
+
+    #[test]
+    fn seal_round_trip() {
+        let key = test_key(1);
+        for plaintext in [&b""[..], b"hello", "提示词内容\n".as_bytes(), &[0u8; 4096]] {
+            let sealed = seal_with(&key, plaintext).unwrap();
+            assert!(is_sealed(&sealed));
+            assert_eq!(open_with(&key, &sealed).unwrap(), plaintext);
+        }
+        // 每次加密使用不同的 nonce
+        assert_ne!(seal_with(&key, b"same").unwrap(), seal_with(&key, b"same").unwrap());
+    }
+
+    #[test]
+    fn open_rejects_wrong_key() {
+        let sealed = seal_with(&test_key(1), b"secret").unwrap();
+        assert!(open_with(&test_key(2), &sealed).is_err());
+    }
+
+    #[test]
+    fn open_rejects_tampered_ciphertext() {
+        let key = test_key(1);
+        let sealed = seal_with(&key, b"secret prompt").unwrap();
+        // 依次修改 nonce、密文和认证标签中的一个字节
+        for i in [MAGIC.len(), MAGIC.len() + NONCE_LEN, sealed.len() - 1] {
+            let mut tampered = sealed.clone();
+            tampered[i] ^= 0x01;
+            assert!(open_with(&key, &tampered).is_err(), "修改第 {} 字节", i);
+        }
+        assert!(open_with(&key, &sealed[..sealed.len() - 1]).is_err());
+        assert!(open_with(&key, &sealed[..MAGIC.len() + NONCE_LEN - 1]).is_err());
+        assert!(open_with(&key, &sealed[MAGIC.len()..]).is_err());
+        assert!(open_with(&key, b"").is_err());
+    }
+
+    #[test]
+    fn text_round_trip() {
+        with_key(Some(test_key(3)), || {
+            let plaintext = "---\nid: abc\n---\n正文 {{selection}}\n";
+            let armored = encrypt_text(plaintext).unwrap();
+            assert!(is_armored(&armored));
+            assert!(!armored.contains("正文"));
+            assert_eq!(decrypt_text(&armored).unwrap(), plaintext);
+        });
+    }
+
+    #[test]
+    fn decrypt_text_passes_plain_text_through() {
+        // 未解锁时明文也原样返回
+        with_key(None, || {
+            for plain in ["", "普通提示词", "---\ntitle: x\n---\nbody", " pm-encrypted:v1:abc", "PMENC1"] {
+                assert_eq!(decrypt_text(plain).unwrap(), plain);
+            }
+        });
+    }
+
+    #[test]
+    fn decrypt_text_rejects_tampered_or_locked() {
+        let armored = with_key(Some(test_key(3)), || encrypt_text("secret").unwrap());
+        with_key(Some(test_key(3)), || {
+            let encoded = armored.trim_end().strip_prefix(ARMOR_PREFIX).unwrap();
+            let mut bytes = b64().decode(encoded).unwrap();
+            let last = bytes.len() - 1;
+            bytes[last] ^= 0x80;
+            let tampered = format!("{}{}\n", ARMOR_PREFIX, b64().encode(bytes));
+            assert!(decrypt_text(&tampered).is_err());
+            assert!(decrypt_text(&format!("{}不是 base64", ARMOR_PREFIX)).is_err());
+        });
+        with_key(Some(test_key(4)), || assert!(decrypt_text(&armored).is_err()));
+        with_key(None, || assert_eq!(decrypt_text(&armored), Err(LOCKED.to_string())));
+    }
+
+    // 内存中的远程
+    #[derive(Default)]
+    struct MemoryBackend {
+        files: BTreeMap<String, Vec<u8>>,
+    }
+
+    impl SyncBackend for MemoryBackend {
+        fn identity(&self) -> String {
+            "memory".to_string()
+        }
+
+        fn list(&mut self) -> Result<BTreeMap<String, RemoteFile>, String> {
+            Ok(self.files.keys()
+                .map(|rel| (rel.clone(), RemoteFile { token: rel.clone(), hash: None }))
+                .collect())
+        }
+
+        fn read(&self, rel: &str) -> Result<Vec<u8>, String> {
+            self.files.get(rel).cloned().ok_or_else(|| "文件不存在".to_string())
+        }
+
+        fn write(
+            &mut self,
+            uploads: &[(String, Vec<u8>)],
+            deletes: &[String],
+        ) -> Result<BTreeMap<String, String>, String> {
+            for rel in deletes {
+                self.files.remove(rel);
+            }
+            for (rel, bytes) in uploads {
+                self.files.insert(rel.clone(), bytes.clone());
+            }
+            Ok(uploads.iter().map(|(rel, _)| (rel.clone(), rel.clone())).collect())
+        }
+    }
+
+    #[test]
+    fn encrypted_backend_rejects_plaintext_unless_migrating() {
+        with_key(Some(test_key(5)), || {
+            let sealed = seal(b"sealed").unwrap();
+            let remote = || MemoryBackend {
+                files: BTreeMap::from([
+                    ("plain.md".to_string(), b"injected".to_vec()),
+                    ("sealed.md".to_string(), sealed.clone()),
+                ]),
+            };
+
+            let backend = EncryptedBackend::new(Box::new(remote()), false).unwrap();
+            assert_eq!(backend.read("sealed.md").unwrap(), b"sealed");
+            assert_eq!(backend.read("plain.md"), Err(format!("{}: plain.md", PLAINTEXT_REMOTE)));
+            assert!(!backend.needs_reupload("plain.md"));
+
+            let mut backend = EncryptedBackend::new(Box::new(remote()), true).unwrap();
+            assert_eq!(backend.read("plain.md").unwrap(), b"injected");
+            assert!(backend.needs_reupload("plain.md"));
+            assert!(!backend.needs_reupload("sealed.md"));
+
+            // 重新上传后远程为密文
+            backend.write(&[("plain.md".to_string(), b"injected".to_vec())], &[]).unwrap();
+            let backend = EncryptedBackend { migrate_plaintext: false, ..backend };
+            assert_eq!(backend.read("plain.md").unwrap(), b"injected");
+        });
+        with_key(None, || assert!(EncryptedBackend::new(Box::<MemoryBackend>::default(), true).is_err()));
+    }
+
+    #[test]
+    fn passphrase_setup_and_unlock() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_path = dir.path().join("key.json");
+        with_key(None, || {
+            assert!(setup(&key_path, "short").is_err());
+            assert!(!key_path.exists());
+            assert!(unlock(&key_path, "correct horse").is_err());
+
+            setup(&key_path, "correct horse").unwrap();
+            assert!(status(&key_path).unlocked);
+            let armored = encrypt_text("secret").unwrap();
+            assert!(setup(&key_path, "another passphrase").is_err());
+
+            lock();
+            assert!(!status(&key_path).unlocked);
+            assert_eq!(unlock(&key_path, "wrong horse"), Err("密码错误".to_string()));
+            assert!(!status(&key_path).unlocked);
+
+            unlock(&key_path, "correct horse").unwrap();
+            assert_eq!(decrypt_text(&armored).unwrap(), "secret");
+        });
+    }
+}
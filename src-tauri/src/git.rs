@@ -0,0 +1,640 @@
+// Git 模式 - 提示词库位于 git 仓库中时，保存 / 删除 / 重命名后自动提交，并提供按提示词的提交记录、blame 和拉取 / 推送
+// 只支持本地路径或 file:// 远程仓库（不依赖 ssh / https）
+// 提示词库可以是仓库的子文件夹，此时只提交该文件夹内的变化
+use chrono::{DateTime, Local};
+use git2::build::CheckoutBuilder;
+use git2::{
+    BlameOptions, Commit, DiffFindOptions, IndexAddOption, Oid, PushOptions, RemoteCallbacks,
+    Repository, Signature, Tree,
+};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// 仓库未配置 user.name / user.email 时使用的提交者
+const DEFAULT_AUTHOR: (&str, &str) = ("Prompt Manager", "prompt-manager@localhost");
+
+// 该时间（秒）内对同一提示词的连续保存合并为一个提交（与版本历史相同），避免自动保存产生大量提交
+const AMEND_SECS: i64 = 60;
+// 最近一次拉取或推送时与远程一致的提交，它及之前的提交已公开，不再修订
+const SYNCED_REF: &str = "refs/prompt-manager/synced";
+
+// Git 模式配置
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct GitConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    // 远程仓库（本地路径或 file:// 地址），为空时使用仓库的 origin
+    #[serde(default)]
+    pub remote: String,
+}
+
+// 提交记录
+#[derive(Debug, Serialize, Clone)]
+pub struct CommitInfo {
+    pub id: String,
+    pub short_id: String,
+    pub summary: String,
+    pub author: String,
+    pub time: String,
+    // 该提交中提示词的路径（相对提示词库根目录，重命名前的提交为旧路径）
+    pub rel_path: String,
+}
+
+// blame 的一行
+#[derive(Debug, Serialize, Clone)]
+pub struct BlameLine {
+    pub line: usize,
+    pub text: String,
+    pub short_id: String,
+    pub author: String,
+    pub time: String,
+}
+
+// 拉取 / 推送结果
+#[derive(Debug, Serialize, Clone)]
+pub struct GitSyncResult {
+    pub changed: bool,
+    pub message: String,
+}
+
+// 打开的仓库，prefix 为提示词库相对工作区根目录的路径（为空表示就是工作区根目录）
+struct LibraryRepo {
+    repo: Repository,
+    prefix: String,
+}
+
+fn git_err(action: &str, e: git2::Error) -> String {
+    format!("{}失败: {}", action, e.message())
+}
+
+fn format_time(time: git2::Time) -> String {
+    DateTime::from_timestamp(time.seconds(), 0)
+        .map(|t| t.with_timezone(&Local).to_rfc3339_opts(chrono::SecondsFormat::Secs, false))
+        .unwrap_or_default()
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+// 相对 base 的路径（统一使用 / 分隔）
+fn relative(base: &Path, path: &Path) -> Option<String> {
+    let rest = path.strip_prefix(base).ok()?;
+    let parts: Vec<_> = rest.components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    Some(parts.join("/"))
+}
+
+fn join(prefix: &str, rel: &str) -> String {
+    match (prefix.is_empty(), rel.is_empty()) {
+        (true, _) => rel.to_string(),
+        (false, true) => prefix.to_string(),
+        (false, false) => format!("{}/{}", prefix, rel),
+    }
+}
+
+impl LibraryRepo {
+    // 打开提示词库所在的仓库；create 为 true 且不在仓库中时在提示词库根目录初始化新仓库
+    fn open(root: &Path, create: bool) -> Result<Self, String> {
+        let repo = match Repository::discover(root) {
+            Ok(repo) => repo,
+            Err(_) if create => Repository::init(root).map_err(|e| git_err("初始化仓库", e))?,
+            Err(_) => return Err("提示词库不在 git 仓库中".to_string()),
+        };
+        let workdir = repo.workdir().ok_or("不支持裸仓库")?;
+        let prefix = relative(&canonical(workdir), &canonical(root))
+            .ok_or("提示词库不在仓库工作区中")?;
+
+        let library = Self { repo, prefix };
+        library.exclude_trash();
+        Ok(library)
+    }
+
+    // 回收站不纳入版本管理（写入 .git/info/exclude，不修改仓库中的 .gitignore）
+    fn exclude_trash(&self) {
+        let pattern = format!("/{}", join(&self.prefix, &format!("{}/", crate::trash::TRASH_DIR)));
+        let path = self.repo.path().join("info").join("exclude");
+        // 文件存在但无法读取（如非 UTF-8 编码）时不改写，避免覆盖用户的规则
+        let mut content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                eprintln!("读取 {} 失败，未排除回收站: {}", path.display(), e);
+                return;
+            }
+        };
+        if content.lines().any(|l| l.trim() == pattern) {
+            return;
+        }
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(&pattern);
+        content.push('\n');
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let _ = fs::write(&path, content);
+    }
+
+    // 绝对路径转换为仓库内路径
+    fn repo_path(&self, root: &Path, path: &Path) -> Option<String> {
+        let rel = crate::library::to_relative(root, path)?;
+        Some(join(&self.prefix, &rel))
+    }
+
+    fn signature(&self) -> Result<Signature<'static>, String> {
+        self.repo.signature()
+            .or_else(|_| Signature::now(DEFAULT_AUTHOR.0, DEFAULT_AUTHOR.1))
+            .map_err(|e| git_err("创建提交者", e))
+    }
+
+    fn head_commit(&self) -> Option<Commit<'_>> {
+        self.repo.head().ok()?.peel_to_commit().ok()
+    }
+
+    // 提交是否已推送或来自远程（远程跟踪分支或同步记录包含该提交）
+    fn is_published(&self, oid: Oid) -> bool {
+        let Ok(references) = self.repo.references() else { return true };
+        references.flatten()
+            .filter(|r| r.name().is_some_and(|n| n.starts_with("refs/remotes/") || n == SYNCED_REF))
+            .filter_map(|r| r.target())
+            .any(|target| target == oid || self.repo.graph_descendant_of(target, oid).unwrap_or(true))
+    }
+
+    // 能否修订上一个提交：提交说明相同（同一操作和路径）、第一次保存在 AMEND_SECS 内，且尚未公开
+    fn can_amend(&self, commit: &Commit, message: &str) -> bool {
+        let age = Local::now().timestamp() - commit.author().when().seconds();
+        commit.parent_count() <= 1
+            && commit.message() == Some(message)
+            && (0..AMEND_SECS).contains(&age)
+            && !self.is_published(commit.id())
+    }
+
+    fn mark_synced(&self, oid: Oid) -> Result<(), String> {
+        self.repo.reference(SYNCED_REF, oid, true, "prompt-manager: synced")
+            .map(|_| ())
+            .map_err(|e| git_err("记录同步位置", e))
+    }
+
+    // 暂存指定路径（包括删除）并提交，没有变化时返回 None
+    // coalesce 为 true 且上一个提交可以修订时修订上一个提交（保留第一次保存的作者时间）
+    fn commit(&self, pathspecs: &[String], message: &str, coalesce: bool) -> Result<Option<Oid>, String> {
+        let mut index = self.repo.index().map_err(|e| git_err("读取暂存区", e))?;
+        let specs: Vec<&str> = pathspecs.iter()
+            .map(|s| if s.is_empty() { "*" } else { s.as_str() })
+            .collect();
+        index.add_all(&specs, IndexAddOption::DEFAULT, None)
+            .and_then(|_| index.update_all(&specs, None))
+            .and_then(|_| index.write())
+            .map_err(|e| git_err("暂存修改", e))?;
+
+        let tree_id = index.write_tree().map_err(|e| git_err("写入目录树", e))?;
+        let parent = self.head_commit();
+        // 新仓库中没有文件时不创建空提交，以便之后直接拉取远程分支
+        if parent.as_ref().map_or(index.is_empty(), |p| p.tree_id() == tree_id) {
+            return Ok(None);
+        }
+        let tree = self.repo.find_tree(tree_id).map_err(|e| git_err("写入目录树", e))?;
+        let sig = self.signature()?;
+        if let Some(parent) = parent.as_ref().filter(|p| coalesce && self.can_amend(p, message)) {
+            return parent.amend(Some("HEAD"), None, Some(&sig), None, None, Some(&tree))
+                .map(Some)
+                .map_err(|e| git_err("提交", e));
+        }
+        let parents: Vec<&Commit> = parent.iter().collect();
+        self.repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+            .map(Some)
+            .map_err(|e| git_err("提交", e))
+    }
+
+    // 提交提示词库中所有未提交的修改
+    fn commit_all(&self, message: &str) -> Result<Option<Oid>, String> {
+        self.commit(std::slice::from_ref(&self.prefix), message, false)
+    }
+
+    // 当前分支名（新仓库尚无提交时从 HEAD 的符号引用读取）
+    fn branch(&self) -> Result<String, String> {
+        let head = self.repo.find_reference("HEAD").map_err(|e| git_err("读取 HEAD", e))?;
+        head.symbolic_target()
+            .and_then(|target| target.strip_prefix("refs/heads/"))
+            .map(|b| b.to_string())
+            .ok_or_else(|| "HEAD 未指向分支，请先切换到分支".to_string())
+    }
+
+    fn remote(&self, url: &str) -> Result<git2::Remote<'_>, String> {
+        if url.is_empty() {
+            return self.repo.find_remote("origin")
+                .map_err(|_| "请先配置远程仓库地址".to_string());
+        }
+        let remotes = self.repo.remotes().map_err(|e| git_err("读取远程仓库", e))?;
+        for name in remotes.iter().flatten() {
+            if let Ok(remote) = self.repo.find_remote(name) {
+                if remote.url() == Some(url) {
+                    return Ok(remote);
+                }
+            }
+        }
+        self.repo.remote_anonymous(url).map_err(|e| git_err("连接远程仓库", e))
+    }
+}
+
+fn path_id(tree: &Tree, path: &str) -> Option<Oid> {
+    tree.get_path(Path::new(path)).ok().map(|e| e.id())
+}
+
+// 提交中该路径在父提交里的旧路径（文件重命名），没有重命名时返回 None
+fn renamed_from(repo: &Repository, parent: &Tree, tree: &Tree, path: &str) -> Option<String> {
+    let mut diff = repo.diff_tree_to_tree(Some(parent), Some(tree), None).ok()?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true))).ok()?;
+    diff.deltas()
+        .filter(|d| d.status() == git2::Delta::Renamed)
+        .find(|d| d.new_file().path().is_some_and(|p| p == Path::new(path)))
+        .and_then(|d| d.old_file().path().map(|p| p.to_string_lossy().replace('\\', "/")))
+}
+
+// 提交提示词库中指定文件或文件夹的变化（不在仓库中时初始化仓库）
+// coalesce 为 true 时，短时间内提交说明相同的连续提交合并为一个（用于保存提示词内容）
+pub fn commit(root: &Path, paths: &[&Path], message: &str, coalesce: bool) -> Result<(), String> {
+    let library = LibraryRepo::open(root, true)?;
+    let specs: Vec<String> = paths.iter()
+        .filter_map(|p| library.repo_path(root, p))
+        .collect();
+    library.commit(&specs, message, coalesce)?;
+    Ok(())
+}
+
+// 提示词的提交记录（最新的在前），跟随文件重命名
+pub fn log(root: &Path, path: &Path, limit: usize) -> Result<Vec<CommitInfo>, String> {
+    let library = LibraryRepo::open(root, false)?;
+    let repo = &library.repo;
+    let Some(mut current) = library.repo_path(root, path) else {
+        return Ok(Vec::new());
+    };
+    if library.head_commit().is_none() {
+        return Ok(Vec::new());
+    }
+
+    let mut walk = repo.revwalk().map_err(|e| git_err("读取提交记录", e))?;
+    walk.push_head().map_err(|e| git_err("读取提交记录", e))?;
+    walk.set_sorting(git2::Sort::TIME | git2::Sort::TOPOLOGICAL)
+        .map_err(|e| git_err("读取提交记录", e))?;
+
+    let mut commits = Vec::new();
+    for oid in walk.flatten() {
+        if commits.len() >= limit {
+            break;
+        }
+        let Ok(commit) = repo.find_commit(oid) else { continue };
+        let Ok(tree) = commit.tree() else { continue };
+        let Some(id) = path_id(&tree, &current) else { continue };
+        let parent_tree = commit.parent(0).ok().and_then(|p| p.tree().ok());
+        if parent_tree.as_ref().and_then(|t| path_id(t, &current)) == Some(id) {
+            continue;
+        }
+
+        let author = commit.author();
+        commits.push(CommitInfo {
+            id: oid.to_string(),
+            short_id: oid.to_string().chars().take(7).collect(),
+            summary: commit.summary().unwrap_or_default().to_string(),
+            author: author.name().unwrap_or_default().to_string(),
+            time: format_time(commit.time()),
+            rel_path: current.strip_prefix(&library.prefix)
+                .map(|p| p.trim_start_matches('/').to_string())
+                .unwrap_or_else(|| current.clone()),
+        });
+
+        // 文件在该提交中由其他路径重命名而来，更早的记录沿旧路径查找
+        if let Some(parent_tree) = parent_tree {
+            if path_id(&parent_tree, &current).is_none() {
+                match renamed_from(repo, &parent_tree, &tree, &current) {
+                    Some(old) => current = old,
+                    None => break,
+                }
+            }
+        }
+    }
+    Ok(commits)
+}
+
+// 最近一次提交中提示词文件每一行的来源
+pub fn blame(root: &Path, path: &Path) -> Result<Vec<BlameLine>, String> {
+    let library = LibraryRepo::open(root, false)?;
+    let repo = &library.repo;
+    let repo_path = library.repo_path(root, path).ok_or("提示词不在提示词库中")?;
+    let head = library.head_commit().ok_or("仓库中还没有提交")?;
+
+    let blob = head.tree()
+        .and_then(|tree| tree.get_path(Path::new(&repo_path)))
+        .and_then(|entry| entry.to_object(repo))
+        .and_then(|obj| obj.peel_to_blob())
+        .map_err(|_| "该提示词尚未提交".to_string())?;
+    let blame = repo.blame_file(Path::new(&repo_path), Some(&mut BlameOptions::new()))
+        .map_err(|e| git_err("读取 blame", e))?;
+
+    let content = String::from_utf8_lossy(blob.content());
+    Ok(content.lines()
+        .enumerate()
+        .map(|(i, text)| {
+            let hunk = blame.get_line(i + 1);
+            let (short_id, author, time) = hunk
+                .map(|h| {
+                    let sig = h.final_signature();
+                    (
+                        h.final_commit_id().to_string().chars().take(7).collect(),
+                        sig.name().unwrap_or_default().to_string(),
+                        format_time(sig.when()),
+                    )
+                })
+                .unwrap_or_default();
+            BlameLine { line: i + 1, text: text.to_string(), short_id, author, time }
+        })
+        .collect())
+}
+
+// 从远程仓库拉取当前分支：先提交本地修改，能快进时快进，否则在没有冲突时自动合并
+pub fn pull(root: &Path, url: &str) -> Result<GitSyncResult, String> {
+    let library = LibraryRepo::open(root, true)?;
+    let repo = &library.repo;
+    library.commit_all("同步前提交本地修改")?;
+    let branch = library.branch()?;
+
+    let mut remote = library.remote(url)?;
+    remote.fetch(&[&format!("refs/heads/{}", branch)], None, None)
+        .map_err(|e| git_err("拉取", e))?;
+    let fetch_head = repo.find_reference("FETCH_HEAD").map_err(|e| git_err("拉取", e))?;
+    let theirs = repo.reference_to_annotated_commit(&fetch_head).map_err(|e| git_err("拉取", e))?;
+    let their_commit = repo.find_commit(theirs.id()).map_err(|e| git_err("拉取", e))?;
+    library.mark_synced(theirs.id())?;
+
+    let (analysis, _) = repo.merge_analysis(&[&theirs]).map_err(|e| git_err("分析合并", e))?;
+    if analysis.is_up_to_date() {
+        return Ok(GitSyncResult { changed: false, message: "已是最新".to_string() });
+    }
+
+    let ref_name = format!("refs/heads/{}", branch);
+    if analysis.is_unborn() || analysis.is_fast_forward() {
+        // 先检出再移动分支，检出失败（工作区有冲突的修改）时分支保持不变
+        repo.checkout_tree(their_commit.as_object(), Some(CheckoutBuilder::new().safe()))
+            .map_err(|e| git_err("检出", e))?;
+        match repo.find_reference(&ref_name) {
+            Ok(mut reference) => reference.set_target(theirs.id(), "pull: fast-forward").map(|_| ()),
+            Err(_) => repo.reference(&ref_name, theirs.id(), true, "pull: fast-forward").map(|_| ()),
+        }
+        .and_then(|_| repo.set_head(&ref_name))
+        .map_err(|e| git_err("更新分支", e))?;
+        return Ok(GitSyncResult { changed: true, message: "已快进到远程最新版本".to_string() });
+    }
+
+    let ours = library.head_commit().ok_or("读取本地提交失败")?;
+    let mut index = repo.merge_commits(&ours, &their_commit, None)
+        .map_err(|e| git_err("合并", e))?;
+    if index.has_conflicts() {
+        let paths: Vec<String> = index.conflicts()
+            .map(|conflicts| conflicts.flatten()
+                .filter_map(|c| c.our.or(c.their))
+                .map(|e| String::from_utf8_lossy(&e.path).to_string())
+                .collect())
+            .unwrap_or_default();
+        return Err(format!("合并冲突，请在仓库中手动解决: {}", paths.join(", ")));
+    }
+
+    let tree_id = index.write_tree_to(repo).map_err(|e| git_err("合并", e))?;
+    let tree = repo.find_tree(tree_id).map_err(|e| git_err("合并", e))?;
+    repo.checkout_tree(tree.as_object(), Some(CheckoutBuilder::new().safe()))
+        .map_err(|e| git_err("检出", e))?;
+    let sig = library.signature()?;
+    let message = format!("合并远程分支 {}", branch);
+    repo.commit(Some("HEAD"), &sig, &sig, &message, &tree, &[&ours, &their_commit])
+        .map_err(|e| git_err("提交", e))?;
+
+    Ok(GitSyncResult { changed: true, message: "已拉取并合并远程修改".to_string() })
+}
+
+// 推送当前分支到远程仓库（先提交本地修改；远程有新提交时需要先拉取）
+pub fn push(root: &Path, url: &str) -> Result<GitSyncResult, String> {
+    let library = LibraryRepo::open(root, true)?;
+    library.commit_all("同步前提交本地修改")?;
+    if library.head_commit().is_none() {
+        return Err("仓库中还没有提交".to_string());
+    }
+    let branch = library.branch()?;
+
+    let mut rejected = None;
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.push_update_reference(|_, status| {
+        if let Some(status) = status {
+            rejected = Some(status.to_string());
+        }
+        Ok(())
+    });
+    let mut options = PushOptions::new();
+    options.remote_callbacks(callbacks);
+
+    let refspec = format!("refs/heads/{0}:refs/heads/{0}", branch);
+    let mut remote = library.remote(url)?;
+    remote.push(&[&refspec], Some(&mut options)).map_err(|e| {
+        if e.code() == git2::ErrorCode::NotFastForward {
+            "远程仓库有新的提交，请先拉取".to_string()
+        } else {
+            git_err("推送", e)
+        }
+    })?;
+    drop(options);
+
+    if let Some(status) = rejected {
+        return Err(format!("推送被拒绝: {}", status));
+    }
+    if let Some(head) = library.head_commit() {
+        library.mark_synced(head.id())?;
+    }
+    Ok(GitSyncResult { changed: true, message: format!("已推送分支 {}", branch) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Time;
+
+    fn write(root: &Path, name: &str, content: &str) {
+        fs::write(root.join(name), content).unwrap();
+    }
+
+    fn summaries(root: &Path, name: &str) -> Vec<String> {
+        log(root, &root.join(name), 50).unwrap().into_iter().map(|c| c.summary).collect()
+    }
+
+    fn commit_count(root: &Path) -> usize {
+        let repo = Repository::open(root).unwrap();
+        let mut walk = repo.revwalk().unwrap();
+        walk.push_head().unwrap();
+        walk.count()
+    }
+
+    // 两个克隆：library 目录 a 和 b，以及 file:// 地址的裸仓库
+    fn setup() -> (tempfile::TempDir, PathBuf, PathBuf, String) {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (dir.path().join("a"), dir.path().join("b"));
+        fs::create_dir_all(&a).unwrap();
+        fs::create_dir_all(&b).unwrap();
+        let bare = dir.path().join("remote.git");
+        Repository::init_bare(&bare).unwrap();
+        let url = format!("file://{}", bare.display());
+        (dir, a, b, url)
+    }
+
+    #[test]
+    fn commits_changes_and_follows_renames() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, "a.md", "one\n");
+        commit(root, &[&root.join("a.md")], "新建提示词: a.md", false).unwrap();
+        write(root, "a.md", "one\ntwo\n");
+        commit(root, &[&root.join("a.md")], "更新提示词: a.md", false).unwrap();
+        // 没有变化时不提交
+        commit(root, &[&root.join("a.md")], "更新提示词: a.md", false).unwrap();
+        assert_eq!(commit_count(root), 2);
+
+        let lines = blame(root, &root.join("a.md")).unwrap();
+        let history = log(root, &root.join("a.md"), 50).unwrap();
+        assert_eq!(lines.iter().map(|l| l.text.as_str()).collect::<Vec<_>>(), ["one", "two"]);
+        assert_eq!(lines[0].short_id, history[1].short_id);
+        assert_eq!(lines[1].short_id, history[0].short_id);
+
+        fs::rename(root.join("a.md"), root.join("b.md")).unwrap();
+        commit(root, &[&root.join("a.md"), &root.join("b.md")], "重命名提示词: a.md -> b.md", false).unwrap();
+        let history = log(root, &root.join("b.md"), 50).unwrap();
+        let paths: Vec<_> = history.iter().map(|c| c.rel_path.as_str()).collect();
+        assert_eq!(paths, ["b.md", "a.md", "a.md"]);
+        assert_eq!(summaries(root, "b.md"), ["重命名提示词: a.md -> b.md", "更新提示词: a.md", "新建提示词: a.md"]);
+        assert_eq!(log(root, &root.join("b.md"), 2).unwrap().len(), 2);
+
+        // 删除也会提交
+        fs::remove_file(root.join("b.md")).unwrap();
+        commit(root, &[&root.join("b.md")], "删除提示词: b.md", false).unwrap();
+        assert_eq!(commit_count(root), 4);
+    }
+
+    #[test]
+    fn only_commits_given_paths_inside_library() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("prompts");
+        fs::create_dir_all(&root).unwrap();
+        Repository::init(dir.path()).unwrap();
+        fs::write(dir.path().join("outside.txt"), "x").unwrap();
+        write(&root, "a.md", "a");
+        write(&root, "b.md", "b");
+        commit(&root, &[&root.join("a.md")], "新建提示词: a.md", false).unwrap();
+
+        let repo = Repository::open(dir.path()).unwrap();
+        let tree = repo.head().unwrap().peel_to_tree().unwrap();
+        assert!(tree.get_path(Path::new("prompts/a.md")).is_ok());
+        assert!(tree.get_path(Path::new("prompts/b.md")).is_err());
+        assert!(tree.get_path(Path::new("outside.txt")).is_err());
+        assert_eq!(summaries(&root, "a.md"), ["新建提示词: a.md"]);
+    }
+
+    #[test]
+    fn rapid_edits_amend_the_previous_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let path = root.join("a.md");
+        write(root, "a.md", "a");
+        commit(root, &[&path], "新建提示词: a.md", true).unwrap();
+        for content in ["ab", "abc", "abcd"] {
+            write(root, "a.md", content);
+            commit(root, &[&path], "更新提示词: a.md", true).unwrap();
+        }
+        assert_eq!(summaries(root, "a.md"), ["更新提示词: a.md", "新建提示词: a.md"]);
+        let repo = Repository::open(root).unwrap();
+        let head = repo.head().unwrap().peel_to_tree().unwrap();
+        let blob = head.get_path(Path::new("a.md")).unwrap().to_object(&repo).unwrap();
+        assert_eq!(blob.as_blob().unwrap().content(), b"abcd");
+
+        // 其他提示词的修改之后重新开始；不合并的提交总是新建提交，之后的快速保存仍会合并
+        write(root, "b.md", "b");
+        commit(root, &[&root.join("b.md")], "更新提示词: b.md", true).unwrap();
+        write(root, "a.md", "abcde");
+        commit(root, &[&path], "更新提示词: a.md", true).unwrap();
+        write(root, "a.md", "abcdef");
+        commit(root, &[&path], "更新提示词: a.md", false).unwrap();
+        assert_eq!(commit_count(root), 5);
+        write(root, "a.md", "abcdefg");
+        commit(root, &[&path], "更新提示词: a.md", true).unwrap();
+        assert_eq!(commit_count(root), 5);
+    }
+
+    #[test]
+    fn old_or_pushed_commits_are_not_amended() {
+        let (_dir, root, _, url) = setup();
+        let path = root.join("a.md");
+        write(&root, "a.md", "a");
+        commit(&root, &[&path], "更新提示词: a.md", true).unwrap();
+        push(&root, &url).unwrap();
+        write(&root, "a.md", "ab");
+        commit(&root, &[&path], "更新提示词: a.md", true).unwrap();
+        assert_eq!(commit_count(&root), 2);
+
+        // 第一次保存超过 AMEND_SECS 的提交
+        let repo = Repository::open(&root).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let old = Time::new(Local::now().timestamp() - AMEND_SECS, 0);
+        let sig = Signature::new(DEFAULT_AUTHOR.0, DEFAULT_AUTHOR.1, &old).unwrap();
+        head.amend(Some("HEAD"), Some(&sig), None, None, None, None).unwrap();
+        write(&root, "a.md", "abc");
+        commit(&root, &[&path], "更新提示词: a.md", true).unwrap();
+        assert_eq!(commit_count(&root), 3);
+    }
+
+    #[test]
+    fn pull_fast_forwards_merges_and_reports_conflicts() {
+        let (_dir, a, b, url) = setup();
+        write(&a, "a.md", "one\n");
+        commit(&a, &[&a.join("a.md")], "新建提示词: a.md", false).unwrap();
+        let pushed = push(&a, &url).unwrap();
+        assert!(pushed.message.starts_with("已推送分支 "));
+
+        // 空的提示词库直接检出远程版本
+        let pulled = pull(&b, &url).unwrap();
+        assert_eq!(pulled.message, "已快进到远程最新版本");
+        assert_eq!(fs::read_to_string(b.join("a.md")).unwrap(), "one\n");
+        assert!(!pull(&b, &url).unwrap().changed);
+
+        // 快进
+        write(&b, "a.md", "one\ntwo\n");
+        commit(&b, &[&b.join("a.md")], "更新提示词: a.md", false).unwrap();
+        push(&b, &url).unwrap();
+        assert_eq!(pull(&a, &url).unwrap().message, "已快进到远程最新版本");
+        assert_eq!(fs::read_to_string(a.join("a.md")).unwrap(), "one\ntwo\n");
+
+        // 不同文件的修改自动合并，本地未提交的修改先提交
+        write(&a, "a.md", "one\ntwo\nthree\n");
+        push(&a, &url).unwrap();
+        write(&b, "b.md", "b\n");
+        assert_eq!(
+            push(&b, &url).unwrap_err(),
+            "远程仓库有新的提交，请先拉取"
+        );
+        assert_eq!(pull(&b, &url).unwrap().message, "已拉取并合并远程修改");
+        assert_eq!(fs::read_to_string(b.join("a.md")).unwrap(), "one\ntwo\nthree\n");
+        assert_eq!(fs::read_to_string(b.join("b.md")).unwrap(), "b\n");
+        push(&b, &url).unwrap();
+        pull(&a, &url).unwrap();
+        assert_eq!(fs::read_to_string(a.join("b.md")).unwrap(), "b\n");
+
+        // 同一行的修改产生冲突，本地分支和工作区保持不变
+        write(&a, "a.md", "one\ntwo\nthree (a)\n");
+        push(&a, &url).unwrap();
+        write(&b, "a.md", "one\ntwo\nthree (b)\n");
+        let head_before = Repository::open(&b).unwrap().head().unwrap().target();
+        let err = pull(&b, &url).unwrap_err();
+        assert!(err.starts_with("合并冲突"), "{}", err);
+        assert!(err.contains("a.md"), "{}", err);
+        assert_eq!(fs::read_to_string(b.join("a.md")).unwrap(), "one\ntwo\nthree (b)\n");
+        let repo = Repository::open(&b).unwrap();
+        assert_eq!(repo.head().unwrap().peel_to_commit().unwrap().parent_id(0).ok(), head_before);
+    }
+}
@@ -0,0 +1,369 @@
+// 版本历史 - 保存提示词时记录快照，支持对比与恢复
+// 快照按提示词 ID 保存在配置目录的 history/<id>.json 中，文件重命名或移动后历史保持不变
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// 该时间（秒）内的连续保存合并为同一个版本，避免自动保存产生大量版本
+const COALESCE_SECS: i64 = 60;
+// 每个提示词最多保留的版本数
+const MAX_VERSIONS: usize = 100;
+// 超过该天数的版本会被清理，但始终保留最近的 KEEP_RECENT 个
+const MAX_AGE_DAYS: i64 = 180;
+const KEEP_RECENT: usize = 10;
+
+// 一个版本
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PromptVersion {
+    // 递增的版本号
+    pub version: u64,
+    pub saved_at: String,
+    // 合并保存时第一次保存的时间
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_saved_at: Option<String>,
+    pub title: String,
+    pub hash: String,
+    pub content: String,
+}
+
+// 版本列表项（不含内容）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VersionInfo {
+    pub version: u64,
+    pub saved_at: String,
+    pub title: String,
+    // 内容字符数
+    pub chars: usize,
+    // 第一行，便于辨认
+    pub summary: String,
+}
+
+// 待记录的快照
+pub struct Snapshot<'a> {
+    pub title: &'a str,
+    pub content: &'a str,
+    pub saved_at: DateTime<Local>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct History {
+    #[serde(default)]
+    versions: Vec<PromptVersion>,
+}
+
+fn snapshot_hash(title: &str, content: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(title.as_bytes());
+    hasher.update([0u8]);
+    hasher.update(content.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+fn parse_time(s: &str) -> Option<DateTime<Local>> {
+    DateTime::parse_from_rfc3339(s).ok().map(|t| t.with_timezone(&Local))
+}
+
+fn history_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{}.json", id))
+}
+
+impl History {
+    fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("读取版本历史失败: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("解析版本历史失败: {}", e))
+    }
+
+    fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("创建目录失败: {}", e))?;
+        }
+        let content = serde_json::to_string(self)
+            .map_err(|e| format!("序列化版本历史失败: {}", e))?;
+        fs::write(path, content).map_err(|e| format!("保存版本历史失败: {}", e))
+    }
+
+    fn latest(&self) -> Option<&PromptVersion> {
+        self.versions.last()
+    }
+
+    // 添加快照；与最新版本相同时跳过，coalesce 为 true 且最新版本开始于不久前时替换最新版本
+    fn push(&mut self, snapshot: &Snapshot, coalesce: bool) -> bool {
+        let hash = snapshot_hash(snapshot.title, snapshot.content);
+        if self.latest().is_some_and(|v| v.hash == hash) {
+            return false;
+        }
+
+        let merge = coalesce && self.latest()
+            .and_then(|v| parse_time(v.first_saved_at.as_deref().unwrap_or(&v.saved_at)))
+            .is_some_and(|t| (snapshot.saved_at - t).num_seconds() < COALESCE_SECS);
+        let saved_at = snapshot.saved_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, false);
+        let (version, first_saved_at) = match self.versions.pop() {
+            Some(v) if merge => (v.version, Some(v.first_saved_at.unwrap_or(v.saved_at))),
+            Some(v) => {
+                let next = v.version + 1;
+                self.versions.push(v);
+                (next, None)
+            }
+            None => (1, None),
+        };
+
+        self.versions.push(PromptVersion {
+            version,
+            saved_at,
+            first_saved_at,
+            title: snapshot.title.to_string(),
+            hash,
+            content: snapshot.content.to_string(),
+        });
+        true
+    }
+
+    // 清理超出数量或过旧的版本
+    fn prune(&mut self, now: DateTime<Local>) {
+        let keep_from = self.versions.len().saturating_sub(KEEP_RECENT);
+        let mut index = 0;
+        self.versions.retain(|v| {
+            index += 1;
+            index > keep_from
+                || parse_time(&v.saved_at).is_none_or(|t| (now - t).num_days() <= MAX_AGE_DAYS)
+        });
+        let excess = self.versions.len().saturating_sub(MAX_VERSIONS);
+        self.versions.drain(..excess);
+    }
+}
+
+// 保存提示词时记录快照
+// previous 为被覆盖的磁盘内容：与最新快照不同时（首次记录或被外部修改）先单独保存一份
+// coalesce 为 false 时（如恢复版本）总是新建版本
+pub fn record(
+    dir: &Path,
+    id: &str,
+    previous: Option<Snapshot>,
+    current: Snapshot,
+    coalesce: bool,
+) -> Result<(), String> {
+    let path = history_path(dir, id);
+    let mut history = History::load(&path)?;
+
+    // 刚保存的旧内容不能被合并覆盖
+    let captured = previous.is_some_and(|p| history.push(&p, false));
+    let changed = history.push(&current, coalesce && !captured) || captured;
+    if !changed {
+        return Ok(());
+    }
+
+    history.prune(current.saved_at);
+    history.save(&path)
+}
+
+// 删除提示词的全部版本历史
+pub fn remove(dir: &Path, id: &str) -> Result<(), String> {
+    let path = history_path(dir, id);
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| format!("删除版本历史失败: {}", e))?;
+    }
+    Ok(())
+}
+
+// 版本列表（最新的在前）
+pub fn list(dir: &Path, id: &str) -> Result<Vec<VersionInfo>, String> {
+    let history = History::load(&history_path(dir, id))?;
+    Ok(history.versions.iter()
+        .rev()
+        .map(|v| VersionInfo {
+            version: v.version,
+            saved_at: v.saved_at.clone(),
+            title: v.title.clone(),
+            chars: v.content.chars().count(),
+            summary: v.content.lines().find(|l| !l.trim().is_empty()).unwrap_or_default().to_string(),
+        })
+        .collect())
+}
+
+pub fn get(dir: &Path, id: &str, version: u64) -> Result<PromptVersion, String> {
+    History::load(&history_path(dir, id))?
+        .versions
+        .into_iter()
+        .find(|v| v.version == version)
+        .ok_or_else(|| format!("版本 {} 不存在", version))
+}
+
+// ====== 对比 ======
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffTag {
+    Equal,
+    Insert,
+    Delete,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DiffLine {
+    pub tag: DiffTag,
+    pub text: String,
+}
+
+// 按行对比
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    use similar::{ChangeTag, TextDiff};
+
+    TextDiff::from_lines(old, new)
+        .iter_all_changes()
+        .map(|change| DiffLine {
+            tag: match change.tag() {
+                ChangeTag::Equal => DiffTag::Equal,
+                ChangeTag::Insert => DiffTag::Insert,
+                ChangeTag::Delete => DiffTag::Delete,
+            },
+            text: change.value().trim_end_matches(['\r', '\n']).to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    const ID: &str = "prompt-1";
+
+    fn at(secs: i64) -> DateTime<Local> {
+        Local.timestamp_opt(1_717_200_000, 0).unwrap() + Duration::seconds(secs)
+    }
+
+    fn snap(content: &str, secs: i64) -> Snapshot<'_> {
+        Snapshot { title: "标题", content, saved_at: at(secs) }
+    }
+
+    fn save(dir: &Path, content: &str, secs: i64, coalesce: bool) {
+        record(dir, ID, None, snap(content, secs), coalesce).unwrap();
+    }
+
+    fn versions(dir: &Path) -> Vec<(u64, String)> {
+        History::load(&history_path(dir, ID)).unwrap()
+            .versions
+            .into_iter()
+            .map(|v| (v.version, v.content))
+            .collect()
+    }
+
+    fn numbers(dir: &Path) -> Vec<u64> {
+        versions(dir).into_iter().map(|(v, _)| v).collect()
+    }
+
+    #[test]
+    fn rapid_saves_coalesce_into_one_version() {
+        let dir = tempfile::tempdir().unwrap();
+        save(dir.path(), "a", 0, true);
+        save(dir.path(), "ab", 20, true);
+        save(dir.path(), "abc", 59, true);
+        assert_eq!(versions(dir.path()), [(1, "abc".to_string())]);
+        let v = get(dir.path(), ID, 1).unwrap();
+        assert_eq!(v.first_saved_at.as_deref().and_then(parse_time), Some(at(0)));
+        assert_eq!(parse_time(&v.saved_at), Some(at(59)));
+
+        // 从第一次保存算起超过合并时间后新建版本，即使距上次保存很近
+        save(dir.path(), "abcd", 60, true);
+        save(dir.path(), "abcde", 70, true);
+        assert_eq!(versions(dir.path()), [(1, "abc".to_string()), (2, "abcde".to_string())]);
+    }
+
+    #[test]
+    fn unchanged_or_uncoalesced_saves() {
+        let dir = tempfile::tempdir().unwrap();
+        save(dir.path(), "a", 0, true);
+        // 内容相同时不记录
+        save(dir.path(), "a", 10, true);
+        assert_eq!(numbers(dir.path()), [1]);
+        // 恢复版本等不合并的保存总是新建版本
+        save(dir.path(), "b", 20, false);
+        save(dir.path(), "c", 30, false);
+        assert_eq!(numbers(dir.path()), [1, 2, 3]);
+        // 不合并的版本之后的快速保存仍会合并
+        save(dir.path(), "d", 40, true);
+        assert_eq!(versions(dir.path()).last(), Some(&(3, "d".to_string())));
+    }
+
+    #[test]
+    fn external_edit_is_kept_as_its_own_version() {
+        let dir = tempfile::tempdir().unwrap();
+        save(dir.path(), "a", 0, true);
+        // 磁盘内容被外部修改为 x，随后在应用中保存为 y
+        record(dir.path(), ID, Some(snap("x", 10)), snap("y", 20), true).unwrap();
+        assert_eq!(
+            versions(dir.path()),
+            [(1, "a".to_string()), (2, "x".to_string()), (3, "y".to_string())]
+        );
+        // 磁盘内容与最新版本相同时不重复记录
+        record(dir.path(), ID, Some(snap("y", 30)), snap("z", 40), true).unwrap();
+        assert_eq!(versions(dir.path()).last(), Some(&(3, "z".to_string())));
+    }
+
+    #[test]
+    fn prunes_to_max_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let total = MAX_VERSIONS as i64 + 5;
+        for i in 0..total {
+            save(dir.path(), &i.to_string(), i * 10, false);
+        }
+        let numbers = numbers(dir.path());
+        assert_eq!(numbers.len(), MAX_VERSIONS);
+        assert_eq!(numbers.first(), Some(&6));
+        assert_eq!(numbers.last(), Some(&(total as u64)));
+    }
+
+    #[test]
+    fn prunes_old_versions_but_keeps_recent_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..15 {
+            save(dir.path(), &format!("old {}", i), i * 10, false);
+        }
+        // 半年后保存：过旧的版本中只保留最近的 KEEP_RECENT 个（含新版本）
+        save(dir.path(), "new", (MAX_AGE_DAYS + 2) * 24 * 3600, true);
+        assert_eq!(numbers(dir.path()), (7..=16).collect::<Vec<_>>());
+
+        // 未超过期限的版本不清理
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..15 {
+            save(dir.path(), &format!("v{}", i), i * 24 * 3600, false);
+        }
+        save(dir.path(), "new", MAX_AGE_DAYS * 24 * 3600, false);
+        assert_eq!(numbers(dir.path()), (1..=16).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn list_get_and_remove() {
+        let dir = tempfile::tempdir().unwrap();
+        save(dir.path(), "\n  \n第一行\n第二行", 0, false);
+        save(dir.path(), "新内容", 100, false);
+        let infos = list(dir.path(), ID).unwrap();
+        let summary: Vec<(u64, &str, usize)> = infos.iter().map(|v| (v.version, v.summary.as_str(), v.chars)).collect();
+        assert_eq!(summary, [(2, "新内容", 3), (1, "第一行", 11)]);
+        assert!(get(dir.path(), ID, 3).is_err());
+
+        remove(dir.path(), ID).unwrap();
+        assert!(list(dir.path(), ID).unwrap().is_empty());
+        remove(dir.path(), ID).unwrap();
+    }
+
+    #[test]
+    fn diff_by_lines() {
+        let diff: Vec<(DiffTag, String)> = diff_lines("a\nb\r\nc\n", "a\nc\nd")
+            .into_iter()
+            .map(|l| (l.tag, l.text))
+            .collect();
+        assert_eq!(diff, [
+            (DiffTag::Equal, "a".to_string()),
+            (DiffTag::Delete, "b".to_string()),
+            (DiffTag::Equal, "c".to_string()),
+            (DiffTag::Insert, "d".to_string()),
+        ]);
+    }
+}
@@ -0,0 +1,381 @@
+// 模拟输入 - 逐字符把文本输入到当前焦点窗口（不经过剪贴板），支持中文等任意 Unicode 字符
+// 各平台的输入方式实现 TextInjector，由 type_text 按配置的间隔逐字符输入，按下中止键时停止：
+//   Windows   SendInput（KEYEVENTF_UNICODE）
+//   X11       XTest，临时把空闲键码映射为要输入的字符（inject_x11.rs）
+//   Wayland   wtype（通过 virtual-keyboard 协议输入，需要合成器支持）
+//   uinput    创建虚拟键盘设备（inject_uinput.rs），适用于任意桌面环境，需要 /dev/uinput 写权限
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+// 输入方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum InjectBackend {
+    // 按平台和会话类型自动选择
+    #[default]
+    Auto,
+    X11,
+    Wayland,
+    Uinput,
+}
+
+// 中止键
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AbortKey {
+    #[default]
+    Escape,
+    Pause,
+    ScrollLock,
+    F12,
+    // 不检测中止键
+    Disabled,
+}
+
+// 模拟输入配置
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SimulateConfig {
+    #[serde(default)]
+    pub backend: InjectBackend,
+    // 每个字符之间的间隔（毫秒）
+    #[serde(default = "default_char_delay")]
+    pub char_delay_ms: u64,
+    #[serde(default)]
+    pub abort_key: AbortKey,
+}
+
+fn default_char_delay() -> u64 {
+    2
+}
+
+impl Default for SimulateConfig {
+    fn default() -> Self {
+        Self {
+            backend: InjectBackend::Auto,
+            char_delay_ms: default_char_delay(),
+            abort_key: AbortKey::Escape,
+        }
+    }
+}
+
+// 文本输入方式
+pub trait TextInjector {
+    // 输入一个字符（\n 为回车，\t 为 Tab）
+    fn type_char(&mut self, c: char) -> Result<(), String>;
+
+    // 中止键是否处于按下状态（无法检测时返回 false）
+    fn abort_requested(&mut self) -> bool;
+
+    // 每次输入的最大字符数：每次输入开销较大的方式（如启动外部进程）一次输入一段文本
+    // 分段输入的方式自行处理字符间隔（如 wtype -d），type_text 不再在段之间等待
+    fn chunk_size(&self) -> usize {
+        1
+    }
+
+    // 输入一段文本（不超过 chunk_size 个字符），默认逐字符输入
+    fn type_str(&mut self, text: &str) -> Result<(), String> {
+        text.chars().try_for_each(|c| self.type_char(c))
+    }
+}
+
+// 输入结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeOutcome {
+    Completed,
+    // 用户按下中止键，已输入 typed 个字符
+    Aborted { typed: usize },
+}
+
+// 按 chunk_size 分段输入文本，每段之前检测中止键（\r\n 按一次回车输入）
+// 逐字符输入时每个字符之后间隔 delay，分段输入的方式已在段内按字符间隔输入
+pub fn type_text(
+    injector: &mut dyn TextInjector,
+    text: &str,
+    delay: Duration,
+    mut sleep: impl FnMut(Duration),
+) -> Result<TypeOutcome, String> {
+    let chars: Vec<char> = text.replace("\r\n", "\n").chars().collect();
+    let chunk_size = injector.chunk_size().max(1);
+    let delay = if chunk_size == 1 { delay } else { Duration::ZERO };
+    let mut typed = 0;
+    for chunk in chars.chunks(chunk_size) {
+        if injector.abort_requested() {
+            return Ok(TypeOutcome::Aborted { typed });
+        }
+        injector.type_str(&chunk.iter().collect::<String>())?;
+        typed += chunk.len();
+        if !delay.is_zero() {
+            sleep(delay);
+        }
+    }
+    Ok(TypeOutcome::Completed)
+}
+
+// 按配置创建输入方式
+pub fn create(config: &SimulateConfig) -> Result<Box<dyn TextInjector>, String> {
+    #[cfg(target_os = "windows")]
+    {
+        match config.backend {
+            InjectBackend::Auto => Ok(Box::new(win::SendInputInjector::new(config.abort_key))),
+            backend => Err(format!("Windows 不支持输入方式 {:?}", backend)),
+        }
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let backend = match config.backend {
+            InjectBackend::Auto => auto_backend(),
+            backend => backend,
+        };
+        match backend {
+            InjectBackend::X11 => Ok(Box::new(crate::inject_x11::XTestInjector::new(config.abort_key)?)),
+            InjectBackend::Wayland => {
+                Ok(Box::new(wayland::WtypeInjector::new(config.abort_key, config.char_delay_ms)?))
+            }
+            _ => Ok(Box::new(crate::inject_uinput::UinputInjector::new(config.abort_key)?)),
+        }
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    {
+        let _ = config;
+        Err("当前平台不支持模拟输入".to_string())
+    }
+}
+
+// 自动选择：Wayland 下优先 wtype，X11 下使用 XTest，都不可用时使用 uinput
+#[cfg(target_os = "linux")]
+fn auto_backend() -> InjectBackend {
+    let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+    if wayland && wayland::wtype_available() {
+        InjectBackend::Wayland
+    } else if !wayland && std::env::var_os("DISPLAY").is_some() {
+        InjectBackend::X11
+    } else {
+        InjectBackend::Uinput
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod win {
+    use super::{AbortKey, TextInjector};
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        GetAsyncKeyState, SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS,
+        KEYEVENTF_KEYUP, KEYEVENTF_UNICODE, VIRTUAL_KEY,
+    };
+
+    // 使用 SendInput 输入 UTF-16 字符
+    pub struct SendInputInjector {
+        abort_vk: Option<i32>,
+    }
+
+    impl SendInputInjector {
+        pub fn new(abort_key: AbortKey) -> Self {
+            let abort_vk = match abort_key {
+                AbortKey::Escape => Some(0x1B),
+                AbortKey::Pause => Some(0x13),
+                AbortKey::ScrollLock => Some(0x91),
+                AbortKey::F12 => Some(0x7B),
+                AbortKey::Disabled => None,
+            };
+            Self { abort_vk }
+        }
+    }
+
+    fn key_input(unit: u16, flags: KEYBD_EVENT_FLAGS) -> INPUT {
+        INPUT {
+            r#type: INPUT_KEYBOARD,
+            Anonymous: INPUT_0 {
+                ki: KEYBDINPUT {
+                    wVk: VIRTUAL_KEY(0),
+                    wScan: unit,
+                    dwFlags: flags,
+                    time: 0,
+                    dwExtraInfo: 0,
+                },
+            },
+        }
+    }
+
+    impl TextInjector for SendInputInjector {
+        fn type_char(&mut self, c: char) -> Result<(), String> {
+            let c = if c == '\n' { '\r' } else { c };
+            let mut units = [0u16; 2];
+            for &unit in c.encode_utf16(&mut units).iter() {
+                let inputs = [
+                    key_input(unit, KEYEVENTF_UNICODE),
+                    key_input(unit, KEYEVENTF_UNICODE | KEYEVENTF_KEYUP),
+                ];
+                if unsafe { SendInput(&inputs, std::mem::size_of::<INPUT>() as i32) } == 0 {
+                    return Err("模拟输入失败".to_string());
+                }
+            }
+            Ok(())
+        }
+
+        fn abort_requested(&mut self) -> bool {
+            self.abort_vk
+                .is_some_and(|vk| unsafe { GetAsyncKeyState(vk) } as u16 & 0x8000 != 0)
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod wayland {
+    use super::{AbortKey, TextInjector};
+    use crate::inject_uinput::KeyboardState;
+    use std::process::Command;
+
+    pub fn wtype_available() -> bool {
+        Command::new("wtype").arg("-h").output().is_ok()
+    }
+
+    // 每次调用 wtype 输入的字符数：逐字符启动进程太慢，且系统繁忙时各进程的输入顺序可能错乱
+    const CHUNK_CHARS: usize = 64;
+
+    // 调用 wtype 输入（wtype 为每个字符生成键盘映射，支持任意 Unicode 字符，\n 和 \t 输入为回车和 Tab）
+    // Wayland 下无法获取全局按键状态，中止键通过读取 /dev/input 检测（需要 input 组权限，否则不检测）
+    pub struct WtypeInjector {
+        keyboard: Option<KeyboardState>,
+        // 字符间隔（毫秒），由 wtype -d 处理
+        char_delay_ms: u64,
+    }
+
+    impl WtypeInjector {
+        pub fn new(abort_key: AbortKey, char_delay_ms: u64) -> Result<Self, String> {
+            if !wtype_available() {
+                return Err("未找到 wtype，请先安装 wtype 或在设置中选择其他输入方式".to_string());
+            }
+            Ok(Self { keyboard: KeyboardState::open(abort_key), char_delay_ms })
+        }
+    }
+
+    impl TextInjector for WtypeInjector {
+        fn type_char(&mut self, c: char) -> Result<(), String> {
+            self.type_str(&c.to_string())
+        }
+
+        fn chunk_size(&self) -> usize {
+            CHUNK_CHARS
+        }
+
+        fn type_str(&mut self, text: &str) -> Result<(), String> {
+            let output = Command::new("wtype")
+                .args(["-d", &self.char_delay_ms.to_string(), "--", text])
+                .output()
+                .map_err(|e| format!("运行 wtype 失败: {}", e))?;
+            if !output.status.success() {
+                return Err(format!("wtype 输入失败: {}", String::from_utf8_lossy(&output.stderr).trim()));
+            }
+            Ok(())
+        }
+
+        fn abort_requested(&mut self) -> bool {
+            self.keyboard.as_mut().is_some_and(KeyboardState::abort_pressed)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 记录输入内容的假输入方式，第 abort_at 次检测中止键时返回 true
+    #[derive(Default)]
+    struct FakeInjector {
+        chunk: usize,
+        typed: Vec<String>,
+        checks: usize,
+        abort_at: Option<usize>,
+    }
+
+    impl TextInjector for FakeInjector {
+        fn type_char(&mut self, c: char) -> Result<(), String> {
+            if c == '!' {
+                return Err("无法输入".to_string());
+            }
+            self.typed.push(c.to_string());
+            Ok(())
+        }
+
+        fn abort_requested(&mut self) -> bool {
+            self.checks += 1;
+            self.abort_at == Some(self.checks)
+        }
+
+        fn chunk_size(&self) -> usize {
+            self.chunk
+        }
+
+        fn type_str(&mut self, text: &str) -> Result<(), String> {
+            if self.chunk <= 1 {
+                return text.chars().try_for_each(|c| self.type_char(c));
+            }
+            self.typed.push(text.to_string());
+            Ok(())
+        }
+    }
+
+    fn run(injector: &mut FakeInjector, text: &str) -> (Result<TypeOutcome, String>, usize) {
+        let mut sleeps = 0;
+        let outcome = type_text(injector, text, Duration::from_millis(5), |_| sleeps += 1);
+        (outcome, sleeps)
+    }
+
+    #[test]
+    fn types_characters_in_order_with_newlines_normalized() {
+        let mut injector = FakeInjector { chunk: 1, ..Default::default() };
+        let (outcome, sleeps) = run(&mut injector, "a中\r\nb\n\té");
+        assert_eq!(outcome, Ok(TypeOutcome::Completed));
+        assert_eq!(injector.typed, ["a", "中", "\n", "b", "\n", "\t", "é"]);
+        assert_eq!(injector.checks, 7);
+        assert_eq!(sleeps, 7);
+    }
+
+    #[test]
+    fn abort_mid_stream_reports_typed_count() {
+        let mut injector = FakeInjector { chunk: 1, abort_at: Some(3), ..Default::default() };
+        let (outcome, sleeps) = run(&mut injector, "abcdef");
+        assert_eq!(outcome, Ok(TypeOutcome::Aborted { typed: 2 }));
+        assert_eq!(injector.typed, ["a", "b"]);
+        assert_eq!(sleeps, 2);
+    }
+
+    #[test]
+    fn abort_before_first_character() {
+        let mut injector = FakeInjector { chunk: 1, abort_at: Some(1), ..Default::default() };
+        assert_eq!(run(&mut injector, "abc").0, Ok(TypeOutcome::Aborted { typed: 0 }));
+        assert!(injector.typed.is_empty());
+    }
+
+    #[test]
+    fn chunked_injector_receives_whole_chunks() {
+        let mut injector = FakeInjector { chunk: 3, ..Default::default() };
+        let (outcome, sleeps) = run(&mut injector, "ab\r\ncdef中");
+        assert_eq!(outcome, Ok(TypeOutcome::Completed));
+        assert_eq!(injector.typed, ["ab\n", "cde", "f中"]);
+        // 字符间隔由输入方式自己处理，段之间不再等待
+        assert_eq!(sleeps, 0);
+
+        let mut injector = FakeInjector { chunk: 3, abort_at: Some(2), ..Default::default() };
+        assert_eq!(run(&mut injector, "abcdefg").0, Ok(TypeOutcome::Aborted { typed: 3 }));
+        assert_eq!(injector.typed, ["abc"]);
+    }
+
+    #[test]
+    fn errors_stop_typing() {
+        let mut injector = FakeInjector { chunk: 1, ..Default::default() };
+        assert!(run(&mut injector, "ab!cd").0.is_err());
+        assert_eq!(injector.typed, ["a", "b"]);
+    }
+
+    #[test]
+    fn empty_text_and_zero_delay() {
+        let mut injector = FakeInjector { chunk: 1, ..Default::default() };
+        assert_eq!(run(&mut injector, "").0, Ok(TypeOutcome::Completed));
+        assert_eq!(injector.checks, 0);
+
+        let mut sleeps = 0;
+        let outcome = type_text(&mut injector, "ab", Duration::ZERO, |_| sleeps += 1);
+        assert_eq!(outcome, Ok(TypeOutcome::Completed));
+        assert_eq!(sleeps, 0);
+    }
+}
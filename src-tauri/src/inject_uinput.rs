@@ -0,0 +1,282 @@
+// uinput 模拟输入 - 在内核中创建虚拟键盘设备发送按键，不依赖 X11 或 Wayland 合成器
+// 需要 /dev/uinput 写权限（通常把用户加入 input 组，或添加 udev 规则）
+// 按键码按美式键盘布局发送 ASCII 字符；其他字符（如中文）使用 Ctrl+Shift+U + 十六进制码位 + 空格输入，
+// 需要输入法（IBus、Fcitx）或 GTK 程序支持该组合键
+use crate::inject::{AbortKey, TextInjector};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::time::Duration;
+
+// linux/uinput.h、linux/input.h
+const UI_SET_EVBIT: u64 = 0x4004_5564; // _IOW('U', 100, int)
+const UI_SET_KEYBIT: u64 = 0x4004_5565; // _IOW('U', 101, int)
+const UI_DEV_SETUP: u64 = 0x405c_5503; // _IOW('U', 3, struct uinput_setup)
+const UI_DEV_CREATE: u64 = 0x5501; // _IO('U', 1)
+const UI_DEV_DESTROY: u64 = 0x5502; // _IO('U', 2)
+const EVIOCGKEY: u64 = 0x8060_4518; // _IOR('E', 0x18, char[96])
+
+const EV_SYN: u16 = 0x00;
+const EV_KEY: u16 = 0x01;
+const SYN_REPORT: u16 = 0;
+const BUS_VIRTUAL: u16 = 0x06;
+const KEY_MAX_BYTES: usize = 96;
+
+const KEY_ENTER: u16 = 28;
+const KEY_LEFTCTRL: u16 = 29;
+const KEY_LEFTSHIFT: u16 = 42;
+const KEY_SPACE: u16 = 57;
+const KEY_TAB: u16 = 15;
+const KEY_U: u16 = 22;
+// 注册的按键码范围（KEY_ESC 到 KEY_MICMUTE）
+const KEY_RANGE: std::ops::RangeInclusive<u16> = 1..=248;
+
+// a-z 对应的按键码
+const LETTERS: [u16; 26] = [
+    30, 48, 46, 32, 18, 33, 34, 35, 23, 36, 37, 38, 50, 49, 24, 25, 16, 19, 31, 20, 22, 47, 17, 45, 21, 44,
+];
+
+fn abort_code(key: AbortKey) -> Option<u16> {
+    match key {
+        AbortKey::Escape => Some(1),
+        AbortKey::Pause => Some(119),
+        AbortKey::ScrollLock => Some(70),
+        AbortKey::F12 => Some(88),
+        AbortKey::Disabled => None,
+    }
+}
+
+// 美式键盘布局中 ASCII 字符对应的按键码和是否需要 Shift
+pub fn ascii_key(c: char) -> Option<(u16, bool)> {
+    const SHIFTED_DIGITS: &str = ")!@#$%^&*(";
+    let key = match c {
+        'a'..='z' => (LETTERS[c as usize - 'a' as usize], false),
+        'A'..='Z' => (LETTERS[c as usize - 'A' as usize], true),
+        '0' => (11, false),
+        '1'..='9' => (c as u16 - '1' as u16 + 2, false),
+        ')' | '!' | '@' | '#' | '$' | '%' | '^' | '&' | '*' | '(' => {
+            let digit = SHIFTED_DIGITS.find(c)?;
+            (if digit == 0 { 11 } else { digit as u16 + 1 }, true)
+        }
+        '-' => (12, false),
+        '_' => (12, true),
+        '=' => (13, false),
+        '+' => (13, true),
+        '[' => (26, false),
+        '{' => (26, true),
+        ']' => (27, false),
+        '}' => (27, true),
+        ';' => (39, false),
+        ':' => (39, true),
+        '\'' => (40, false),
+        '"' => (40, true),
+        '`' => (41, false),
+        '~' => (41, true),
+        '\\' => (43, false),
+        '|' => (43, true),
+        ',' => (51, false),
+        '<' => (51, true),
+        '.' => (52, false),
+        '>' => (52, true),
+        '/' => (53, false),
+        '?' => (53, true),
+        ' ' => (KEY_SPACE, false),
+        '\n' | '\r' => (KEY_ENTER, false),
+        '\t' => (KEY_TAB, false),
+        _ => return None,
+    };
+    Some(key)
+}
+
+fn ioctl_error(action: &str) -> String {
+    format!("{}失败: {}", action, std::io::Error::last_os_error())
+}
+
+pub struct UinputInjector {
+    device: File,
+    keyboard: Option<KeyboardState>,
+}
+
+impl UinputInjector {
+    pub fn new(abort_key: AbortKey) -> Result<Self, String> {
+        let device = OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open("/dev/uinput")
+            .map_err(|e| format!("打开 /dev/uinput 失败（需要写权限）: {}", e))?;
+        let fd = device.as_raw_fd();
+
+        let mut setup: libc::uinput_setup = unsafe { std::mem::zeroed() };
+        setup.id = libc::input_id { bustype: BUS_VIRTUAL, vendor: 0, product: 0, version: 1 };
+        for (dst, src) in setup.name.iter_mut().zip(b"prompt-manager virtual keyboard") {
+            *dst = *src as libc::c_char;
+        }
+
+        unsafe {
+            if libc::ioctl(fd, UI_SET_EVBIT as _, EV_KEY as libc::c_int) < 0 {
+                return Err(ioctl_error("创建虚拟键盘"));
+            }
+            for code in KEY_RANGE {
+                if libc::ioctl(fd, UI_SET_KEYBIT as _, code as libc::c_int) < 0 {
+                    return Err(ioctl_error("创建虚拟键盘"));
+                }
+            }
+            if libc::ioctl(fd, UI_DEV_SETUP as _, &setup) < 0 || libc::ioctl(fd, UI_DEV_CREATE as _) < 0 {
+                return Err(ioctl_error("创建虚拟键盘"));
+            }
+        }
+        // 等待桌面环境识别新设备，否则最初的按键会丢失
+        std::thread::sleep(Duration::from_millis(200));
+
+        Ok(Self { device, keyboard: KeyboardState::open(abort_key) })
+    }
+
+    fn emit(&mut self, type_: u16, code: u16, value: i32) -> Result<(), String> {
+        let mut event: libc::input_event = unsafe { std::mem::zeroed() };
+        event.type_ = type_;
+        event.code = code;
+        event.value = value;
+        let bytes = unsafe {
+            std::slice::from_raw_parts(
+                &event as *const libc::input_event as *const u8,
+                std::mem::size_of::<libc::input_event>(),
+            )
+        };
+        self.device.write_all(bytes).map_err(|e| format!("模拟按键失败: {}", e))
+    }
+
+    fn key(&mut self, code: u16, pressed: bool) -> Result<(), String> {
+        self.emit(EV_KEY, code, pressed as i32)?;
+        self.emit(EV_SYN, SYN_REPORT, 0)
+    }
+
+    // 按下修饰键和按键后依次松开
+    fn chord(&mut self, modifiers: &[u16], code: u16) -> Result<(), String> {
+        for &m in modifiers {
+            self.key(m, true)?;
+        }
+        self.key(code, true)?;
+        self.key(code, false)?;
+        for &m in modifiers.iter().rev() {
+            self.key(m, false)?;
+        }
+        Ok(())
+    }
+}
+
+impl TextInjector for UinputInjector {
+    fn type_char(&mut self, c: char) -> Result<(), String> {
+        if let Some((code, shift)) = ascii_key(c) {
+            let modifiers: &[u16] = if shift { &[KEY_LEFTSHIFT] } else { &[] };
+            return self.chord(modifiers, code);
+        }
+        // Ctrl+Shift+U，输入十六进制码位，空格确认
+        self.chord(&[KEY_LEFTCTRL, KEY_LEFTSHIFT], KEY_U)?;
+        for digit in format!("{:x}", c as u32).chars() {
+            if let Some((code, _)) = ascii_key(digit) {
+                self.chord(&[], code)?;
+            }
+        }
+        self.chord(&[], KEY_SPACE)
+    }
+
+    fn abort_requested(&mut self) -> bool {
+        self.keyboard.as_mut().is_some_and(KeyboardState::abort_pressed)
+    }
+}
+
+impl Drop for UinputInjector {
+    fn drop(&mut self) {
+        // 给目标程序留出处理最后几个按键的时间再移除设备
+        std::thread::sleep(Duration::from_millis(50));
+        unsafe {
+            libc::ioctl(self.device.as_raw_fd(), UI_DEV_DESTROY as _);
+        }
+    }
+}
+
+// 读取 /dev/input 中各输入设备的按键状态，检测中止键（没有读取权限时不可用）
+pub struct KeyboardState {
+    devices: Vec<File>,
+    code: u16,
+}
+
+impl KeyboardState {
+    pub fn open(abort_key: AbortKey) -> Option<Self> {
+        let code = abort_code(abort_key)?;
+        let devices: Vec<File> = fs::read_dir("/dev/input")
+            .ok()?
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().starts_with("event"))
+            .filter_map(|e| {
+                OpenOptions::new().read(true).custom_flags(libc::O_NONBLOCK).open(e.path()).ok()
+            })
+            .collect();
+        if devices.is_empty() {
+            return None;
+        }
+        Some(Self { devices, code })
+    }
+
+    pub fn abort_pressed(&mut self) -> bool {
+        let (byte, bit) = (self.code as usize / 8, self.code % 8);
+        self.devices.iter().any(|device| {
+            let mut keys = [0u8; KEY_MAX_BYTES];
+            let ok = unsafe { libc::ioctl(device.as_raw_fd(), EVIOCGKEY as _, keys.as_mut_ptr()) } >= 0;
+            ok && keys[byte] & (1 << bit) != 0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_key_follows_us_layout() {
+        let cases = [
+            ('a', (30, false)),
+            ('z', (44, false)),
+            ('A', (30, true)),
+            ('Q', (16, true)),
+            ('1', (2, false)),
+            ('9', (10, false)),
+            ('0', (11, false)),
+            ('!', (2, true)),
+            ('@', (3, true)),
+            ('(', (10, true)),
+            (')', (11, true)),
+            ('-', (12, false)),
+            ('_', (12, true)),
+            ('"', (40, true)),
+            ('~', (41, true)),
+            ('|', (43, true)),
+            ('?', (53, true)),
+            (' ', (KEY_SPACE, false)),
+            ('\n', (KEY_ENTER, false)),
+            ('\t', (KEY_TAB, false)),
+        ];
+        for (c, expected) in cases {
+            assert_eq!(ascii_key(c), Some(expected), "{:?}", c);
+        }
+    }
+
+    #[test]
+    fn every_printable_ascii_has_a_key_and_others_do_not() {
+        for c in (0x20u8..=0x7e).map(char::from) {
+            let (code, _) = ascii_key(c).unwrap_or_else(|| panic!("{:?} 没有对应的按键", c));
+            assert!(KEY_RANGE.contains(&code), "{:?}", c);
+        }
+        for c in ['é', '中', '€', '\u{7f}', '\0'] {
+            assert_eq!(ascii_key(c), None, "{:?}", c);
+        }
+    }
+
+    #[test]
+    fn hex_digits_for_unicode_input_are_unshifted() {
+        for c in "0123456789abcdef".chars() {
+            assert!(matches!(ascii_key(c), Some((_, false))), "{:?}", c);
+        }
+    }
+}
@@ -0,0 +1,261 @@
+// X11 模拟输入 - 通过 XTest 扩展发送按键
+// 键盘布局中不一定有要输入的字符（如中文），因此把没有映射任何字符的空闲键码临时映射为该字符的 keysym 后按下，
+// 输入完成后恢复原映射。多个空闲键码轮流使用，避免目标程序还未处理上一个按键时映射就被修改
+use crate::inject::{AbortKey, TextInjector};
+use x11rb::connection::{Connection, RequestConnection};
+use x11rb::protocol::xproto::{ConnectionExt as _, Keycode, Keysym, Window, KEY_PRESS_EVENT, KEY_RELEASE_EVENT};
+use x11rb::protocol::xtest::ConnectionExt as _;
+use x11rb::rust_connection::RustConnection;
+use x11rb::CURRENT_TIME;
+
+const NO_SYMBOL: Keysym = 0;
+const XK_RETURN: Keysym = 0xff0d;
+const XK_TAB: Keysym = 0xff09;
+
+// 字符对应的 keysym：Latin-1 字符直接使用码位，其他字符为 0x01000000 + Unicode 码位
+pub fn keysym_for(c: char) -> Keysym {
+    match c {
+        '\n' | '\r' => XK_RETURN,
+        '\t' => XK_TAB,
+        c if matches!(c as u32, 0x20..=0x7e | 0xa0..=0xff) => c as u32,
+        c => 0x0100_0000 + c as u32,
+    }
+}
+
+fn abort_keysym(key: AbortKey) -> Option<Keysym> {
+    match key {
+        AbortKey::Escape => Some(0xff1b),
+        AbortKey::Pause => Some(0xff13),
+        AbortKey::ScrollLock => Some(0xff14),
+        AbortKey::F12 => Some(0xffc9),
+        AbortKey::Disabled => None,
+    }
+}
+
+pub struct XTestInjector {
+    conn: RustConnection,
+    root: Window,
+    keysyms_per_keycode: u8,
+    // 空闲键码及其当前映射的 keysym
+    spare: Vec<(Keycode, Keysym)>,
+    // 下一个要重新映射的空闲键码
+    next: usize,
+    abort_keycode: Option<Keycode>,
+}
+
+impl XTestInjector {
+    pub fn new(abort_key: AbortKey) -> Result<Self, String> {
+        let (conn, screen_num) = x11rb::connect(None)
+            .map_err(|e| format!("连接 X11 显示服务器失败: {}", e))?;
+        conn.extension_information(x11rb::protocol::xtest::X11_EXTENSION_NAME)
+            .ok()
+            .flatten()
+            .ok_or("X11 显示服务器不支持 XTest 扩展")?;
+        let root = conn.setup().roots[screen_num].root;
+        let (min, max) = (conn.setup().min_keycode, conn.setup().max_keycode);
+
+        let mapping = conn.get_keyboard_mapping(min, max - min + 1)
+            .map_err(|e| format!("读取键盘映射失败: {}", e))?
+            .reply()
+            .map_err(|e| format!("读取键盘映射失败: {}", e))?;
+        let per = mapping.keysyms_per_keycode as usize;
+        let keysyms_of = |keycode: Keycode| {
+            let start = (keycode - min) as usize * per;
+            &mapping.keysyms[start..start + per]
+        };
+
+        let spare: Vec<(Keycode, Keysym)> = (min..=max)
+            .filter(|&k| keysyms_of(k).iter().all(|&s| s == NO_SYMBOL))
+            .map(|k| (k, NO_SYMBOL))
+            .collect();
+        if spare.is_empty() {
+            return Err("键盘映射中没有空闲键码，无法输入".to_string());
+        }
+        let abort_keycode = abort_keysym(abort_key)
+            .and_then(|sym| (min..=max).find(|&k| keysyms_of(k).contains(&sym)));
+
+        Ok(Self {
+            conn,
+            root,
+            keysyms_per_keycode: mapping.keysyms_per_keycode,
+            spare,
+            next: 0,
+            abort_keycode,
+        })
+    }
+
+    fn remap(&self, keycode: Keycode, keysym: Keysym) -> Result<(), String> {
+        // 所有列（含 Shift 等）映射为同一字符，按键时不受修饰键状态影响
+        let keysyms = vec![keysym; self.keysyms_per_keycode as usize];
+        self.conn.change_keyboard_mapping(1, keycode, self.keysyms_per_keycode, &keysyms)
+            .map_err(|e| format!("修改键盘映射失败: {}", e))?;
+        self.sync()
+    }
+
+    // 等待显示服务器处理完已发送的请求
+    fn sync(&self) -> Result<(), String> {
+        self.conn.get_input_focus()
+            .map_err(|e| format!("X11 请求失败: {}", e))?
+            .reply()
+            .map(|_| ())
+            .map_err(|e| format!("X11 请求失败: {}", e))
+    }
+
+    // 获取映射为该字符的键码，没有时轮流重新映射一个空闲键码
+    fn keycode_for(&mut self, keysym: Keysym) -> Result<Keycode, String> {
+        if let Some(&(keycode, _)) = self.spare.iter().find(|(_, s)| *s == keysym) {
+            return Ok(keycode);
+        }
+        let index = self.next;
+        self.next = (self.next + 1) % self.spare.len();
+        let keycode = self.spare[index].0;
+        self.remap(keycode, keysym)?;
+        self.spare[index].1 = keysym;
+        Ok(keycode)
+    }
+
+    fn fake_key(&self, event: u8, keycode: Keycode) -> Result<(), String> {
+        self.conn.xtest_fake_input(event, keycode, CURRENT_TIME, self.root, 0, 0, 0)
+            .map_err(|e| format!("模拟按键失败: {}", e))?;
+        Ok(())
+    }
+}
+
+impl TextInjector for XTestInjector {
+    fn type_char(&mut self, c: char) -> Result<(), String> {
+        let keycode = self.keycode_for(keysym_for(c))?;
+        self.fake_key(KEY_PRESS_EVENT, keycode)?;
+        self.fake_key(KEY_RELEASE_EVENT, keycode)?;
+        self.sync()
+    }
+
+    fn abort_requested(&mut self) -> bool {
+        let Some(keycode) = self.abort_keycode else {
+            return false;
+        };
+        let Some(keymap) = self.conn.query_keymap().ok().and_then(|c| c.reply().ok()) else {
+            return false;
+        };
+        keymap.keys[keycode as usize / 8] & (1 << (keycode % 8)) != 0
+    }
+}
+
+impl Drop for XTestInjector {
+    // 恢复空闲键码（给目标程序留出处理最后几个按键的时间）
+    fn drop(&mut self) {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        for &(keycode, keysym) in &self.spare {
+            if keysym != NO_SYMBOL {
+                let _ = self.remap(keycode, NO_SYMBOL);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keysym_for_maps_latin1_directly_and_unicode_with_offset() {
+        let cases = [
+            ('a', 0x61),
+            ('Z', 0x5a),
+            (' ', 0x20),
+            ('~', 0x7e),
+            ('\u{a0}', 0xa0),
+            ('é', 0xe9),
+            ('ÿ', 0xff),
+            ('\n', XK_RETURN),
+            ('\r', XK_RETURN),
+            ('\t', XK_TAB),
+            ('€', 0x0100_20ac),
+            ('中', 0x0100_4e2d),
+            ('😀', 0x0101_f600),
+            // 控制字符不在 Latin-1 可打印范围内
+            ('\u{7f}', 0x0100_007f),
+        ];
+        for (c, expected) in cases {
+            assert_eq!(keysym_for(c), expected, "{:?}", c);
+        }
+    }
+
+    // 在 Xvfb 中输入文本，检查焦点窗口收到的按键映射到对应字符，输入完成后恢复键盘映射
+    // 运行：cargo test xvfb -- --ignored（需要安装 Xvfb）
+    #[test]
+    #[ignore = "需要 Xvfb"]
+    fn xvfb_types_unicode_through_spare_keycodes() {
+        use std::process::{Command, Stdio};
+        use std::time::{Duration, Instant};
+        use x11rb::protocol::xproto::{CreateWindowAux, EventMask, InputFocus, WindowClass};
+        use x11rb::protocol::Event;
+        use x11rb::COPY_DEPTH_FROM_PARENT;
+
+        // 测试结束（包括断言失败）时关闭 Xvfb
+        struct Xvfb(std::process::Child);
+        impl Drop for Xvfb {
+            fn drop(&mut self) {
+                let _ = self.0.kill();
+                let _ = self.0.wait();
+            }
+        }
+
+        let display = ":94";
+        let _xvfb = Xvfb(
+            Command::new("Xvfb")
+                .arg(display)
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+                .expect("启动 Xvfb 失败"),
+        );
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let (conn, screen_num) = loop {
+            match x11rb::connect(Some(display)) {
+                Ok(connection) => break connection,
+                Err(_) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(100)),
+                Err(e) => panic!("连接 Xvfb 失败: {}", e),
+            }
+        };
+        std::env::set_var("DISPLAY", display);
+
+        let screen = &conn.setup().roots[screen_num];
+        let window = conn.generate_id().unwrap();
+        let aux = CreateWindowAux::new().event_mask(EventMask::KEY_PRESS);
+        conn.create_window(
+            COPY_DEPTH_FROM_PARENT, window, screen.root, 0, 0, 100, 100, 0,
+            WindowClass::INPUT_OUTPUT, screen.root_visual, &aux,
+        ).unwrap();
+        conn.map_window(window).unwrap();
+        conn.get_input_focus().unwrap().reply().unwrap();
+        conn.set_input_focus(InputFocus::PARENT, window, CURRENT_TIME).unwrap();
+        conn.get_input_focus().unwrap().reply().unwrap();
+
+        let text = "aZé€中\n";
+        let mut injector = XTestInjector::new(AbortKey::Disabled).expect("Xvfb 不支持 XTest");
+        for c in text.chars() {
+            injector.type_char(c).unwrap();
+        }
+
+        let mut keycodes = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while keycodes.len() < text.chars().count() && Instant::now() < deadline {
+            match conn.poll_for_event().unwrap() {
+                Some(Event::KeyPress(event)) => keycodes.push(event.detail),
+                Some(_) => {}
+                None => std::thread::sleep(Duration::from_millis(10)),
+            }
+        }
+        assert_eq!(keycodes.len(), text.chars().count(), "焦点窗口没有收到全部按键");
+        for (keycode, c) in keycodes.iter().zip(text.chars()) {
+            let mapping = conn.get_keyboard_mapping(*keycode, 1).unwrap().reply().unwrap();
+            assert_eq!(mapping.keysyms[0], keysym_for(c), "{:?}", c);
+        }
+
+        drop(injector);
+        for keycode in keycodes {
+            let mapping = conn.get_keyboard_mapping(keycode, 1).unwrap().reply().unwrap();
+            assert!(mapping.keysyms.iter().all(|&s| s == NO_SYMBOL), "键码 {} 没有恢复", keycode);
+        }
+    }
+}
@@ -0,0 +1,569 @@
+// 插入配置 - 按当前焦点程序选择插入方式、粘贴快捷键、换行符和插入前后的按键
+// 焦点程序：Windows 使用前台窗口的类名和进程名，X11 使用活动窗口的 WM_CLASS 和进程名，
+// Wayland 通过合成器 IPC 获取（Hyprland、Sway），macOS 使用前台程序名，获取不到时使用默认配置
+// 配置匹配、换行符转换和按键解析为纯函数，不依赖当前桌面环境
+use enigo::Key;
+use serde::{Deserialize, Serialize};
+
+// 插入方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum InsertMethod {
+    // 通过剪贴板粘贴
+    #[default]
+    Paste,
+    // 逐字符模拟输入（见 inject.rs）
+    Simulate,
+}
+
+// 换行符转换
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LineEnding {
+    // 保持原样
+    #[default]
+    Keep,
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    pub fn apply(self, text: &str) -> String {
+        match self {
+            Self::Keep => text.to_string(),
+            Self::Lf => text.replace("\r\n", "\n"),
+            Self::Crlf => text.replace("\r\n", "\n").replace('\n', "\r\n"),
+        }
+    }
+}
+
+// 一个程序（或一组程序）的插入配置
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct InsertProfile {
+    pub name: String,
+    // 匹配的窗口类名或进程名（不区分大小写，可省略 .exe）
+    #[serde(default)]
+    pub apps: Vec<String>,
+    #[serde(default)]
+    pub method: InsertMethod,
+    // 粘贴快捷键，如 "Ctrl+Shift+V"
+    #[serde(default = "default_paste_chord")]
+    pub paste_chord: String,
+    // 获取选中文本（{{selection}}）时的复制快捷键，终端中 Ctrl+C 会中断前台进程
+    #[serde(default = "default_copy_chord")]
+    pub copy_chord: String,
+    #[serde(default)]
+    pub line_ending: LineEnding,
+    // 插入前依次按下的按键，如 ["End"]
+    #[serde(default)]
+    pub before_keys: Vec<String>,
+    // 插入后依次按下的按键，如 ["Enter"]
+    #[serde(default)]
+    pub after_keys: Vec<String>,
+}
+
+// 插入设置：未匹配任何配置时使用 paste_chord 粘贴
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct InsertConfig {
+    #[serde(default = "default_paste_chord")]
+    pub paste_chord: String,
+    #[serde(default = "default_profiles")]
+    pub profiles: Vec<InsertProfile>,
+}
+
+pub fn default_paste_chord() -> String {
+    if cfg!(target_os = "macos") { "Cmd+V" } else { "Ctrl+V" }.to_string()
+}
+
+// 获取选中文本时使用的复制快捷键
+pub fn default_copy_chord() -> String {
+    if cfg!(target_os = "macos") { "Cmd+C" } else { "Ctrl+C" }.to_string()
+}
+
+// 只修改粘贴和复制快捷键的配置
+fn paste_profile(name: &str, apps: &[&str], paste_chord: &str, copy_chord: &str) -> InsertProfile {
+    InsertProfile {
+        name: name.to_string(),
+        apps: apps.iter().map(|a| a.to_string()).collect(),
+        method: InsertMethod::Paste,
+        paste_chord: paste_chord.to_string(),
+        copy_chord: copy_chord.to_string(),
+        line_ending: LineEnding::Keep,
+        before_keys: Vec::new(),
+        after_keys: Vec::new(),
+    }
+}
+
+// 内置配置：Linux 终端使用 Ctrl+Shift+V / Ctrl+Shift+C，Windows 上的 mintty（Git Bash）使用 Shift+Insert / Ctrl+Insert
+fn default_profiles() -> Vec<InsertProfile> {
+    if cfg!(target_os = "linux") {
+        vec![paste_profile(
+            "终端",
+            &[
+                "gnome-terminal-server", "org.gnome.Console", "kgx", "konsole", "org.kde.konsole",
+                "xfce4-terminal", "tilix", "terminator", "kitty", "alacritty", "foot",
+                "org.wezfurlong.wezterm", "com.mitchellh.ghostty",
+            ],
+            "Ctrl+Shift+V",
+            "Ctrl+Shift+C",
+        )]
+    } else if cfg!(target_os = "windows") {
+        vec![paste_profile("mintty", &["mintty"], "Shift+Insert", "Ctrl+Insert")]
+    } else {
+        Vec::new()
+    }
+}
+
+impl Default for InsertConfig {
+    fn default() -> Self {
+        Self {
+            paste_chord: default_paste_chord(),
+            profiles: default_profiles(),
+        }
+    }
+}
+
+// 当前焦点程序
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FocusedApp {
+    // 窗口类名（X11 的 WM_CLASS 包含实例名和类名两项，Wayland 为 app_id）
+    pub classes: Vec<String>,
+    pub process: Option<String>,
+}
+
+// 比较时忽略大小写和 .exe 后缀
+fn normalize(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    name.strip_suffix(".exe").map(str::to_string).unwrap_or(name)
+}
+
+impl InsertProfile {
+    pub fn matches(&self, app: &FocusedApp) -> bool {
+        let names: Vec<String> = app.classes.iter().chain(&app.process).map(|n| normalize(n)).collect();
+        self.apps.iter().any(|a| names.contains(&normalize(a)))
+    }
+}
+
+impl InsertConfig {
+    // 选择第一个匹配焦点程序的配置，没有时使用默认配置
+    pub fn profile_for(&self, app: Option<&FocusedApp>) -> InsertProfile {
+        app.and_then(|app| self.profiles.iter().find(|p| p.matches(app)))
+            .cloned()
+            .unwrap_or_else(|| paste_profile("", &[], &self.paste_chord, &default_copy_chord()))
+    }
+
+    // 检查所有快捷键和按键能否识别（保存设置时调用）
+    pub fn validate(&self) -> Result<(), String> {
+        parse_chord(&self.paste_chord)?;
+        for profile in &self.profiles {
+            let check = || -> Result<(), String> {
+                parse_chord(&profile.paste_chord)?;
+                parse_chord(&profile.copy_chord)?;
+                parse_sequence(&profile.before_keys)?;
+                parse_sequence(&profile.after_keys)?;
+                Ok(())
+            };
+            check().map_err(|e| format!("插入配置 {}: {}", profile.name, e))?;
+        }
+        Ok(())
+    }
+}
+
+fn modifier_key(name: &str) -> Option<Key> {
+    match name {
+        "ctrl" | "control" => Some(Key::Control),
+        "shift" => Some(Key::Shift),
+        "alt" | "option" => Some(Key::Alt),
+        "cmd" | "command" | "super" | "meta" | "win" => Some(Key::Meta),
+        _ => None,
+    }
+}
+
+fn named_key(name: &str) -> Option<Key> {
+    let key = match name {
+        "enter" | "return" => Key::Return,
+        "tab" => Key::Tab,
+        "esc" | "escape" => Key::Escape,
+        "space" => Key::Space,
+        "backspace" => Key::Backspace,
+        "delete" | "del" => Key::Delete,
+        #[cfg(not(target_os = "macos"))]
+        "insert" | "ins" => Key::Insert,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" | "pgup" => Key::PageUp,
+        "pagedown" | "pgdn" => Key::PageDown,
+        "up" => Key::UpArrow,
+        "down" => Key::DownArrow,
+        "left" => Key::LeftArrow,
+        "right" => Key::RightArrow,
+        "f1" => Key::F1,
+        "f2" => Key::F2,
+        "f3" => Key::F3,
+        "f4" => Key::F4,
+        "f5" => Key::F5,
+        "f6" => Key::F6,
+        "f7" => Key::F7,
+        "f8" => Key::F8,
+        "f9" => Key::F9,
+        "f10" => Key::F10,
+        "f11" => Key::F11,
+        "f12" => Key::F12,
+        _ => {
+            let mut chars = name.chars();
+            return match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(Key::Unicode(c)),
+                _ => None,
+            };
+        }
+    };
+    Some(key)
+}
+
+// 解析组合键（如 "Ctrl+Shift+V"），返回修饰键和最后按下的键，按键名不区分大小写
+pub fn parse_chord(chord: &str) -> Result<Vec<Key>, String> {
+    let parts: Vec<String> = chord.split('+').map(|p| p.trim().to_lowercase()).collect();
+    let Some((last, modifiers)) = parts.split_last().filter(|(last, _)| !last.is_empty()) else {
+        return Err(format!("组合键格式错误: {}", chord));
+    };
+    let mut keys = modifiers
+        .iter()
+        .map(|m| modifier_key(m).ok_or_else(|| format!("无法识别的修饰键: {}", m)))
+        .collect::<Result<Vec<Key>, String>>()?;
+    let key = named_key(last)
+        .or_else(|| modifier_key(last))
+        .ok_or_else(|| format!("无法识别的按键: {}", last))?;
+    keys.push(key);
+    Ok(keys)
+}
+
+// 解析按键序列，每项为一个组合键
+pub fn parse_sequence(chords: &[String]) -> Result<Vec<Vec<Key>>, String> {
+    chords.iter().map(|c| parse_chord(c)).collect()
+}
+
+// 获取当前焦点程序（获取不到时返回 None）
+pub fn focused_app() -> Option<FocusedApp> {
+    #[cfg(target_os = "windows")]
+    {
+        windows_focused_app()
+    }
+    #[cfg(target_os = "linux")]
+    {
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            // XWayland 中只能看到 X11 窗口，不可靠
+            hyprland_focused_app().or_else(sway_focused_app)
+        } else {
+            x11_focused_app()
+        }
+    }
+    #[cfg(target_os = "macos")]
+    {
+        macos_focused_app()
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+    {
+        None
+    }
+}
+
+// Windows：前台窗口的类名和所属进程的可执行文件名
+#[cfg(target_os = "windows")]
+fn windows_focused_app() -> Option<FocusedApp> {
+    use windows::core::PWSTR;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{GetClassNameW, GetForegroundWindow, GetWindowThreadProcessId};
+
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.0.is_null() {
+            return None;
+        }
+        let mut class = [0u16; 256];
+        let len = GetClassNameW(hwnd, &mut class);
+        let classes = if len > 0 {
+            vec![String::from_utf16_lossy(&class[..len as usize])]
+        } else {
+            Vec::new()
+        };
+
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(hwnd, Some(&mut pid as *mut u32));
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok().and_then(|handle| {
+            let mut path = [0u16; 1024];
+            let mut size = path.len() as u32;
+            let result = QueryFullProcessImageNameW(handle, PROCESS_NAME_WIN32, PWSTR(path.as_mut_ptr()), &mut size);
+            let _ = CloseHandle(handle);
+            result.ok()?;
+            let path = String::from_utf16_lossy(&path[..size as usize]);
+            path.rsplit('\\').next().map(str::to_string)
+        });
+        Some(FocusedApp { classes, process })
+    }
+}
+
+// X11：根窗口 _NET_ACTIVE_WINDOW 指向的窗口的 WM_CLASS，进程名由 _NET_WM_PID 查 /proc
+#[cfg(target_os = "linux")]
+fn x11_focused_app() -> Option<FocusedApp> {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{AtomEnum, ConnectionExt};
+
+    let (conn, screen_num) = x11rb::connect(None).ok()?;
+    let root = conn.setup().roots.get(screen_num)?.root;
+    let atom = |name: &[u8]| conn.intern_atom(false, name).ok()?.reply().ok().map(|r| r.atom);
+    let (active_atom, pid_atom) = (atom(b"_NET_ACTIVE_WINDOW")?, atom(b"_NET_WM_PID")?);
+
+    let window = conn.get_property(false, root, active_atom, AtomEnum::WINDOW, 0, 1)
+        .ok()?.reply().ok()?
+        .value32()?.next()
+        .filter(|&w| w != 0)?;
+    let classes = conn.get_property(false, window, AtomEnum::WM_CLASS, AtomEnum::STRING, 0, 256)
+        .ok()
+        .and_then(|c| c.reply().ok())
+        .map(|r| parse_wm_class(&r.value))
+        .unwrap_or_default();
+    let process = conn.get_property(false, window, pid_atom, AtomEnum::CARDINAL, 0, 1)
+        .ok()
+        .and_then(|c| c.reply().ok())
+        .and_then(|r| r.value32()?.next())
+        .and_then(process_name);
+    Some(FocusedApp { classes, process })
+}
+
+// WM_CLASS 为以 \0 分隔的实例名和类名
+#[cfg(target_os = "linux")]
+fn parse_wm_class(value: &[u8]) -> Vec<String> {
+    value.split(|&b| b == 0)
+        .filter(|s| !s.is_empty())
+        .map(|s| String::from_utf8_lossy(s).to_string())
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+fn process_name(pid: u32) -> Option<String> {
+    let comm = std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok()?;
+    Some(comm.trim().to_string()).filter(|c| !c.is_empty())
+}
+
+// Hyprland：hyprctl activewindow -j
+#[cfg(target_os = "linux")]
+fn hyprland_focused_app() -> Option<FocusedApp> {
+    std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE")?;
+    let output = std::process::Command::new("hyprctl").args(["activewindow", "-j"]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let window: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
+    app_from_json(&window, &["class", "initialClass"])
+}
+
+// Sway：swaymsg -t get_tree，查找 focused 为 true 的窗口
+#[cfg(target_os = "linux")]
+fn sway_focused_app() -> Option<FocusedApp> {
+    std::env::var_os("SWAYSOCK")?;
+    let output = std::process::Command::new("swaymsg").args(["-t", "get_tree"]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let tree: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
+    let node = find_focused_node(&tree)?;
+    // XWayland 窗口没有 app_id，类名在 window_properties 中
+    let mut app = app_from_json(node, &["app_id"])?;
+    if let Some(class) = node.pointer("/window_properties/class").and_then(|c| c.as_str()) {
+        app.classes.push(class.to_string());
+    }
+    Some(app)
+}
+
+// 从合成器返回的窗口信息中读取类名字段和 pid
+#[cfg(target_os = "linux")]
+fn app_from_json(window: &serde_json::Value, class_fields: &[&str]) -> Option<FocusedApp> {
+    let classes = class_fields.iter()
+        .filter_map(|f| window.get(f)?.as_str())
+        .filter(|c| !c.is_empty())
+        .map(str::to_string)
+        .collect();
+    let process = window.get("pid")
+        .and_then(|p| p.as_u64())
+        .and_then(|pid| process_name(pid as u32));
+    Some(FocusedApp { classes, process })
+}
+
+// 在 Sway 的窗口树中查找获得焦点的节点
+#[cfg(target_os = "linux")]
+fn find_focused_node(node: &serde_json::Value) -> Option<&serde_json::Value> {
+    if node.get("focused").and_then(|f| f.as_bool()) == Some(true) {
+        return Some(node);
+    }
+    ["nodes", "floating_nodes"].iter()
+        .filter_map(|key| node.get(key)?.as_array())
+        .flatten()
+        .find_map(find_focused_node)
+}
+
+// macOS：通过 System Events 获取前台程序名（首次使用需要授予自动化权限）
+#[cfg(target_os = "macos")]
+fn macos_focused_app() -> Option<FocusedApp> {
+    let output = std::process::Command::new("osascript")
+        .args(["-e", "tell application \"System Events\" to get name of first process whose frontmost is true"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Some(FocusedApp { classes: Vec::new(), process: Some(name).filter(|n| !n.is_empty()) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(classes: &[&str], process: Option<&str>) -> FocusedApp {
+        FocusedApp {
+            classes: classes.iter().map(|c| c.to_string()).collect(),
+            process: process.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn parses_chords_case_insensitively() {
+        let cases = [
+            ("Ctrl+Shift+V", vec![Key::Control, Key::Shift, Key::Unicode('v')]),
+            ("CTRL+sHiFt+v", vec![Key::Control, Key::Shift, Key::Unicode('v')]),
+            ("control + V", vec![Key::Control, Key::Unicode('v')]),
+            ("cmd+v", vec![Key::Meta, Key::Unicode('v')]),
+            ("Command+V", vec![Key::Meta, Key::Unicode('v')]),
+            ("Super+Win+Meta+V", vec![Key::Meta, Key::Meta, Key::Meta, Key::Unicode('v')]),
+            ("Option+Alt+Enter", vec![Key::Alt, Key::Alt, Key::Return]),
+            ("Shift+F12", vec![Key::Shift, Key::F12]),
+            ("Return", vec![Key::Return]),
+            ("PgDn", vec![Key::PageDown]),
+            ("Ctrl+é", vec![Key::Control, Key::Unicode('é')]),
+            // 单独的修饰键可以作为最后一个键
+            ("Shift", vec![Key::Shift]),
+        ];
+        for (chord, keys) in cases {
+            assert_eq!(parse_chord(chord), Ok(keys), "{}", chord);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_chords() {
+        for chord in ["", " ", "+", "Ctrl+", "Ctrl++", "+V", "V+Ctrl", "Ctrl+Foo", "Hyper+V", "Ctrl+VV", "Ctrl+F13", "Ctrl V"] {
+            assert!(parse_chord(chord).is_err(), "{:?} 应被拒绝", chord);
+        }
+        let keys = vec!["End".to_string(), "Ctrl+Bogus".to_string()];
+        assert!(parse_sequence(&keys).is_err());
+        assert_eq!(parse_sequence(&["End".to_string(), "enter".to_string()]), Ok(vec![vec![Key::End], vec![Key::Return]]));
+    }
+
+    #[test]
+    fn validate_reports_profile_name() {
+        assert_eq!(InsertConfig::default().validate(), Ok(()));
+        let mut config = InsertConfig { paste_chord: "Ctrl+V".to_string(), profiles: Vec::new() };
+        config.profiles.push(InsertProfile {
+            after_keys: vec!["Entr".to_string()],
+            ..paste_profile("聊天", &["slack"], "Ctrl+V", "Ctrl+C")
+        });
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("聊天") && err.contains("entr"), "{}", err);
+        config.profiles.clear();
+        config.paste_chord = "Ctrl+".to_string();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn converts_line_endings() {
+        let text = "a\nb\r\nc\rd\n";
+        assert_eq!(LineEnding::Keep.apply(text), text);
+        assert_eq!(LineEnding::Lf.apply(text), "a\nb\nc\rd\n");
+        // 已经是 CRLF 的不会变成 \r\r\n，单独的 \r 保留
+        assert_eq!(LineEnding::Crlf.apply(text), "a\r\nb\r\nc\rd\r\n");
+        assert_eq!(LineEnding::Crlf.apply("\r\n\r\n"), "\r\n\r\n");
+        assert_eq!(LineEnding::Crlf.apply(""), "");
+    }
+
+    #[test]
+    fn matches_profiles_by_class_or_process() {
+        let config = InsertConfig {
+            paste_chord: "Ctrl+V".to_string(),
+            profiles: vec![
+                paste_profile("终端", &["kitty", "Gnome-Terminal-Server"], "Ctrl+Shift+V", "Ctrl+Shift+C"),
+                paste_profile("mintty", &["mintty.exe"], "Shift+Insert", "Ctrl+Insert"),
+                paste_profile("也匹配 kitty", &["KITTY"], "Ctrl+Alt+V", "Ctrl+C"),
+            ],
+        };
+        let chord = |app: Option<&FocusedApp>| config.profile_for(app).paste_chord;
+        // 类名或进程名任一匹配，不区分大小写
+        assert_eq!(chord(Some(&app(&["gnome-terminal-server", "Gnome-terminal"], None))), "Ctrl+Shift+V");
+        assert_eq!(chord(Some(&app(&[], Some("Kitty")))), "Ctrl+Shift+V");
+        // .exe 后缀在两边都可省略
+        assert_eq!(chord(Some(&app(&[], Some("mintty")))), "Shift+Insert");
+        assert_eq!(chord(Some(&app(&["MinTTY.EXE"], None))), "Shift+Insert");
+        // 不做部分匹配
+        assert_eq!(chord(Some(&app(&["gnome-terminal"], Some("kitty-helper")))), "Ctrl+V");
+        assert_eq!(chord(Some(&app(&[], None))), "Ctrl+V");
+        assert_eq!(chord(None), "Ctrl+V");
+
+        assert_eq!(config.profile_for(Some(&app(&[], Some("kitty")))).copy_chord, "Ctrl+Shift+C");
+        let fallback = config.profile_for(None);
+        assert_eq!(fallback.copy_chord, default_copy_chord());
+        assert_eq!(fallback.method, InsertMethod::Paste);
+        assert!(fallback.apps.is_empty() && fallback.before_keys.is_empty() && fallback.after_keys.is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn builtin_terminal_profile_does_not_copy_with_ctrl_c() {
+        let config = InsertConfig::default();
+        let profile = config.profile_for(Some(&app(&["kitty"], Some("kitty"))));
+        assert_eq!(profile.name, "终端");
+        assert_eq!(profile.copy_chord, "Ctrl+Shift+C");
+        assert_eq!(config.profile_for(Some(&app(&["firefox"], None))).copy_chord, "Ctrl+C");
+    }
+
+    #[test]
+    fn deserializes_partial_config() {
+        let config: InsertConfig = serde_json::from_str(
+            r#"{"profiles":[{"name":"x","apps":["a"],"method":"simulate","line_ending":"crlf"}]}"#,
+        ).unwrap();
+        assert_eq!(config.paste_chord, default_paste_chord());
+        let profile = &config.profiles[0];
+        assert_eq!(profile.method, InsertMethod::Simulate);
+        assert_eq!(profile.line_ending, LineEnding::Crlf);
+        assert_eq!(profile.paste_chord, default_paste_chord());
+        assert_eq!(profile.copy_chord, default_copy_chord());
+        // 未设置 profiles 时使用内置配置
+        let config: InsertConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(config, InsertConfig::default());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn parses_compositor_window_info() {
+        assert_eq!(parse_wm_class(b"kitty\0kitty\0"), ["kitty", "kitty"]);
+        assert_eq!(parse_wm_class(b"\0Navigator\0firefox"), ["Navigator", "firefox"]);
+        assert!(parse_wm_class(b"").is_empty());
+
+        let tree = serde_json::json!({
+            "focused": false,
+            "nodes": [
+                {"focused": false, "nodes": [{"focused": false, "app_id": "kitty"}]},
+                {"nodes": [], "floating_nodes": [{"focused": true, "app_id": "foot", "pid": 1}]},
+            ],
+        });
+        let node = find_focused_node(&tree).unwrap();
+        assert_eq!(node["app_id"], "foot");
+        assert!(find_focused_node(&serde_json::json!({"nodes": []})).is_none());
+
+        let window = serde_json::json!({"class": "org.wezfurlong.wezterm", "initialClass": "", "pid": "x"});
+        assert_eq!(
+            app_from_json(&window, &["class", "initialClass"]),
+            Some(app(&["org.wezfurlong.wezterm"], None))
+        );
+    }
+}
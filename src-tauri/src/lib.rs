@@ -1,14 +1,47 @@
 // 提示词管理器 - Rust 后端
+mod api;
+mod cli;
+mod clipboard;
+mod crypto;
+mod git;
+mod history;
+mod inject;
+#[cfg(target_os = "linux")]
+mod inject_uinput;
+#[cfg(target_os = "linux")]
+mod inject_x11;
+mod insert_profile;
+mod library;
+mod mcp;
+mod metadata;
+mod mnemonic;
+mod screen;
+mod search;
+mod search_index;
+mod ssh;
+mod sync;
+mod template;
+mod trash;
+mod usage;
+mod watcher;
+mod webdav;
+
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tauri::{
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
     Manager, WindowEvent,
 };
 use walkdir::WalkDir;
 
-// 提示词数据结构
+use library::LibraryIndex;
+pub use metadata::PromptMetadata;
+use watcher::LibraryState;
+
+// 提示词数据结构（content 为去掉 front matter 后的正文）
+// rel_path 为相对提示词库根目录的路径，file_path 为运行时解析出的绝对路径
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Prompt {
     pub id: String,
@@ -16,6 +49,10 @@ pub struct Prompt {
     pub content: String,
     pub category: String,
     pub file_path: String,
+    #[serde(default)]
+    pub rel_path: String,
+    #[serde(default)]
+    pub metadata: PromptMetadata,
 }
 
 // 应用配置
@@ -27,6 +64,27 @@ pub struct AppConfig {
     pub theme: String,
     #[serde(default)]
     pub autostart: bool,
+    #[serde(default)]
+    pub git: git::GitConfig,
+    #[serde(default)]
+    pub remote_sync: ssh::RemoteSyncConfig,
+    #[serde(default)]
+    pub webdav: webdav::WebDavConfig,
+    // 同步时加密（远程只保存密文），需先设置加密密码并解锁
+    #[serde(default)]
+    pub sync_encryption: bool,
+    // 本地 HTTP 接口（供编辑器插件和脚本使用）
+    #[serde(default)]
+    pub api: api::ApiConfig,
+    // 模拟输入（逐字符输入）的输入方式、字符间隔和中止键
+    #[serde(default)]
+    pub simulate: inject::SimulateConfig,
+    // 粘贴插入的等待时间，以及插入后是否恢复原来的剪贴板内容
+    #[serde(default)]
+    pub clipboard: clipboard::ClipboardConfig,
+    // 粘贴快捷键，以及按焦点程序选择的插入配置
+    #[serde(default)]
+    pub insert: insert_profile::InsertConfig,
 }
 
 fn default_theme() -> String {
@@ -40,17 +98,30 @@ impl Default for AppConfig {
             hotkey: "Alt+Space".to_string(),
             theme: "dark".to_string(),
             autostart: false,
+            git: git::GitConfig::default(),
+            remote_sync: ssh::RemoteSyncConfig::default(),
+            webdav: webdav::WebDavConfig::default(),
+            sync_encryption: false,
+            api: api::ApiConfig::default(),
+            simulate: inject::SimulateConfig::default(),
+            clipboard: clipboard::ClipboardConfig::default(),
+            insert: insert_profile::InsertConfig::default(),
         }
     }
 }
 
-// 获取配置文件路径
-fn get_config_path() -> PathBuf {
+// 获取配置目录
+fn get_config_dir() -> PathBuf {
     let config_dir = dirs::config_dir()
         .unwrap_or_else(|| PathBuf::from("."))
         .join("prompt-manager");
     fs::create_dir_all(&config_dir).ok();
-    config_dir.join("config.json")
+    config_dir
+}
+
+// 获取配置文件路径
+fn get_config_path() -> PathBuf {
+    get_config_dir().join("config.json")
 }
 
 // 获取配置文件路径（供前端显示）
@@ -73,168 +144,439 @@ fn get_config() -> Result<AppConfig, String> {
     }
 }
 
-// 保存配置
+// 保存配置（提示词文件夹变化时重新监听，启用 Git 模式时提交现有内容）
 #[tauri::command]
-fn save_config(config: AppConfig) -> Result<(), String> {
+fn save_config(app: tauri::AppHandle, config: AppConfig) -> Result<(), String> {
+    config.insert.validate()?;
+    let old = get_config().unwrap_or_default();
+    let old_folder = old.prompts_folder;
     let config_path = get_config_path();
     let content = serde_json::to_string_pretty(&config)
         .map_err(|e| format!("序列化配置失败: {}", e))?;
     fs::write(&config_path, content)
-        .map_err(|e| format!("保存配置失败: {}", e))
+        .map_err(|e| format!("保存配置失败: {}", e))?;
+
+    if config.prompts_folder != old_folder && !config.prompts_folder.is_empty() {
+        if let Err(e) = watcher::watch_library(&app, PathBuf::from(&config.prompts_folder)) {
+            eprintln!("{}", e);
+        }
+    }
+
+    let git_enabled = config.git.enabled && !old.git.enabled;
+    if git_enabled && !config.prompts_folder.is_empty() {
+        let root = PathBuf::from(&config.prompts_folder);
+        git::commit(&root, &[&root], "初始化提示词库", false)?;
+    }
+
+    if config.api != old.api {
+        api::apply(&app, &config.api)?;
+    }
+    Ok(())
 }
 
-// ====== 助记词元数据 ======
+// ====== 本地接口 ======
 
-// 助记词映射数据结构
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
-pub struct PromptsMeta {
-    // mnemonic -> file_path
-    pub mnemonics: std::collections::HashMap<String, String>,
+// 获取本地接口令牌文件路径
+fn get_api_token_path() -> PathBuf {
+    get_config_dir().join("api-token")
 }
 
-// 获取元数据文件路径
-fn get_meta_path() -> PathBuf {
-    let config_dir = dirs::config_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("prompt-manager");
-    fs::create_dir_all(&config_dir).ok();
-    config_dir.join("prompts-meta.json")
+// 获取本地接口令牌（不存在时生成）
+#[tauri::command]
+fn get_api_token() -> Result<String, String> {
+    api::load_or_create_token(&get_api_token_path())
 }
 
-// 读取助记词元数据
+// 重新生成本地接口令牌
 #[tauri::command]
-fn get_prompts_meta() -> Result<PromptsMeta, String> {
-    let meta_path = get_meta_path();
-    if meta_path.exists() {
-        let content = fs::read_to_string(&meta_path)
-            .map_err(|e| format!("读取元数据失败: {}", e))?;
-        serde_json::from_str(&content)
-            .map_err(|e| format!("解析元数据失败: {}", e))
-    } else {
-        Ok(PromptsMeta::default())
+fn regenerate_api_token() -> Result<String, String> {
+    api::regenerate_token(&get_api_token_path())
+}
+
+// ====== 助记词元数据 ======
+// 助记词保存在各提示词文件的 front matter 中
+
+// 旧版助记词元数据文件路径（仅用于迁移）
+fn get_meta_path() -> PathBuf {
+    get_config_dir().join("prompts-meta.json")
+}
+
+// ====== 搜索索引 ======
+
+// 获取搜索缓存文件路径
+fn get_search_cache_path() -> PathBuf {
+    get_config_dir().join("prompts-cache.json")
+}
+
+// 从内存中的搜索索引读取提示词库（索引加载或刷新后会为缺少 ID 的提示词写入 ID）
+fn indexed_files(app: &tauri::AppHandle) -> Result<Vec<library::ScannedFile>, String> {
+    let root = library_root()?;
+    Ok(app.state::<LibraryState>().with_index(&root, |index| index.files()))
+}
+
+// ====== 提示词 ID 索引 ======
+
+// 获取 ID 索引文件路径
+fn get_index_path() -> PathBuf {
+    get_config_dir().join("prompts-index.json")
+}
+
+// 当前提示词库根目录
+fn library_root() -> Result<PathBuf, String> {
+    let config = get_config()?;
+    if config.prompts_folder.is_empty() {
+        return Err("请先选择提示词文件夹".to_string());
     }
+    Ok(PathBuf::from(config.prompts_folder))
 }
 
-// 保存助记词元数据
-fn save_prompts_meta(meta: &PromptsMeta) -> Result<(), String> {
-    let meta_path = get_meta_path();
-    let content = serde_json::to_string_pretty(meta)
-        .map_err(|e| format!("序列化元数据失败: {}", e))?;
-    fs::write(&meta_path, content)
-        .map_err(|e| format!("保存元数据失败: {}", e))
+// 解析前端传入的路径：相对路径视为相对提示词库根目录
+fn resolve_library_path(path: &str) -> Result<PathBuf, String> {
+    if Path::new(path).is_absolute() {
+        return Ok(PathBuf::from(path));
+    }
+    Ok(library::to_absolute(&library_root()?, path))
 }
 
-// 设置助记词
-#[tauri::command]
-fn set_mnemonic(mnemonic: String, file_path: String) -> Result<(), String> {
-    let mnemonic = mnemonic.trim().to_lowercase();
-    if mnemonic.is_empty() {
-        return Err("助记词不能为空".to_string());
+// 加载当前提示词库的 ID 索引
+fn load_index() -> Result<LibraryIndex, String> {
+    LibraryIndex::load(&get_index_path(), &library_root()?)
+}
+
+// 扫描提示词库并同步 ID 索引
+fn scan_library(folder: &Path) -> Result<Vec<library::ScannedFile>, String> {
+    let index_path = get_index_path();
+    let mut index = LibraryIndex::load(&index_path, folder)?;
+    let result = library::scan(folder, &mut index, true);
+    report_skipped(&result.skipped);
+    for r in &result.relocations {
+        println!("检测到提示词移动: {} -> {}", r.from, r.to);
+    }
+    index.save(&index_path)?;
+    Ok(result.files)
+}
+
+// 只读取提示词库，不分配 ID、不修改索引（只读场景使用）
+fn read_library(folder: &Path) -> Result<Vec<library::ScannedFile>, String> {
+    let mut index = LibraryIndex::load(&get_index_path(), folder)?;
+    let result = library::scan(folder, &mut index, false);
+    report_skipped(&result.skipped);
+    Ok(result.files)
+}
+
+// 提示无法读取或解析而跳过的文件（这些文件保持原样）
+fn report_skipped(skipped: &[(String, String)]) {
+    for (rel_path, reason) in skipped {
+        eprintln!("跳过提示词 {}: {}", rel_path, reason);
     }
+}
 
-    let mut meta = get_prompts_meta()?;
+// 修改 ID 索引并保存
+fn update_index(f: impl FnOnce(&mut LibraryIndex)) -> Result<(), String> {
+    let mut index = load_index()?;
+    f(&mut index);
+    index.save(&get_index_path())
+}
 
-    // 检查是否已被其他文件使用
-    if let Some(existing_path) = meta.mnemonics.get(&mnemonic) {
-        if existing_path != &file_path {
-            return Err(format!("助记词 '{}' 已被其他文件使用", mnemonic));
+// 根据 ID 查找提示词文件（索引过期时重新扫描提示词库）
+fn resolve_prompt_path(id: &str) -> Result<PathBuf, String> {
+    let index = load_index()?;
+    if let Some(path) = index.resolve(id) {
+        if let Ok((meta, _)) = metadata::read_prompt_file(&path) {
+            if meta.id.as_deref() == Some(id) {
+                return Ok(path);
+            }
         }
     }
 
-    // 移除该文件之前的助记词（如果有）
-    meta.mnemonics.retain(|_, v| v != &file_path);
+    scan_library(&library_root()?)?
+        .into_iter()
+        .find(|f| f.id() == id)
+        .map(|f| f.path)
+        .ok_or_else(|| "提示词不存在".to_string())
+}
 
-    // 设置新助记词
-    meta.mnemonics.insert(mnemonic, file_path);
-    save_prompts_meta(&meta)
+// 检查助记词在提示词所在文件夹（命名空间）内是否已被其他提示词使用
+fn check_mnemonic_conflicts(
+    files: &[library::ScannedFile],
+    file: &library::ScannedFile,
+    mnemonics: &[String],
+) -> Result<(), String> {
+    let namespace = mnemonic::namespace_of(&file.rel_path);
+    for m in mnemonics {
+        if let Some(other) = mnemonic::find_conflict(files, &namespace, m, file.id()) {
+            return Err(format!(
+                "助记词 '{}' 已被同一文件夹中的 '{}' 使用",
+                m,
+                library::file_title(&other.path)
+            ));
+        }
+    }
+    Ok(())
 }
 
-// 删除助记词
-#[tauri::command]
-fn remove_mnemonic(file_path: String) -> Result<(), String> {
-    let mut meta = get_prompts_meta()?;
-    meta.mnemonics.retain(|_, v| v != &file_path);
-    save_prompts_meta(&meta)
+// 根据当前元数据计算新的助记词列表并写入文件
+fn update_mnemonics(
+    id: &str,
+    f: impl FnOnce(&PromptMetadata) -> Vec<String>,
+) -> Result<(), String> {
+    let files = scan_library(&library_root()?)?;
+    let file = files.iter().find(|f| f.id() == id).ok_or("提示词不存在")?;
+
+    let mnemonics = f(&file.metadata);
+    if mnemonics == file.metadata.mnemonics() {
+        return Ok(());
+    }
+    check_mnemonic_conflicts(&files, file, &mnemonics)?;
+
+    let mut meta = file.metadata.clone();
+    meta.set_mnemonics(mnemonics);
+    metadata::write_prompt_file(&file.path, &meta, &file.body)?;
+    update_index(|index| index.update(id, &file.path, &meta, &file.body))?;
+    git_commit("修改助记词", &[&file.path]);
+    Ok(())
 }
 
-// 通过助记词查找文件路径
+// 设置主助记词（保留其他别名）
 #[tauri::command]
-fn find_by_mnemonic(mnemonic: String) -> Result<Option<String>, String> {
-    let mnemonic = mnemonic.trim().to_lowercase();
-    let meta = get_prompts_meta()?;
-    Ok(meta.mnemonics.get(&mnemonic).cloned())
+fn set_mnemonic(mnemonic: String, id: String) -> Result<(), String> {
+    let mnemonic = mnemonic::validate(&mnemonic)?;
+    update_mnemonics(&id, |meta| {
+        let aliases = meta.mnemonics().into_iter()
+            .skip(usize::from(meta.mnemonic.is_some()))
+            .filter(|m| m != &mnemonic);
+        std::iter::once(mnemonic.clone()).chain(aliases).collect()
+    })
 }
 
-// 获取文件的助记词
+// 设置全部助记词：第一个为主助记词，其余为别名
 #[tauri::command]
-fn get_mnemonic_for_file(file_path: String) -> Result<Option<String>, String> {
-    let meta = get_prompts_meta()?;
-    for (m, p) in &meta.mnemonics {
-        if p == &file_path {
-            return Ok(Some(m.clone()));
+fn set_mnemonics(mnemonics: Vec<String>, id: String) -> Result<(), String> {
+    let mut list: Vec<String> = Vec::new();
+    for m in &mnemonics {
+        let m = mnemonic::validate(m)?;
+        if !list.contains(&m) {
+            list.push(m);
         }
     }
-    Ok(None)
+    update_mnemonics(&id, |_| list)
+}
+
+// 删除提示词的全部助记词
+#[tauri::command]
+fn remove_mnemonic(id: String) -> Result<(), String> {
+    update_mnemonics(&id, |_| Vec::new())
+}
+
+// 通过助记词查找提示词
+// mnemonic 可写为 work/sum 指定文件夹；scope 为当前所在文件夹（相对路径），从该处逐级向上查找
+#[tauri::command]
+fn find_by_mnemonic(
+    app: tauri::AppHandle,
+    mnemonic: String,
+    scope: Option<String>,
+) -> Result<mnemonic::MnemonicLookup, String> {
+    let config = get_config()?;
+    if config.prompts_folder.is_empty() {
+        return Ok(mnemonic::MnemonicLookup::NotFound);
+    }
+
+    let files = indexed_files(&app)?;
+    Ok(mnemonic::resolve(&files, &mnemonic, scope.as_deref()))
+}
+
+// 获取提示词的主助记词
+#[tauri::command]
+fn get_mnemonic(id: String) -> Result<Option<String>, String> {
+    let (meta, _) = metadata::read_prompt_file(&resolve_prompt_path(&id)?)?;
+    Ok(meta.mnemonic)
+}
+
+// 获取提示词的全部助记词（主助记词在前）
+#[tauri::command]
+fn get_mnemonics(id: String) -> Result<Vec<String>, String> {
+    let (meta, _) = metadata::read_prompt_file(&resolve_prompt_path(&id)?)?;
+    Ok(meta.mnemonics())
+}
+
+// 列出孤立的助记词（文件被外部删除或 front matter 被清除）及可重新关联的候选文件
+#[tauri::command]
+fn repair_mnemonics() -> Result<Vec<library::OrphanedMnemonic>, String> {
+    let config = get_config()?;
+    if config.prompts_folder.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let files = scan_library(Path::new(&config.prompts_folder))?;
+    let index = load_index()?;
+    Ok(library::find_orphans(&index, &files))
+}
+
+// 将孤立的助记词重新关联到指定提示词（恢复原 ID，保持引用不变）
+#[tauri::command]
+fn relink_mnemonic(orphan_id: String, target_id: String) -> Result<(), String> {
+    let files = scan_library(&library_root()?)?;
+    let mut index = load_index()?;
+    library::relink(&mut index, &files, &orphan_id, &target_id)?;
+    index.save(&get_index_path())
+}
+
+// 放弃孤立的助记词
+#[tauri::command]
+fn discard_orphaned_mnemonic(orphan_id: String) -> Result<(), String> {
+    update_index(|index| {
+        if index.prompts.get(&orphan_id).is_some_and(|e| e.orphaned) {
+            index.prompts.remove(&orphan_id);
+        }
+    })
 }
 
 // 助记词列表项
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MnemonicItem {
+    pub id: String,
     pub mnemonic: String,
+    pub aliases: Vec<String>,
+    // 所在文件夹（命名空间）
+    pub namespace: String,
     pub title: String,
     pub content: String,
 }
 
 // 获取所有提示词（用于快速插入弹窗，支持全文检索）
+// 从内存中的搜索索引读取，不再每次遍历提示词库
 #[tauri::command]
-fn get_all_mnemonics() -> Result<Vec<MnemonicItem>, String> {
+fn get_all_mnemonics(app: tauri::AppHandle) -> Result<Vec<MnemonicItem>, String> {
     let config = get_config()?;
     if config.prompts_folder.is_empty() {
         return Ok(Vec::new());
     }
 
-    let meta = get_prompts_meta()?;
     let mut items = Vec::new();
 
-    // 遍历所有 md 文件
-    for entry in WalkDir::new(&config.prompts_folder)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().extension().map_or(false, |ext| ext == "md" || ext == "txt"))
-    {
-        let path = entry.path();
-        let file_path = path.to_string_lossy().to_string();
-        let title = path.file_stem()
-            .map(|s| s.to_string_lossy().to_string())
-            .unwrap_or_default();
-        let content = std::fs::read_to_string(path).unwrap_or_default();
-
-        // 查找该文件的助记词
-        let mnemonic = meta.mnemonics.iter()
-            .find(|(_, p)| *p == &file_path)
-            .map(|(m, _)| m.clone())
-            .unwrap_or_default();
-
+    for file in indexed_files(&app)? {
+        let mut mnemonics = file.metadata.mnemonics().into_iter();
         items.push(MnemonicItem {
-            mnemonic,
-            title,
-            content,
+            id: file.id().to_string(),
+            title: library::file_title(&file.path),
+            mnemonic: mnemonics.next().unwrap_or_default(),
+            aliases: mnemonics.collect(),
+            namespace: mnemonic::namespace_of(&file.rel_path),
+            content: file.body,
         });
     }
 
-    // 按标题排序
-    items.sort_by(|a, b| a.title.cmp(&b.title));
+    // 置顶的在前，其余按 frecency、标题排序
+    let usage = usage::UsageLog::load(&get_usage_path());
+    let now = usage::now();
+    items.sort_by(|a, b| {
+        usage.is_pinned(&b.id).cmp(&usage.is_pinned(&a.id))
+            .then_with(|| usage.frecency(&b.id, now).total_cmp(&usage.frecency(&a.id, now)))
+            .then_with(|| a.title.cmp(&b.title))
+    });
+    Ok(items)
+}
+
+// 快速插入弹窗搜索：助记词、标题（含拼音首字母）和正文的模糊匹配，按相关度排序
+#[tauri::command]
+fn search_prompts(
+    app: tauri::AppHandle,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<search::SearchResult>, String> {
+    let config = get_config()?;
+    if config.prompts_folder.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let files = indexed_files(&app)?;
+    let usage = usage::UsageLog::load(&get_usage_path());
+    Ok(search::search(&files, &query, limit.unwrap_or(50), &usage))
+}
+
+// ====== 使用记录 ======
+
+// 获取使用记录文件路径
+fn get_usage_path() -> PathBuf {
+    get_config_dir().join("usage.json")
+}
+
+// 修改使用记录并保存
+fn update_usage(f: impl FnOnce(&mut usage::UsageLog)) -> Result<(), String> {
+    let path = get_usage_path();
+    let mut log = usage::UsageLog::load(&path);
+    f(&mut log);
+    log.save(&path)
+}
+
+// 记录一次插入或复制（失败不影响插入本身）
+fn record_usage(id: Option<&str>) {
+    let Some(id) = id.filter(|id| !id.is_empty()) else {
+        return;
+    };
+    if let Err(e) = update_usage(|log| log.record(id, usage::now())) {
+        eprintln!("{}", e);
+    }
+}
+
+// 使用统计
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UsageItem {
+    pub id: String,
+    pub title: String,
+    pub count: u32,
+    pub last_used: Option<String>,
+    pub pinned: bool,
+    pub frecency: f64,
+}
+
+// 置顶 / 取消置顶提示词
+#[tauri::command]
+fn pin_prompt(id: String, pinned: bool) -> Result<(), String> {
+    update_usage(|log| log.set_pinned(&id, pinned))
+}
+
+// 查看使用统计（按 frecency 排序）
+#[tauri::command]
+fn get_usage_stats(app: tauri::AppHandle) -> Result<Vec<UsageItem>, String> {
+    let titles: HashMap<String, String> = indexed_files(&app)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|f| (f.id().to_string(), library::file_title(&f.path)))
+        .collect();
+
+    let now = usage::now();
+    let mut items: Vec<UsageItem> = usage::UsageLog::load(&get_usage_path())
+        .prompts
+        .into_iter()
+        .map(|(id, stats)| UsageItem {
+            title: titles.get(&id).cloned().unwrap_or_default(),
+            count: stats.count,
+            last_used: stats.last_used()
+                .and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+                .map(|t| t.with_timezone(&chrono::Local).to_rfc3339_opts(chrono::SecondsFormat::Secs, false)),
+            pinned: stats.pinned,
+            frecency: stats.frecency(now),
+            id,
+        })
+        .collect();
+    items.sort_by(|a, b| b.pinned.cmp(&a.pinned).then_with(|| b.frecency.total_cmp(&a.frecency)));
     Ok(items)
 }
 
+// 清空使用统计（保留置顶）；id 为空时清空全部
+#[tauri::command]
+fn reset_usage_stats(id: Option<String>) -> Result<(), String> {
+    update_usage(|log| log.reset(id.as_deref()))
+}
+
 // 显示快速插入弹窗
 #[tauri::command]
 async fn show_popup(app: tauri::AppHandle) -> Result<(), String> {
     use tauri::Manager;
 
     // 获取鼠标位置并计算弹窗位置
-    let (cursor_x, cursor_y) = get_cursor_position();
-    let (x, y) = calculate_popup_position(cursor_x, cursor_y);
+    let (x, y) = popup_position(&app);
 
     if let Some(popup) = app.get_webview_window("popup") {
         // 设置位置并显示
@@ -255,83 +597,32 @@ async fn hide_popup(app: tauri::AppHandle) -> Result<(), String> {
     Ok(())
 }
 
-// 获取当前鼠标位置 (Windows)
-fn get_cursor_position() -> (i32, i32) {
-    #[cfg(target_os = "windows")]
-    {
-        use windows::Win32::UI::WindowsAndMessaging::GetCursorPos;
-        use windows::Win32::Foundation::POINT;
-        let mut point = POINT { x: 0, y: 0 };
-        unsafe {
-            let _ = GetCursorPos(&mut point);
-        }
-        (point.x, point.y)
-    }
-    #[cfg(not(target_os = "windows"))]
-    {
-        (100, 100) // 默认位置
-    }
-}
+// ====== 弹窗定位 ======
 
-// 获取光标所在显示器的工作区域
-fn get_monitor_work_area(cursor_x: i32, cursor_y: i32) -> (i32, i32, i32, i32) {
-    #[cfg(target_os = "windows")]
-    {
-        use windows::Win32::Graphics::Gdi::{MonitorFromPoint, GetMonitorInfoW, MONITORINFO, MONITOR_DEFAULTTONEAREST};
-        use windows::Win32::Foundation::POINT;
-
-        let point = POINT { x: cursor_x, y: cursor_y };
-        unsafe {
-            let monitor = MonitorFromPoint(point, MONITOR_DEFAULTTONEAREST);
-            let mut info = MONITORINFO {
-                cbSize: std::mem::size_of::<MONITORINFO>() as u32,
-                ..Default::default()
-            };
-            if GetMonitorInfoW(monitor, &mut info).as_bool() {
-                let rc = info.rcWork;
-                return (rc.left, rc.top, rc.right, rc.bottom);
-            }
-        }
-        (0, 0, 1920, 1080) // 默认值
-    }
-    #[cfg(not(target_os = "windows"))]
-    {
-        (0, 0, 1920, 1080) // 默认值
-    }
+// 各显示器的工作区（物理像素）
+fn monitor_work_areas(app: &tauri::AppHandle) -> Vec<screen::Rect> {
+    app.available_monitors()
+        .unwrap_or_default()
+        .iter()
+        .map(work_area_rect)
+        .collect()
 }
 
-// 弹窗尺寸常量（考虑 DPI 缩放，使用较大的估算值）
-const POPUP_WIDTH: i32 = 450;  // 300 * 1.5 DPI
-const POPUP_HEIGHT: i32 = 600; // 400 * 1.5 DPI
-const MARGIN: i32 = 10; // 边距
-
-// 计算弹窗位置（智能判断向上或向下弹出）
-fn calculate_popup_position(cursor_x: i32, cursor_y: i32) -> (i32, i32) {
-    let (work_left, work_top, work_right, work_bottom) = get_monitor_work_area(cursor_x, cursor_y);
-
-    let mut x = cursor_x;
-    let mut y = cursor_y;
-
-    // 判断是否在屏幕下半部分（如果光标下方空间不足以显示弹窗）
-    if cursor_y + POPUP_HEIGHT + MARGIN > work_bottom {
-        // 向上弹出（光标上方）
-        y = cursor_y - POPUP_HEIGHT - MARGIN;
-    }
-
-    // 确保不超出左右边界
-    if x + POPUP_WIDTH > work_right - MARGIN {
-        x = work_right - POPUP_WIDTH - MARGIN;
-    }
-    if x < work_left + MARGIN {
-        x = work_left + MARGIN;
-    }
+fn work_area_rect(monitor: &tauri::Monitor) -> screen::Rect {
+    let area = monitor.work_area();
+    screen::Rect::new(area.position.x, area.position.y, area.size.width as i32, area.size.height as i32)
+}
 
-    // 确保不超出上边界
-    if y < work_top + MARGIN {
-        y = work_top + MARGIN;
+// 计算弹窗位置：显示在光标附近；无法获取光标位置时（如 Wayland）显示在主显示器中央
+fn popup_position(app: &tauri::AppHandle) -> (i32, i32) {
+    let areas = monitor_work_areas(app);
+    match screen::cursor_position() {
+        Some((x, y)) => screen::calculate_popup_position(x, y, screen::work_area_at(&areas, x, y)),
+        None => {
+            let primary = app.primary_monitor().ok().flatten().map(|m| work_area_rect(&m));
+            screen::centered_position(primary.or(areas.first().copied()).unwrap_or(screen::DEFAULT_WORK_AREA))
+        }
     }
-
-    (x, y)
 }
 
 // 扫描所有子文件夹
@@ -346,6 +637,7 @@ fn scan_folders(folder: String) -> Result<Vec<String>, String> {
 
     for entry in WalkDir::new(&folder_path)
         .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
         .filter_map(|e| e.ok())
         .filter(|e| e.path().is_dir() && e.path() != folder_path)
     {
@@ -355,6 +647,32 @@ fn scan_folders(folder: String) -> Result<Vec<String>, String> {
     Ok(folders)
 }
 
+// 提示词库中的文件转为前端使用的提示词（category 为所在文件夹的相对路径）
+fn to_prompt(folder_path: &Path, file: library::ScannedFile) -> Prompt {
+    let path = &file.path;
+    let file_path = path.to_string_lossy().to_string();
+
+    let title = path.file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let category = path.parent()
+        .and_then(|p| p.strip_prefix(folder_path).ok())
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|| "默认".to_string());
+    let category = if category.is_empty() { "默认".to_string() } else { category };
+
+    Prompt {
+        id: file.id().to_string(),
+        title,
+        content: file.body,
+        category,
+        file_path,
+        rel_path: file.rel_path,
+        metadata: file.metadata,
+    }
+}
+
 // 扫描提示词文件夹
 #[tauri::command]
 fn scan_prompts(folder: String) -> Result<Vec<Prompt>, String> {
@@ -363,57 +681,22 @@ fn scan_prompts(folder: String) -> Result<Vec<Prompt>, String> {
         return Err("文件夹不存在".to_string());
     }
 
-    let mut prompts = Vec::new();
-
-    for entry in WalkDir::new(&folder_path)
+    Ok(scan_library(&folder_path)?
         .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().extension().map_or(false, |ext| ext == "md" || ext == "txt"))
-    {
-        let path = entry.path();
-        let file_path = path.to_string_lossy().to_string();
-
-        let title = path.file_stem()
-            .map(|s| s.to_string_lossy().to_string())
-            .unwrap_or_default();
-
-        let category = path.parent()
-            .and_then(|p| p.strip_prefix(&folder_path).ok())
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_else(|| "默认".to_string());
-        let category = if category.is_empty() { "默认".to_string() } else { category };
-
-        let content = fs::read_to_string(path).unwrap_or_default();
-        let id = format!("{:x}", md5_hash(&file_path));
-
-        prompts.push(Prompt {
-            id,
-            title,
-            content,
-            category,
-            file_path,
-        });
-    }
-
-    Ok(prompts)
-}
-
-fn md5_hash(s: &str) -> u64 {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
-    let mut hasher = DefaultHasher::new();
-    s.hash(&mut hasher);
-    hasher.finish()
+        .map(|file| to_prompt(&folder_path, file))
+        .collect())
 }
 
 // 保存提示词 - 优化：原地重命名
+// metadata 为 None 时保留文件原有的 front matter，只更新时间戳
 #[tauri::command]
 fn save_prompt(
     folder: String,
     category: String,
     title: String,
     content: String,
-    original_path: Option<String>
+    original_path: Option<String>,
+    metadata: Option<PromptMetadata>,
 ) -> Result<String, String> {
     let mut target_folder = PathBuf::from(&folder);
 
@@ -423,9 +706,12 @@ fn save_prompt(
         fs::create_dir_all(&target_folder).map_err(|e| format!("创建目录失败: {}", e))?;
     }
 
+    // 原路径可以是相对提示词库根目录的路径
+    let original_path = original_path.map(|p| library::to_absolute(Path::new(&folder), &p));
+
     // 保持原有扩展名，新建默认用 md
     let ext = original_path.as_ref()
-        .and_then(|p| PathBuf::from(p).extension().map(|e| e.to_string_lossy().to_string()))
+        .and_then(|p| p.extension().map(|e| e.to_string_lossy().to_string()))
         .unwrap_or_else(|| "md".to_string());
 
     let file_name = format!("{}.{}", title, ext);
@@ -433,25 +719,169 @@ fn save_prompt(
     let target_path_str = target_path.to_string_lossy().to_string();
 
     // 如果有原路径，且与新路径不同，执行重命名
-    if let Some(ref orig) = original_path {
-        let orig_path = PathBuf::from(orig);
-        if orig_path != target_path && orig_path.exists() {
+    let mut renamed_from = None;
+    if let Some(ref orig_path) = original_path {
+        if orig_path != &target_path && orig_path.exists() {
+            renamed_from = Some(orig_path.clone());
             // 执行重命名
-            fs::rename(&orig_path, &target_path)
+            fs::rename(orig_path, &target_path)
                 .map_err(|e| format!("重命名失败: {}", e))?;
         }
     }
 
+    // 读取已有元数据，避免覆盖助记词等字段；旧内容用于版本历史
+    let (existing, previous) = match original_path {
+        Some(_) if target_path.exists() => {
+            let (meta, body) = metadata::read_prompt_file(&target_path)?;
+            (meta, Some((body, file_modified(&target_path))))
+        }
+        _ => (PromptMetadata::default(), None),
+    };
+    let previous_title = original_path.as_deref().map(library::file_title).unwrap_or_default();
+    let mut meta = match metadata {
+        Some(mut meta) => {
+            meta.id = meta.id.or(existing.id);
+            meta.created = meta.created.or(existing.created);
+            meta
+        }
+        None => existing,
+    };
+    let id = meta.id.get_or_insert_with(library::new_id).clone();
+    let now = metadata::now_timestamp();
+    if meta.created.is_none() {
+        meta.created = Some(now.clone());
+    }
+    meta.updated = Some(now);
+
     // 写入最新内容
-    fs::write(&target_path, &content).map_err(|e| format!("保存文件失败: {}", e))?;
+    metadata::write_prompt_file(&target_path, &meta, &content)?;
+
+    // 更新 ID 索引
+    update_index(|index| index.update(&id, &target_path, &meta, &content))?;
+
+    // 记录版本历史
+    let previous = previous.as_ref().map(|(body, modified)| history::Snapshot {
+        title: &previous_title,
+        content: body,
+        saved_at: *modified,
+    });
+    record_history(&id, &target_path, previous, &title, &content, true);
+
+    match (&renamed_from, &original_path) {
+        (Some(from), _) => git_commit("重命名提示词", &[from, &target_path]),
+        (None, Some(_)) => git_commit_edit(&target_path),
+        (None, None) => git_commit("新建提示词", &[&target_path]),
+    }
 
     Ok(target_path_str)
 }
 
-// 删除提示词
+// ====== 版本历史 ======
+
+// 获取版本历史目录
+fn get_history_dir() -> PathBuf {
+    get_config_dir().join("history")
+}
+
+fn file_modified(path: &Path) -> chrono::DateTime<chrono::Local> {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map(chrono::DateTime::from)
+        .unwrap_or_else(|_| chrono::Local::now())
+}
+
+// 记录版本快照（失败不影响保存本身）
+// 版本历史以明文保存在配置目录中，加密的提示词不记录
+fn record_history(
+    id: &str,
+    path: &Path,
+    previous: Option<history::Snapshot>,
+    title: &str,
+    content: &str,
+    coalesce: bool,
+) {
+    if crypto::is_encrypted_file(path) {
+        return;
+    }
+    let current = history::Snapshot { title, content, saved_at: chrono::Local::now() };
+    if let Err(e) = history::record(&get_history_dir(), id, previous, current, coalesce) {
+        eprintln!("{}", e);
+    }
+}
+
+// 列出提示词的历史版本（最新的在前）
+#[tauri::command]
+fn list_prompt_versions(id: String) -> Result<Vec<history::VersionInfo>, String> {
+    history::list(&get_history_dir(), &id)
+}
+
+// 获取某个历史版本的完整内容
+#[tauri::command]
+fn get_prompt_version(id: String, version: u64) -> Result<history::PromptVersion, String> {
+    history::get(&get_history_dir(), &id, version)
+}
+
+// 版本对比结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VersionDiff {
+    pub from_title: String,
+    pub to_title: String,
+    pub lines: Vec<history::DiffLine>,
+}
+
+// 对比两个版本；to 为空时与当前文件对比
+#[tauri::command]
+fn diff_prompt_versions(id: String, from: u64, to: Option<u64>) -> Result<VersionDiff, String> {
+    let dir = get_history_dir();
+    let old = history::get(&dir, &id, from)?;
+    let (to_title, new_content) = match to {
+        Some(to) => {
+            let new = history::get(&dir, &id, to)?;
+            (new.title, new.content)
+        }
+        None => {
+            let path = resolve_prompt_path(&id)?;
+            (library::file_title(&path), metadata::read_prompt_file(&path)?.1)
+        }
+    };
+
+    Ok(VersionDiff {
+        lines: history::diff_lines(&old.content, &new_content),
+        from_title: old.title,
+        to_title,
+    })
+}
+
+// 恢复到某个历史版本（只恢复正文，文件名和元数据保持不变）
+// 恢复前的内容会先记录为新版本，恢复操作本身也可以撤销
+#[tauri::command]
+fn restore_prompt_version(id: String, version: u64) -> Result<String, String> {
+    let target = history::get(&get_history_dir(), &id, version)?;
+    let path = resolve_prompt_path(&id)?;
+    let (mut meta, body) = metadata::read_prompt_file(&path)?;
+    let title = library::file_title(&path);
+
+    let previous = history::Snapshot { title: &title, content: &body, saved_at: file_modified(&path) };
+    meta.updated = Some(metadata::now_timestamp());
+    metadata::write_prompt_file(&path, &meta, &target.content)?;
+    update_index(|index| index.update(&id, &path, &meta, &target.content))?;
+    record_history(&id, &path, Some(previous), &title, &target.content, false);
+    git_commit(&format!("恢复到版本 {}", version), &[&path]);
+
+    Ok(target.content)
+}
+
+// 删除提示词（移到回收站）
 #[tauri::command]
 fn delete_prompt(file_path: String) -> Result<(), String> {
-    fs::remove_file(&file_path).map_err(|e| format!("删除失败: {}", e))
+    let path = resolve_library_path(&file_path)?;
+    if !path.is_file() {
+        return Err("文件不存在".to_string());
+    }
+    trash::move_to_trash(&library_root()?, &path)?;
+    update_index(|index| index.remove_under(&path))?;
+    git_commit("删除提示词", &[&path]);
+    Ok(())
 }
 
 // 新建文件
@@ -480,6 +910,7 @@ fn create_file(folder: String) -> Result<String, String> {
 
     // 创建空文件
     fs::write(&file_path, "").map_err(|e| format!("创建文件失败: {}", e))?;
+    git_commit("新建提示词", &[&file_path]);
     Ok(file_path.to_string_lossy().to_string())
 }
 
@@ -515,7 +946,7 @@ fn create_folder(parent_folder: String) -> Result<String, String> {
 // 重命名文件夹
 #[tauri::command]
 fn rename_folder(old_path: String, new_name: String) -> Result<String, String> {
-    let old_path_buf = PathBuf::from(&old_path);
+    let old_path_buf = resolve_library_path(&old_path)?;
     if !old_path_buf.exists() || !old_path_buf.is_dir() {
         return Err("文件夹不存在".to_string());
     }
@@ -528,17 +959,303 @@ fn rename_folder(old_path: String, new_name: String) -> Result<String, String> {
     }
 
     fs::rename(&old_path_buf, &new_path).map_err(|e| format!("重命名失败: {}", e))?;
+
+    // 更新 ID 索引中该文件夹下的路径
+    update_index(|index| index.rename_prefix(&old_path_buf, &new_path))?;
+    git_commit("重命名文件夹", &[&old_path_buf, &new_path]);
+
     Ok(new_path.to_string_lossy().to_string())
 }
 
 // 删除文件夹
 #[tauri::command]
 fn delete_folder(folder_path: String) -> Result<(), String> {
-    let path = PathBuf::from(&folder_path);
+    let path = resolve_library_path(&folder_path)?;
     if !path.exists() || !path.is_dir() {
         return Err("文件夹不存在".to_string());
     }
-    fs::remove_dir_all(&path).map_err(|e| format!("删除文件夹失败: {}", e))
+    trash::move_to_trash(&library_root()?, &path)?;
+    update_index(|index| index.remove_under(&path))?;
+    git_commit("删除文件夹", &[&path]);
+    Ok(())
+}
+
+// ====== 回收站 ======
+
+// 恢复结果
+#[derive(Debug, Serialize, Clone)]
+struct RestoredItem {
+    path: String,
+    // 恢复时已被同一文件夹中其他提示词占用而移除的助记词
+    dropped_mnemonics: Vec<String>,
+}
+
+// 列出回收站中的项目（最近删除的在前）
+#[tauri::command]
+fn list_trash() -> Result<Vec<trash::TrashEntry>, String> {
+    let config = get_config()?;
+    if config.prompts_folder.is_empty() {
+        return Ok(Vec::new());
+    }
+    trash::list(Path::new(&config.prompts_folder))
+}
+
+// 从回收站恢复到原位置（同名文件已存在时自动改名），并重新登记 ID 和助记词
+#[tauri::command]
+fn restore_from_trash(entry_id: String) -> Result<RestoredItem, String> {
+    let root = library_root()?;
+    let path = trash::restore(&root, &entry_id)?;
+
+    // 重新扫描时 ID 和助记词从 front matter 重新加入索引；删除期间被占用的助记词需要移除
+    let files = scan_library(&root)?;
+    let mut dropped_mnemonics = Vec::new();
+    for (file, keep, dropped) in trash::conflicting_mnemonics(&files, &path) {
+        let mut meta = file.metadata.clone();
+        meta.set_mnemonics(keep);
+        metadata::write_prompt_file(&file.path, &meta, &file.body)?;
+        update_index(|index| index.update(file.id(), &file.path, &meta, &file.body))?;
+        dropped_mnemonics.extend(dropped);
+    }
+    git_commit("从回收站恢复", &[&path]);
+
+    Ok(RestoredItem {
+        path: path.to_string_lossy().to_string(),
+        dropped_mnemonics,
+    })
+}
+
+// 彻底删除回收站中的项目；entry_id 为空时清空回收站
+#[tauri::command]
+fn empty_trash(entry_id: Option<String>) -> Result<usize, String> {
+    trash::empty(&library_root()?, entry_id.as_deref())
+}
+
+// ====== Git 模式 ======
+
+// Git 模式下提交提示词库中的变化，提交说明为 "操作: 路径"（重命名为 "操作: 旧路径 -> 新路径"）
+// 提交失败只记录日志，不影响保存等操作本身
+fn git_commit(action: &str, paths: &[&Path]) {
+    commit_to_git(action, paths, false);
+}
+
+// 保存提示词内容后提交：自动保存产生的连续修改合并为一个提交
+fn git_commit_edit(path: &Path) {
+    commit_to_git("更新提示词", &[path], true);
+}
+
+fn commit_to_git(action: &str, paths: &[&Path], coalesce: bool) {
+    let Ok(config) = get_config() else { return };
+    if !config.git.enabled || config.prompts_folder.is_empty() {
+        return;
+    }
+
+    let root = PathBuf::from(&config.prompts_folder);
+    let names: Vec<String> = paths.iter()
+        .map(|p| library::to_relative(&root, p).unwrap_or_else(|| p.to_string_lossy().to_string()))
+        .collect();
+    let message = format!("{}: {}", action, names.join(" -> "));
+    if let Err(e) = git::commit(&root, paths, &message, coalesce) {
+        eprintln!("{}", e);
+    }
+}
+
+// 已启用 Git 模式的配置
+fn git_config() -> Result<(PathBuf, git::GitConfig), String> {
+    let config = get_config()?;
+    if !config.git.enabled {
+        return Err("请先在设置中启用 Git 模式".to_string());
+    }
+    Ok((library_root()?, config.git))
+}
+
+// 提示词的提交记录（最新的在前）
+#[tauri::command]
+fn git_log(id: String, limit: Option<usize>) -> Result<Vec<git::CommitInfo>, String> {
+    let (root, _) = git_config()?;
+    git::log(&root, &resolve_prompt_path(&id)?, limit.unwrap_or(50))
+}
+
+// 提示词每一行最后修改的提交
+#[tauri::command]
+fn git_blame(id: String) -> Result<Vec<git::BlameLine>, String> {
+    let (root, _) = git_config()?;
+    git::blame(&root, &resolve_prompt_path(&id)?)
+}
+
+// 从远程仓库拉取
+#[tauri::command]
+fn git_pull() -> Result<git::GitSyncResult, String> {
+    let (root, config) = git_config()?;
+    git::pull(&root, &config.remote)
+}
+
+// 推送到远程仓库
+#[tauri::command]
+fn git_push() -> Result<git::GitSyncResult, String> {
+    let (root, config) = git_config()?;
+    git::push(&root, &config.remote)
+}
+
+// ====== 加密 ======
+
+// 密钥文件路径（只保存盐和校验数据）
+fn get_key_path() -> PathBuf {
+    get_config_dir().join("encryption.json")
+}
+
+// 获取加密状态（是否已设置密码、是否已解锁）
+#[tauri::command]
+fn get_encryption_status() -> crypto::EncryptionStatus {
+    crypto::status(&get_key_path())
+}
+
+// 首次设置加密密码（密码无法找回）
+#[tauri::command]
+fn setup_encryption(app: tauri::AppHandle, passphrase: String) -> Result<(), String> {
+    crypto::setup(&get_key_path(), &passphrase)?;
+    app.state::<LibraryState>().reload();
+    Ok(())
+}
+
+// 解锁：之后可以读取和编辑加密的提示词
+#[tauri::command]
+fn unlock_encryption(app: tauri::AppHandle, passphrase: String) -> Result<(), String> {
+    crypto::unlock(&get_key_path(), &passphrase)?;
+    app.state::<LibraryState>().reload();
+    Ok(())
+}
+
+// 锁定：清除内存中的密钥和已解密的提示词
+#[tauri::command]
+fn lock_encryption(app: tauri::AppHandle) {
+    crypto::lock();
+    app.state::<LibraryState>().reload();
+}
+
+// 列出标记为加密的文件夹
+#[tauri::command]
+fn list_encrypted_folders() -> Result<Vec<String>, String> {
+    let root = library_root()?;
+    Ok(WalkDir::new(&root)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_dir() && e.path().join(crypto::FOLDER_MARKER).is_file())
+        .map(|e| e.path().to_string_lossy().to_string())
+        .collect())
+}
+
+// 加密或取消加密文件夹：其中（含子文件夹）的提示词重新写入为密文或明文
+// 加密后删除这些提示词的版本历史（历史为明文）；仍在其他加密文件夹中的提示词保持加密
+#[tauri::command]
+fn set_folder_encrypted(app: tauri::AppHandle, folder_path: String, encrypted: bool) -> Result<usize, String> {
+    let folder = resolve_library_path(&folder_path)?;
+    if !folder.is_dir() {
+        return Err("文件夹不存在".to_string());
+    }
+    if !crypto::status(&get_key_path()).unlocked {
+        return Err("请先设置加密密码并解锁".to_string());
+    }
+
+    let marker = folder.join(crypto::FOLDER_MARKER);
+    if encrypted {
+        fs::write(&marker, "此文件夹中的提示词加密保存\n")
+            .map_err(|e| format!("标记加密文件夹失败: {}", e))?;
+    } else if marker.exists() {
+        fs::remove_file(&marker).map_err(|e| format!("取消加密文件夹失败: {}", e))?;
+    }
+
+    let mut changed = 0;
+    for path in library::walk_prompt_files(&folder) {
+        let (text, was_encrypted) = crypto::read_text(&path)?;
+        let encrypt = crypto::in_encrypted_folder(&path);
+        if encrypt == was_encrypted {
+            continue;
+        }
+        let content = if encrypt { crypto::encrypt_text(&text)? } else { text.clone() };
+        fs::write(&path, content).map_err(|e| format!("保存文件失败: {}", e))?;
+        if let (true, Some(id)) = (encrypt, metadata::parse(&text).0.id) {
+            history::remove(&get_history_dir(), &id)?;
+        }
+        changed += 1;
+    }
+
+    let action = if encrypted { "加密文件夹" } else { "取消加密文件夹" };
+    git_commit(action, &[&folder]);
+    scan_library(&library_root()?)?;
+    app.state::<LibraryState>().reload();
+    Ok(changed)
+}
+
+// ====== 远程同步 ======
+
+// 同步记录文件路径
+fn get_sync_manifest_path() -> PathBuf {
+    get_config_dir().join("sync-manifest.json")
+}
+
+// 检测 SSH/SCP 是否可用
+#[tauri::command]
+fn check_ssh_available() -> Result<bool, String> {
+    Ok(ssh::check_available())
+}
+
+// 测试 SSH 连接
+#[tauri::command]
+fn test_ssh_connection(config: ssh::RemoteSyncConfig) -> Result<String, ssh::SshError> {
+    ssh::test_connection(&config)
+}
+
+// 测试 WebDAV 连接
+#[tauri::command]
+fn test_webdav_connection(config: webdav::WebDavConfig) -> Result<String, String> {
+    webdav::WebDavRemote::new(config)?.test()
+}
+
+// 当前启用的同步后端（同时启用时优先使用 WebDAV）
+// migrate_plaintext：开启同步加密时接受未加密的远程文件并重新加密上传（需用户确认）
+fn sync_backend(migrate_plaintext: bool) -> Result<(PathBuf, Box<dyn sync::SyncBackend>), String> {
+    let config = get_config()?;
+    let backend: Box<dyn sync::SyncBackend> = if config.webdav.enabled {
+        if config.webdav.url.is_empty() {
+            return Err("请先配置 WebDAV 地址".to_string());
+        }
+        Box::new(webdav::WebDavRemote::new(config.webdav)?)
+    } else if config.remote_sync.enabled {
+        let remote = config.remote_sync;
+        if remote.server.is_empty() || remote.remote_path.is_empty() {
+            return Err("请先配置服务器地址和远程路径".to_string());
+        }
+        Box::new(ssh::SshRemote::new(remote, &std::env::temp_dir().join("prompt-manager-sync"))?)
+    } else {
+        return Err("请先在设置中启用远程同步".to_string());
+    };
+    if config.sync_encryption {
+        return Ok((library_root()?, Box::new(crypto::EncryptedBackend::new(backend, migrate_plaintext)?)));
+    }
+    Ok((library_root()?, backend))
+}
+
+// 预览同步（不修改本机和远程的文件）
+#[tauri::command]
+fn sync_preview() -> Result<Vec<sync::SyncAction>, String> {
+    let (root, mut backend) = sync_backend(false)?;
+    sync::preview(&root, &get_sync_manifest_path(), backend.as_mut())
+}
+
+// 与远程双向同步
+#[tauri::command]
+fn sync_now(migrate_plaintext: Option<bool>) -> Result<sync::SyncReport, String> {
+    let (root, mut backend) = sync_backend(migrate_plaintext.unwrap_or(false))?;
+    let report = sync::run(&root, &get_sync_manifest_path(), backend.as_mut())?;
+
+    update_index(|index| {
+        for path in &report.removed {
+            index.remove_under(path);
+        }
+    })?;
+    scan_library(&root)?;
+    Ok(report)
 }
 
 // 在文件资源管理器中打开
@@ -559,15 +1276,25 @@ fn open_in_explorer(path: String) -> Result<(), String> {
             .map_err(|e| format!("打开失败: {}", e))?;
     }
 
+    #[cfg(target_os = "macos")]
+    {
+        std::process::Command::new("open")
+            .arg(folder)
+            .spawn()
+            .map_err(|e| format!("打开失败: {}", e))?;
+    }
+
     Ok(())
 }
 
-// 复制到剪贴板
+// 复制到剪贴板（id 为复制的提示词，用于记录使用次数）
 #[tauri::command]
-fn copy_to_clipboard(app: tauri::AppHandle, text: String) -> Result<(), String> {
+fn copy_to_clipboard(app: tauri::AppHandle, text: String, id: Option<String>) -> Result<(), String> {
     use tauri_plugin_clipboard_manager::ClipboardExt;
     app.clipboard().write_text(&text)
-        .map_err(|e| format!("复制失败: {}", e))
+        .map_err(|e| format!("复制失败: {}", e))?;
+    record_usage(id.as_deref());
+    Ok(())
 }
 
 // 读取剪贴板内容
@@ -578,101 +1305,267 @@ fn read_clipboard(app: tauri::AppHandle) -> Result<String, String> {
         .map_err(|e| format!("读取剪贴板失败: {}", e))
 }
 
-// 模拟键盘输入（使用剪贴板粘贴方式，支持中文）
+// ====== 模板渲染 ======
+
+// 模板预览结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RenderedPrompt {
+    pub text: String,
+    // 模板中需要用户填写的输入项
+    pub inputs: Vec<template::TemplateInput>,
+    pub cursor_offset: Option<usize>,
+}
+
+// 展开模板中的 {{> partial}}，片段从提示词库中按相对路径查找（只在需要时读取提示词库）
+fn expand_partials(
+    text: &str,
+    files: impl FnOnce() -> Result<Vec<library::ScannedFile>, String>,
+) -> Result<String, String> {
+    if !template::has_includes(text) {
+        return Ok(text.to_string());
+    }
+    let partials: HashMap<String, String> = files()?
+        .into_iter()
+        .map(|f| (template::partial_key(&f.rel_path), f.body))
+        .collect();
+    template::expand_includes(text, &partials)
+}
+
+// 渲染提示词模板，返回渲染结果和需要填写的输入项（{{selection}} 在预览中为空）
 #[tauri::command]
-fn type_text(app: tauri::AppHandle, text: String) -> Result<(), String> {
-    use enigo::{Enigo, Key, Keyboard, Settings};
+fn render_prompt(
+    app: tauri::AppHandle,
+    text: String,
+    inputs: Option<HashMap<String, String>>,
+) -> Result<RenderedPrompt, String> {
     use tauri_plugin_clipboard_manager::ClipboardExt;
 
-    // 1. 先将文本复制到剪贴板
-    app.clipboard().write_text(&text)
-        .map_err(|e| format!("复制到剪贴板失败: {}", e))?;
-
-    // 2. 等待一下确保剪贴板已更新
-    std::thread::sleep(std::time::Duration::from_millis(50));
+    let text = expand_partials(&text, || indexed_files(&app))?;
+    let segments = template::parse(&text);
+    let ctx = template::RenderContext {
+        selection: None,
+        clipboard: app.clipboard().read_text().ok(),
+        inputs: inputs.unwrap_or_default(),
+    };
+    let rendered = template::render(&segments, &ctx)?;
+    Ok(RenderedPrompt {
+        text: rendered.text,
+        inputs: template::required_inputs(&segments),
+        cursor_offset: rendered.cursor_offset,
+    })
+}
 
-    // 3. 模拟 Ctrl+V 粘贴
-    let mut enigo = Enigo::new(&Settings::default())
-        .map_err(|e| format!("初始化输入失败: {}", e))?;
+// 按下组合键：依次按下修饰键，按一下最后一个键，再松开修饰键
+fn press_chord(enigo: &mut enigo::Enigo, keys: &[enigo::Key]) -> Result<(), String> {
+    use enigo::{Direction, Keyboard};
 
-    // 按下 Ctrl+V
-    enigo.key(Key::Control, enigo::Direction::Press)
-        .map_err(|e| format!("按键失败: {}", e))?;
-    enigo.key(Key::Unicode('v'), enigo::Direction::Click)
-        .map_err(|e| format!("按键失败: {}", e))?;
-    enigo.key(Key::Control, enigo::Direction::Release)
+    let Some((&key, modifiers)) = keys.split_last() else {
+        return Ok(());
+    };
+    for &modifier in modifiers {
+        enigo.key(modifier, Direction::Press)
+            .map_err(|e| format!("按键失败: {}", e))?;
+    }
+    enigo.key(key, Direction::Click)
         .map_err(|e| format!("按键失败: {}", e))?;
+    for &modifier in modifiers.iter().rev() {
+        enigo.key(modifier, Direction::Release)
+            .map_err(|e| format!("按键失败: {}", e))?;
+    }
+    Ok(())
+}
 
+// 依次按下按键序列中的组合键（为空时不初始化输入，模拟输入不依赖 enigo）
+fn press_sequence(sequence: &[Vec<enigo::Key>]) -> Result<(), String> {
+    use enigo::{Enigo, Settings};
+
+    if sequence.is_empty() {
+        return Ok(());
+    }
+    let mut enigo = Enigo::new(&Settings::default())
+        .map_err(|e| format!("初始化输入失败: {}", e))?;
+    for keys in sequence {
+        press_chord(&mut enigo, keys)?;
+    }
     Ok(())
 }
 
-// 纯模拟键盘输入（使用 Windows SendInput，支持中文，逐字符输入，ESC可取消）
-#[tauri::command]
-fn type_text_simulate(text: String) -> Result<(), String> {
-    #[cfg(target_os = "windows")]
-    {
-        use windows::Win32::UI::Input::KeyboardAndMouse::{SendInput, INPUT, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_UNICODE, KEYEVENTF_KEYUP, GetAsyncKeyState};
+// 通过模拟复制快捷键获取目标窗口中选中的文本，完成后恢复剪贴板
+fn capture_selection(app: &tauri::AppHandle, copy_chord: &str) -> Result<Option<String>, String> {
+    use enigo::{Enigo, Settings};
+    use tauri_plugin_clipboard_manager::ClipboardExt;
 
+    let copy_keys = insert_profile::parse_chord(copy_chord)?;
+    let previous = clipboard::Snapshot::take(app);
+    // 清空剪贴板之后无论成功与否都要恢复
+    let copy = || -> Result<Option<String>, String> {
+        app.clipboard().write_text(String::new())
+            .map_err(|e| format!("清空剪贴板失败: {}", e))?;
+        let mut enigo = Enigo::new(&Settings::default())
+            .map_err(|e| format!("初始化输入失败: {}", e))?;
+        press_chord(&mut enigo, &copy_keys)?;
         std::thread::sleep(std::time::Duration::from_millis(100));
+        Ok(app.clipboard().read_text().ok().filter(|s| !s.is_empty()))
+    };
+
+    let selection = copy();
+    if let Err(e) = previous.restore(app) {
+        eprintln!("{}", e);
+    }
+    selection
+}
+
+// 插入前渲染模板：获取选中文本和剪贴板，填入输入项（raw 为 true 时原样插入）
+fn render_for_insert(
+    app: &tauri::AppHandle,
+    text: &str,
+    inputs: Option<HashMap<String, String>>,
+    raw: bool,
+) -> Result<template::Rendered, String> {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+
+    if raw {
+        return Ok(template::Rendered { text: text.to_string(), cursor_offset: None });
+    }
+
+    let inputs = inputs.unwrap_or_default();
+    let text = expand_partials(text, || indexed_files(app))?;
+    let segments = template::parse(&text);
+    let missing = template::missing_inputs(&segments, &inputs);
+    if !missing.is_empty() {
+        return Err(format!("缺少输入项: {}", missing.join(", ")));
+    }
 
-        const VK_ESCAPE: i32 = 0x1B;
+    let selection = if template::uses_selection(&segments) {
+        // 按焦点程序的插入配置选择复制快捷键（终端中 Ctrl+C 会中断前台进程）
+        let profile = get_config()?.insert.profile_for(insert_profile::focused_app().as_ref());
+        capture_selection(app, &profile.copy_chord)?
+    } else {
+        None
+    };
+    let ctx = template::RenderContext {
+        selection,
+        clipboard: app.clipboard().read_text().ok(),
+        inputs,
+    };
+    template::render(&segments, &ctx)
+}
 
-        for c in text.encode_utf16() {
-            // 检测 ESC 键是否按下
-            unsafe {
-                if GetAsyncKeyState(VK_ESCAPE) & 0x8000u16 as i16 != 0 {
-                    return Ok(()); // 用户按下 ESC，取消输入
+// 插入完成后把光标移回 {{cursor}} 所在位置
+fn move_cursor_back(offset: Option<usize>) -> Result<(), String> {
+    use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+
+    let Some(offset) = offset.filter(|&n| n > 0) else {
+        return Ok(());
+    };
+    let mut enigo = Enigo::new(&Settings::default())
+        .map_err(|e| format!("初始化输入失败: {}", e))?;
+    for _ in 0..offset {
+        enigo.key(Key::LeftArrow, Direction::Click)
+            .map_err(|e| format!("按键失败: {}", e))?;
+    }
+    Ok(())
+}
+
+// 按焦点程序的插入配置把文本输入到当前焦点窗口（method 为 None 时使用配置中的插入方式）
+// 返回 false 表示用户按下中止键取消了模拟输入
+fn insert_rendered(
+    app: &tauri::AppHandle,
+    rendered: &template::Rendered,
+    method: Option<insert_profile::InsertMethod>,
+) -> Result<bool, String> {
+    use insert_profile::InsertMethod;
+    use std::time::Duration;
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+
+    let config = get_config()?;
+    let profile = config.insert.profile_for(insert_profile::focused_app().as_ref());
+    let paste_keys = insert_profile::parse_chord(&profile.paste_chord)?;
+    let before_keys = insert_profile::parse_sequence(&profile.before_keys)?;
+    let after_keys = insert_profile::parse_sequence(&profile.after_keys)?;
+    let text = profile.line_ending.apply(&rendered.text);
+
+    press_sequence(&before_keys)?;
+
+    match method.unwrap_or(profile.method) {
+        InsertMethod::Paste => {
+            // 保存原来的剪贴板内容，再将文本复制到剪贴板
+            let previous = config.clipboard.restore.then(|| clipboard::Snapshot::take(app));
+            app.clipboard().write_text(&text)
+                .map_err(|e| format!("复制到剪贴板失败: {}", e))?;
+
+            // 等待一下确保剪贴板已更新，再按粘贴键
+            std::thread::sleep(Duration::from_millis(config.clipboard.paste_delay_ms));
+            let pasted = press_sequence(&[paste_keys]);
+
+            // 等目标程序读取剪贴板后恢复原来的内容（按键失败时立即恢复）
+            if let Some(previous) = previous {
+                if pasted.is_ok() {
+                    let delay = Duration::from_millis(config.clipboard.restore_delay_ms);
+                    clipboard::restore_later(app, previous, text, delay);
+                } else if let Err(e) = previous.restore(app) {
+                    eprintln!("{}", e);
                 }
             }
-
-            let inputs = [
-                // Key down
-                INPUT {
-                    r#type: INPUT_KEYBOARD,
-                    Anonymous: windows::Win32::UI::Input::KeyboardAndMouse::INPUT_0 {
-                        ki: KEYBDINPUT {
-                            wVk: windows::Win32::UI::Input::KeyboardAndMouse::VIRTUAL_KEY(0),
-                            wScan: c,
-                            dwFlags: KEYEVENTF_UNICODE,
-                            time: 0,
-                            dwExtraInfo: 0,
-                        },
-                    },
-                },
-                // Key up
-                INPUT {
-                    r#type: INPUT_KEYBOARD,
-                    Anonymous: windows::Win32::UI::Input::KeyboardAndMouse::INPUT_0 {
-                        ki: KEYBDINPUT {
-                            wVk: windows::Win32::UI::Input::KeyboardAndMouse::VIRTUAL_KEY(0),
-                            wScan: c,
-                            dwFlags: KEYEVENTF_UNICODE | KEYEVENTF_KEYUP,
-                            time: 0,
-                            dwExtraInfo: 0,
-                        },
-                    },
-                },
-            ];
-
-            unsafe {
-                SendInput(&inputs, std::mem::size_of::<INPUT>() as i32);
+            pasted?;
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        InsertMethod::Simulate => {
+            let mut injector = inject::create(&config.simulate)?;
+            let delay = Duration::from_millis(config.simulate.char_delay_ms);
+            let outcome = inject::type_text(injector.as_mut(), &text, delay, std::thread::sleep)?;
+            // 先释放输入设备（恢复键盘映射等），再继续按键
+            drop(injector);
+            if let inject::TypeOutcome::Aborted { .. } = outcome {
+                return Ok(false);
             }
-
-            // 每个字符之间延迟 2ms，模拟正常输入
-            std::thread::sleep(std::time::Duration::from_millis(2));
         }
+    }
 
-        Ok(())
+    // 移动光标到 {{cursor}} 处，再按插入后的按键
+    move_cursor_back(rendered.cursor_offset)?;
+    press_sequence(&after_keys)?;
+    Ok(true)
+}
+
+// 插入提示词（默认使用剪贴板粘贴，支持中文），插入方式、粘贴快捷键等按焦点程序的插入配置选择
+#[tauri::command]
+fn type_text(
+    app: tauri::AppHandle,
+    text: String,
+    inputs: Option<HashMap<String, String>>,
+    raw: Option<bool>,
+    id: Option<String>,
+) -> Result<(), String> {
+    let rendered = render_for_insert(&app, &text, inputs, raw.unwrap_or(false))?;
+    if insert_rendered(&app, &rendered, None)? {
+        record_usage(id.as_deref());
     }
-    #[cfg(not(target_os = "windows"))]
-    {
-        Err("模拟输入仅支持 Windows".to_string())
+    Ok(())
+}
+
+// 纯模拟键盘输入（逐字符输入，不经过剪贴板，支持中文，按下中止键可取消）
+// 输入方式见 inject.rs：Windows 使用 SendInput，Linux 使用 XTest / wtype / uinput
+#[tauri::command]
+fn type_text_simulate(
+    app: tauri::AppHandle,
+    text: String,
+    inputs: Option<HashMap<String, String>>,
+    raw: Option<bool>,
+    id: Option<String>,
+) -> Result<(), String> {
+    std::thread::sleep(std::time::Duration::from_millis(100));
+
+    let rendered = render_for_insert(&app, &text, inputs, raw.unwrap_or(false))?;
+    if insert_rendered(&app, &rendered, Some(insert_profile::InsertMethod::Simulate))? {
+        record_usage(id.as_deref());
     }
+    Ok(())
 }
 
 // 设置开机自启
 #[tauri::command]
-fn set_autostart(enable: bool) -> Result<(), String> {
+fn set_autostart(app: tauri::AppHandle, enable: bool) -> Result<(), String> {
     #[cfg(target_os = "windows")]
     {
         use std::env;
@@ -733,12 +1626,13 @@ fn set_autostart(enable: bool) -> Result<(), String> {
         // 保存配置
         let mut config = get_config()?;
         config.autostart = enable;
-        save_config(config)?;
+        save_config(app, config)?;
 
         Ok(())
     }
     #[cfg(not(target_os = "windows"))]
     {
+        let _ = app;
         Err("开机自启仅支持 Windows".to_string())
     }
 }
@@ -781,8 +1675,7 @@ fn update_hotkey(app: tauri::AppHandle, new_hotkey: String) -> Result<(), String
 
     app.global_shortcut().on_shortcuts([shortcut], move |app_handle, _shortcut, event| {
         if event.state == ShortcutState::Pressed {
-            let (cursor_x, cursor_y) = get_cursor_position();
-            let (x, y) = calculate_popup_position(cursor_x, cursor_y);
+            let (x, y) = popup_position(app_handle);
             let app_handle = app_handle.clone();
             tauri::async_runtime::spawn(async move {
                 if let Some(popup) = app_handle.get_webview_window("popup") {
@@ -800,11 +1693,21 @@ fn update_hotkey(app: tauri::AppHandle, new_hotkey: String) -> Result<(), String
 
     // 3. 保存新配置
     config.hotkey = new_hotkey;
-    save_config(config)?;
+    save_config(app, config)?;
 
     Ok(())
 }
 
+// 命令行工具入口（src/bin/prompt-manager-cli.rs）
+pub fn run_cli() -> std::process::ExitCode {
+    cli::run()
+}
+
+// MCP 服务器入口（prompt-manager mcp）
+pub fn run_mcp() -> std::process::ExitCode {
+    mcp::run()
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -812,7 +1715,34 @@ pub fn run() {
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .plugin(tauri_plugin_dialog::init())
+        .manage(LibraryState::default())
+        .manage(api::ApiState::default())
         .setup(|app| {
+            // 将旧版 prompts-meta.json 中的助记词迁移到文件 front matter
+            match metadata::migrate_legacy_meta(&get_meta_path()) {
+                Ok(migration) => {
+                    if migration.migrated > 0 {
+                        println!("已迁移 {} 个助记词到 front matter", migration.migrated);
+                    }
+                    if let Some(path) = migration.unmigrated_path {
+                        eprintln!("{} 个助记词未能迁移，已保存到 {}", migration.unmigrated, path.display());
+                    }
+                }
+                Err(e) => eprintln!("迁移助记词失败: {}", e),
+            }
+
+            // 监听提示词库并在后台加载搜索索引
+            if let Ok(root) = library_root() {
+                if let Err(e) = watcher::watch_library(app.handle(), root) {
+                    eprintln!("{}", e);
+                }
+            }
+
+            // 启动本地接口（已开启时）
+            if let Err(e) = api::apply(app.handle(), &get_config().unwrap_or_default().api) {
+                eprintln!("{}", e);
+            }
+
             // 注册全局快捷键
             #[cfg(desktop)]
             {
@@ -838,8 +1768,7 @@ pub fn run() {
                         // 使用 on_shortcuts 同时注册快捷键和处理器
                         if let Err(e) = app.global_shortcut().on_shortcuts([shortcut], move |app_handle, _shortcut, event| {
                             if event.state == ShortcutState::Pressed {
-                                let (cursor_x, cursor_y) = get_cursor_position();
-                                let (x, y) = calculate_popup_position(cursor_x, cursor_y);
+                                let (x, y) = popup_position(app_handle);
                                 let app_handle = app_handle.clone();
                                 tauri::async_runtime::spawn(async move {
                                     if let Some(popup) = app_handle.get_webview_window("popup") {
@@ -922,12 +1851,20 @@ pub fn run() {
             get_config,
             get_config_path_str,
             save_config,
-            get_prompts_meta,
             set_mnemonic,
+            set_mnemonics,
             remove_mnemonic,
             find_by_mnemonic,
-            get_mnemonic_for_file,
+            get_mnemonic,
+            get_mnemonics,
+            repair_mnemonics,
+            relink_mnemonic,
+            discard_orphaned_mnemonic,
             get_all_mnemonics,
+            search_prompts,
+            pin_prompt,
+            get_usage_stats,
+            reset_usage_stats,
             show_popup,
             hide_popup,
             scan_prompts,
@@ -936,6 +1873,11 @@ pub fn run() {
             open_in_explorer,
             copy_to_clipboard,
             read_clipboard,
+            render_prompt,
+            list_prompt_versions,
+            get_prompt_version,
+            diff_prompt_versions,
+            restore_prompt_version,
             type_text,
             type_text_simulate,
             set_autostart,
@@ -945,6 +1887,26 @@ pub fn run() {
             create_folder,
             rename_folder,
             delete_folder,
+            list_trash,
+            restore_from_trash,
+            empty_trash,
+            git_log,
+            git_blame,
+            git_pull,
+            git_push,
+            check_ssh_available,
+            test_ssh_connection,
+            test_webdav_connection,
+            sync_preview,
+            sync_now,
+            get_encryption_status,
+            setup_encryption,
+            unlock_encryption,
+            lock_encryption,
+            list_encrypted_folders,
+            set_folder_encrypted,
+            get_api_token,
+            regenerate_api_token,
             scan_folders
         ])
         .run(tauri::generate_context!())
@@ -0,0 +1,637 @@
+// 提示词库 - 文件遍历、稳定 ID 分配与 ID 索引（含助记词表）
+use crate::crypto;
+use crate::metadata::{self, PromptMetadata};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+// 遍历提示词库中的所有提示词文件（跳过回收站等隐藏文件夹）
+pub fn walk_prompt_files(folder: &Path) -> Vec<PathBuf> {
+    WalkDir::new(folder)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "md" || ext == "txt"))
+        .map(|e| e.into_path())
+        .collect()
+}
+
+// 相对路径是否位于隐藏文件或文件夹中（如 .trash）
+pub fn is_hidden(rel: &str) -> bool {
+    rel.split('/').any(|part| part.starts_with('.'))
+}
+
+// 转换为相对提示词库根目录的路径（统一使用 / 分隔，便于跨平台共享）
+pub fn to_relative(root: &Path, path: &Path) -> Option<String> {
+    if root.as_os_str().is_empty() {
+        return None;
+    }
+    let rest = path.strip_prefix(root).ok()?;
+    let parts: Vec<_> = rest.components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    Some(parts.join("/"))
+}
+
+// 将相对路径解析为绝对路径（已是绝对路径时原样返回）
+pub fn to_absolute(root: &Path, rel: &str) -> PathBuf {
+    if Path::new(rel).is_absolute() {
+        return PathBuf::from(rel);
+    }
+    rel.split(['/', '\\'])
+        .filter(|s| !s.is_empty())
+        .fold(root.to_path_buf(), |path, part| path.join(part))
+}
+
+// 生成新的提示词 ID
+pub fn new_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+// 文件名（不含扩展名）
+pub fn file_title(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+// 正文摘要长度（字符数），用于孤立助记词的内容相似度匹配
+const EXCERPT_CHARS: usize = 200;
+
+fn excerpt(body: &str) -> String {
+    body.trim().chars().take(EXCERPT_CHARS).collect()
+}
+
+// 索引条目
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct IndexEntry {
+    // 相对提示词库根目录的路径
+    pub path: String,
+    // 主助记词和别名
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mnemonics: Vec<String>,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub excerpt: String,
+    // 文件已找不到（被外部删除或 front matter 被清除），助记词等待修复
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub orphaned: bool,
+}
+
+impl IndexEntry {
+    // 加密的提示词不在索引中保存正文摘要
+    fn new(rel_path: String, path: &Path, meta: &PromptMetadata, body: &str) -> Self {
+        let excerpt = if crypto::is_encrypted_file(path) { String::new() } else { excerpt(body) };
+        Self {
+            title: file_title(Path::new(&rel_path)),
+            path: rel_path,
+            mnemonics: meta.mnemonics(),
+            excerpt,
+            orphaned: false,
+        }
+    }
+}
+
+// ID 索引：记录每个 ID 上次出现的位置和助记词，用于检测文件移动和修复助记词
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct LibraryIndex {
+    // 建立索引时的提示词库根目录
+    #[serde(default)]
+    pub root: String,
+    // id -> entry
+    #[serde(default)]
+    pub prompts: BTreeMap<String, IndexEntry>,
+}
+
+impl LibraryIndex {
+    // 加载索引并切换到当前提示词库根目录
+    // 索引可由扫描重建，旧格式或损坏时从空索引开始
+    pub fn load(path: &Path, root: &Path) -> Result<Self, String> {
+        let mut index = if path.exists() {
+            let content = fs::read_to_string(path)
+                .map_err(|e| format!("读取索引失败: {}", e))?;
+            serde_json::from_str(&content).unwrap_or_else(|e| {
+                eprintln!("解析索引失败，将重新建立: {}", e);
+                Self::default()
+            })
+        } else {
+            Self::default()
+        };
+        index.rebase(root);
+        Ok(index)
+    }
+
+    // 根目录变化时，将绝对路径条目（旧版索引或库被移动前的记录）改写为相对路径
+    fn rebase(&mut self, root: &Path) {
+        let old_root = PathBuf::from(&self.root);
+        for entry in self.prompts.values_mut() {
+            let path = Path::new(&entry.path);
+            if !path.is_absolute() {
+                continue;
+            }
+            if let Some(rel) = to_relative(root, path).or_else(|| to_relative(&old_root, path)) {
+                entry.path = rel;
+            }
+        }
+        self.root = root.to_string_lossy().to_string();
+    }
+
+    // 转换为相对当前根目录的路径（库外路径保持绝对路径）
+    pub fn relative(&self, path: &Path) -> String {
+        to_relative(Path::new(&self.root), path)
+            .unwrap_or_else(|| path.to_string_lossy().to_string())
+    }
+
+    // 解析 ID 对应的绝对路径
+    pub fn resolve(&self, id: &str) -> Option<PathBuf> {
+        self.path_of(id).map(|rel| to_absolute(Path::new(&self.root), rel))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("序列化索引失败: {}", e))?;
+        fs::write(path, content).map_err(|e| format!("保存索引失败: {}", e))
+    }
+
+    pub fn path_of(&self, id: &str) -> Option<&str> {
+        self.prompts.get(id)
+            .filter(|e| !e.orphaned)
+            .map(|e| e.path.as_str())
+    }
+
+    pub fn update(&mut self, id: &str, path: &Path, meta: &PromptMetadata, body: &str) {
+        let entry = IndexEntry::new(self.relative(path), path, meta, body);
+        self.prompts.insert(id.to_string(), entry);
+    }
+
+    // 文件夹重命名后，更新其下所有条目的路径
+    pub fn rename_prefix(&mut self, old_prefix: &Path, new_prefix: &Path) {
+        let root = PathBuf::from(&self.root);
+        for entry in self.prompts.values_mut() {
+            let path = to_absolute(&root, &entry.path);
+            if let Ok(rest) = path.strip_prefix(old_prefix) {
+                let new_path = new_prefix.join(rest);
+                entry.path = to_relative(&root, &new_path)
+                    .unwrap_or_else(|| new_path.to_string_lossy().to_string());
+            }
+        }
+    }
+
+    // 删除文件或文件夹后，移除对应条目（包括助记词）
+    pub fn remove_under(&mut self, path: &Path) {
+        let root = PathBuf::from(&self.root);
+        self.prompts.retain(|_, e| !to_absolute(&root, &e.path).starts_with(path));
+    }
+}
+
+// 扫描得到的提示词文件
+#[derive(Debug, Clone)]
+pub struct ScannedFile {
+    pub path: PathBuf,
+    // 相对提示词库根目录的路径
+    pub rel_path: String,
+    pub metadata: PromptMetadata,
+    pub body: String,
+}
+
+impl ScannedFile {
+    pub fn id(&self) -> &str {
+        self.metadata.id.as_deref().unwrap_or_default()
+    }
+}
+
+// 检测到的文件移动（相对路径）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Relocation {
+    pub id: String,
+    pub from: String,
+    pub to: String,
+}
+
+// 扫描结果
+#[derive(Debug, Default)]
+pub struct ScanResult {
+    pub files: Vec<ScannedFile>,
+    pub relocations: Vec<Relocation>,
+    // 无法读取（如非 UTF-8 编码）或 front matter 无法解析而跳过的文件（相对路径和原因）
+    pub skipped: Vec<(String, String)>,
+}
+
+// 扫描提示词库：assign 为 true 时为缺少 ID 的文件分配 ID，并根据索引检测文件移动
+// 未解锁的加密提示词和无法读取、解析的文件不在结果中，其索引条目保持不变；这些文件不会被改写
+// assign 为 false 时只读取，不写入文件也不修改索引（缺少 ID 的文件 ID 为空）
+pub fn scan(folder: &Path, index: &mut LibraryIndex, assign: bool) -> ScanResult {
+    let mut kept = HashSet::new();
+    let mut result = ScanResult::default();
+    for path in walk_prompt_files(folder) {
+        let rel_path = index.relative(&path);
+        let raw = match fs::read_to_string(&path) {
+            Ok(raw) => raw,
+            Err(e) => {
+                result.skipped.push((rel_path.clone(), format!("读取失败: {}", e)));
+                kept.insert(rel_path);
+                continue;
+            }
+        };
+        let Ok(raw) = crypto::decrypt_text(&raw) else {
+            kept.insert(rel_path);
+            continue;
+        };
+        match metadata::parse_strict(&raw) {
+            Ok((metadata, body)) => result.files.push(ScannedFile { path, rel_path, metadata, body }),
+            Err(e) => {
+                result.skipped.push((rel_path.clone(), e));
+                kept.insert(rel_path);
+            }
+        }
+    }
+
+    if assign {
+        assign_ids(&mut result.files, index);
+        result.relocations = reconcile(index, &result.files, &kept);
+    }
+    result
+}
+
+// 为缺少 ID 的文件分配新 ID；复制文件产生的重复 ID 只保留给索引中记录的那个文件
+fn assign_ids(files: &mut [ScannedFile], index: &LibraryIndex) {
+    let is_indexed = |f: &ScannedFile| index.path_of(f.id()) == Some(f.rel_path.as_str());
+
+    let mut claimed: HashSet<String> = files.iter()
+        .filter(|f| is_indexed(f))
+        .map(|f| f.id().to_string())
+        .collect();
+
+    for file in files.iter_mut() {
+        let keep = if file.id().is_empty() {
+            false
+        } else {
+            is_indexed(file) || claimed.insert(file.id().to_string())
+        };
+        if keep {
+            continue;
+        }
+
+        let id = new_id();
+        claimed.insert(id.clone());
+        let mut meta = file.metadata.clone();
+        meta.id = Some(id);
+        match write_id(&file.path, &meta, &file.body) {
+            Ok(()) => file.metadata = meta,
+            Err(e) => eprintln!("写入提示词 ID 失败 {}: {}", file.path.display(), e),
+        }
+    }
+}
+
+// 文件监听发现新增或修改的提示词时补充 ID 并更新索引中的位置，返回索引是否有变化
+// files 需包含与这些文件 ID 相同的其他提示词，复制文件产生的重复 ID 与扫描时一样处理
+pub fn index_files(index: &mut LibraryIndex, files: &mut [ScannedFile]) -> bool {
+    assign_ids(files, index);
+    let mut changed = false;
+    for file in files.iter().filter(|f| !f.id().is_empty()) {
+        if index.path_of(file.id()) != Some(file.rel_path.as_str()) {
+            index.update(file.id(), &file.path, &file.metadata, &file.body);
+            changed = true;
+        }
+    }
+    changed
+}
+
+// 写入 ID 前确认重新解析能得到相同的元数据和正文，避免改写后丢失内容
+fn write_id(path: &Path, meta: &PromptMetadata, body: &str) -> Result<(), String> {
+    let content = metadata::compose(meta, body)?;
+    if metadata::parse_strict(&content).ok() != Some((meta.clone(), body.to_string())) {
+        return Err("写入后无法还原原内容，已跳过".to_string());
+    }
+    metadata::write_prompt_file(path, meta, body)
+}
+
+// 用扫描结果更新索引，返回路径发生变化的条目
+// 找不到文件的条目若带有助记词则标记为孤立，留待 repair 处理
+fn reconcile(index: &mut LibraryIndex, files: &[ScannedFile], kept: &HashSet<String>) -> Vec<Relocation> {
+    let mut relocations = Vec::new();
+    let mut prompts = BTreeMap::new();
+
+    for file in files.iter().filter(|f| !f.id().is_empty()) {
+        let entry = IndexEntry::new(file.rel_path.clone(), &file.path, &file.metadata, &file.body);
+        if let Some(old) = index.prompts.get(file.id()) {
+            if old.path != entry.path {
+                relocations.push(Relocation {
+                    id: file.id().to_string(),
+                    from: old.path.clone(),
+                    to: entry.path.clone(),
+                });
+            }
+        }
+        prompts.insert(file.id().to_string(), entry);
+    }
+
+    for (id, mut entry) in std::mem::take(&mut index.prompts) {
+        if prompts.contains_key(&id) {
+            continue;
+        }
+        if kept.contains(&entry.path) {
+            prompts.insert(id, entry);
+        } else if !entry.mnemonics.is_empty() {
+            entry.orphaned = true;
+            prompts.insert(id, entry);
+        }
+    }
+
+    index.prompts = prompts;
+    relocations
+}
+
+// ====== 孤立助记词修复 ======
+
+// 可重新关联的候选文件
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RelinkCandidate {
+    pub id: String,
+    pub file_path: String,
+    pub title: String,
+    pub score: f64,
+}
+
+// 孤立的助记词条目
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OrphanedMnemonic {
+    pub id: String,
+    pub mnemonics: Vec<String>,
+    pub last_path: String,
+    pub candidates: Vec<RelinkCandidate>,
+}
+
+const MIN_RELINK_SCORE: f64 = 0.3;
+const MAX_RELINK_CANDIDATES: usize = 3;
+
+// 列出孤立助记词，并按文件名和内容相似度给出候选文件（只考虑尚无助记词的文件）
+pub fn find_orphans(index: &LibraryIndex, files: &[ScannedFile]) -> Vec<OrphanedMnemonic> {
+    index.prompts.iter()
+        .filter(|(_, e)| e.orphaned)
+        .map(|(id, entry)| {
+            let mut candidates: Vec<RelinkCandidate> = files.iter()
+                .filter(|f| f.metadata.mnemonics().is_empty())
+                .map(|f| RelinkCandidate {
+                    id: f.id().to_string(),
+                    file_path: f.path.to_string_lossy().to_string(),
+                    title: file_title(&f.path),
+                    score: similarity(entry, f),
+                })
+                .filter(|c| c.score >= MIN_RELINK_SCORE)
+                .collect();
+            candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+            candidates.truncate(MAX_RELINK_CANDIDATES);
+
+            OrphanedMnemonic {
+                id: id.clone(),
+                mnemonics: entry.mnemonics.clone(),
+                last_path: entry.path.clone(),
+                candidates,
+            }
+        })
+        .collect()
+}
+
+// 将孤立的助记词重新关联到指定提示词：目标文件改用孤立条目的 ID 和助记词，保持引用不变
+pub fn relink(index: &mut LibraryIndex, files: &[ScannedFile], orphan_id: &str, target_id: &str) -> Result<(), String> {
+    let orphan = index.prompts.get(orphan_id)
+        .filter(|e| e.orphaned)
+        .ok_or("孤立助记词不存在")?;
+    let file = files.iter().find(|f| f.id() == target_id).ok_or("提示词不存在")?;
+    crate::check_mnemonic_conflicts(files, file, &orphan.mnemonics)?;
+
+    let mut meta = file.metadata.clone();
+    meta.id = Some(orphan_id.to_string());
+    meta.set_mnemonics(orphan.mnemonics.clone());
+    metadata::write_prompt_file(&file.path, &meta, &file.body)?;
+
+    index.prompts.remove(target_id);
+    index.update(orphan_id, &file.path, &meta, &file.body);
+    Ok(())
+}
+
+// 文件名相似度与内容相似度的平均值
+fn similarity(entry: &IndexEntry, file: &ScannedFile) -> f64 {
+    let name_score = strsim::normalized_levenshtein(
+        &entry.title.to_lowercase(),
+        &file_title(&file.path).to_lowercase(),
+    );
+    let content_score = bigram_similarity(&entry.excerpt, &excerpt(&file.body));
+    (name_score + content_score) / 2.0
+}
+
+// 字符二元组的 Dice 系数
+fn bigram_similarity(a: &str, b: &str) -> f64 {
+    fn bigrams(s: &str) -> HashSet<(char, char)> {
+        let chars: Vec<char> = s.chars().filter(|c| !c.is_whitespace()).collect();
+        chars.windows(2).map(|w| (w[0], w[1])).collect()
+    }
+
+    let (a, b) = (bigrams(a), bigrams(b));
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let common = a.intersection(&b).count();
+    2.0 * common as f64 / (a.len() + b.len()) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index_for(root: &Path) -> LibraryIndex {
+        LibraryIndex::load(&root.join("missing-index.json"), root).unwrap()
+    }
+
+    #[test]
+    fn scan_skips_unreadable_and_unparsable_files_without_rewriting() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        // GBK 编码的“你好”，不是合法的 UTF-8
+        let gbk: &[u8] = &[0xc4, 0xe3, 0xba, 0xc3];
+        fs::write(root.join("gbk.md"), gbk).unwrap();
+        let broken = "---\nmnemonic: [unclosed\n---\n正文\n";
+        fs::write(root.join("broken.md"), broken).unwrap();
+        fs::write(root.join("plain.md"), "hello\n").unwrap();
+
+        let mut index = index_for(root);
+        let result = scan(root, &mut index, true);
+
+        assert_eq!(result.files.len(), 1);
+        assert_eq!(result.files[0].rel_path, "plain.md");
+        assert!(!result.files[0].id().is_empty());
+        let mut skipped: Vec<&str> = result.skipped.iter().map(|(p, _)| p.as_str()).collect();
+        skipped.sort();
+        assert_eq!(skipped, ["broken.md", "gbk.md"]);
+
+        assert_eq!(fs::read(root.join("gbk.md")).unwrap(), gbk);
+        assert_eq!(fs::read_to_string(root.join("broken.md")).unwrap(), broken);
+        let (meta, body) = metadata::read_prompt_file(&root.join("plain.md")).unwrap();
+        assert_eq!(meta.id.as_deref(), Some(result.files[0].id()));
+        assert_eq!(body, "hello\n");
+    }
+
+    #[test]
+    fn read_only_scan_does_not_write_ids_or_touch_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("plain.md"), "hello\n").unwrap();
+
+        let mut index = index_for(root);
+        let result = scan(root, &mut index, false);
+
+        assert_eq!(result.files.len(), 1);
+        assert_eq!(result.files[0].id(), "");
+        assert!(index.prompts.is_empty());
+        assert_eq!(fs::read_to_string(root.join("plain.md")).unwrap(), "hello\n");
+    }
+
+    fn scanned(root: &Path, rel: &str) -> ScannedFile {
+        let path = root.join(rel);
+        let (metadata, body) = metadata::read_prompt_file(&path).unwrap();
+        ScannedFile { path, rel_path: rel.to_string(), metadata, body }
+    }
+
+    #[test]
+    fn index_files_assigns_missing_and_duplicate_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("a.md"), "---\nid: id-a\nmnemonic: a\n---\n正文\n").unwrap();
+        let mut index = index_for(root);
+        scan(root, &mut index, true);
+
+        // 外部新增的文件、复制的文件和移动后的文件
+        fs::write(root.join("new.md"), "新文件\n").unwrap();
+        fs::copy(root.join("a.md"), root.join("copy.md")).unwrap();
+        let mut files = vec![scanned(root, "a.md"), scanned(root, "copy.md"), scanned(root, "new.md")];
+        assert!(index_files(&mut index, &mut files));
+
+        let ids: Vec<&str> = files.iter().map(|f| f.id()).collect();
+        assert_eq!(ids[0], "id-a");
+        assert!(!ids[1].is_empty() && ids[1] != "id-a");
+        assert!(!ids[2].is_empty() && ids[2] != ids[1]);
+        for file in &files {
+            assert_eq!(index.path_of(file.id()), Some(file.rel_path.as_str()));
+            assert_eq!(scanned(root, &file.rel_path).id(), file.id());
+        }
+        // 复制的文件只改写 ID，正文不变
+        assert_eq!(scanned(root, "copy.md").body, "正文\n");
+
+        // 已有 ID 且位置未变时不修改
+        let mut unchanged = vec![scanned(root, "a.md")];
+        assert!(!index_files(&mut index, &mut unchanged));
+
+        // 移动后保留 ID，只更新位置
+        fs::rename(root.join("a.md"), root.join("moved.md")).unwrap();
+        let mut moved = vec![scanned(root, "moved.md")];
+        assert!(index_files(&mut index, &mut moved));
+        assert_eq!(moved[0].id(), "id-a");
+        assert_eq!(index.path_of("id-a"), Some("moved.md"));
+    }
+
+    #[test]
+    fn deleted_prompts_with_mnemonics_become_orphans_and_can_be_relinked() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("work")).unwrap();
+        let review = "请审查以下代码，指出潜在的错误、性能问题和可读性问题。\n";
+        fs::write(root.join("work/code review.md"), format!("---\nid: id-cr\nmnemonic: cr\naliases: [review]\n---\n{}", review)).unwrap();
+        fs::write(root.join("work/plain.md"), "---\nid: id-plain\n---\n没有助记词\n").unwrap();
+        fs::write(root.join("work/taken.md"), "---\nid: id-taken\nmnemonic: x\n---\n请审查以下代码\n").unwrap();
+        let mut index = index_for(root);
+        scan(root, &mut index, true);
+
+        // 外部工具重写文件时丢失了 front matter（ID 和助记词），并且改了名
+        fs::remove_file(root.join("work/code review.md")).unwrap();
+        fs::write(root.join("work/Code Review v2.md"), review).unwrap();
+        fs::remove_file(root.join("work/plain.md")).unwrap();
+        let files = scan(root, &mut index, true).files;
+
+        assert!(index.prompts["id-cr"].orphaned);
+        assert_eq!(index.path_of("id-cr"), None);
+        // 没有助记词的条目直接移除
+        assert!(!index.prompts.contains_key("id-plain"));
+
+        let orphans = find_orphans(&index, &files);
+        assert_eq!(orphans.len(), 1);
+        let orphan = &orphans[0];
+        assert_eq!((orphan.id.as_str(), orphan.last_path.as_str()), ("id-cr", "work/code review.md"));
+        assert_eq!(orphan.mnemonics, ["cr", "review"]);
+        // 已有助记词的文件不作为候选
+        let titles: Vec<&str> = orphan.candidates.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(titles, ["Code Review v2"]);
+        let target_id = orphan.candidates[0].id.clone();
+        assert!(orphan.candidates[0].score > 0.8);
+
+        assert_eq!(relink(&mut index, &files, "id-missing", &target_id).unwrap_err(), "孤立助记词不存在");
+        assert_eq!(relink(&mut index, &files, "id-cr", "id-missing").unwrap_err(), "提示词不存在");
+        relink(&mut index, &files, "id-cr", &target_id).unwrap();
+
+        let (meta, body) = metadata::read_prompt_file(&root.join("work/Code Review v2.md")).unwrap();
+        assert_eq!(meta.id.as_deref(), Some("id-cr"));
+        assert_eq!(meta.mnemonics(), ["cr", "review"]);
+        assert_eq!(body, review);
+        assert_eq!(index.path_of("id-cr"), Some("work/Code Review v2.md"));
+        assert!(!index.prompts.contains_key(&target_id));
+        let files = scan(root, &mut index, true).files;
+        assert!(find_orphans(&index, &files).is_empty());
+    }
+
+    #[test]
+    fn relink_rejects_mnemonics_taken_in_the_same_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("old.md"), "---\nid: id-old\nmnemonic: x\n---\n旧\n").unwrap();
+        let mut index = index_for(root);
+        scan(root, &mut index, true);
+        fs::remove_file(root.join("old.md")).unwrap();
+        fs::write(root.join("taken.md"), "---\nid: id-taken\nmnemonic: x\n---\n新\n").unwrap();
+        fs::write(root.join("target.md"), "---\nid: id-target\n---\n旧\n").unwrap();
+        let files = scan(root, &mut index, true).files;
+
+        let err = relink(&mut index, &files, "id-old", "id-target").unwrap_err();
+        assert!(err.contains("'x'") && err.contains("taken"), "{}", err);
+        assert!(index.prompts["id-old"].orphaned);
+        assert_eq!(metadata::read_prompt_file(&root.join("target.md")).unwrap().0.id.as_deref(), Some("id-target"));
+    }
+
+    #[test]
+    fn load_rebases_entries_when_the_library_moves() {
+        let dir = tempfile::tempdir().unwrap();
+        let index_path = dir.path().join("index.json");
+        let (old_root, new_root) = (dir.path().join("old"), dir.path().join("new"));
+        let entry = |path: &Path| serde_json::json!({ "path": path.to_string_lossy() });
+        let saved = serde_json::json!({
+            "root": old_root.to_string_lossy(),
+            "prompts": {
+                // 库被移动前记录的绝对路径、旧版索引中新根目录下的绝对路径、相对路径和库外路径
+                "moved": entry(&old_root.join("work/a.md")),
+                "legacy": entry(&new_root.join("b.md")),
+                "relative": { "path": "c.md" },
+                "outside": entry(&dir.path().join("elsewhere/d.md")),
+            }
+        });
+        fs::write(&index_path, saved.to_string()).unwrap();
+
+        let index = LibraryIndex::load(&index_path, &new_root).unwrap();
+        assert_eq!(index.root, new_root.to_string_lossy());
+        assert_eq!(index.path_of("moved"), Some("work/a.md"));
+        assert_eq!(index.path_of("legacy"), Some("b.md"));
+        assert_eq!(index.path_of("relative"), Some("c.md"));
+        assert_eq!(index.resolve("moved"), Some(new_root.join("work/a.md")));
+        assert_eq!(index.resolve("relative"), Some(new_root.join("c.md")));
+        let outside = dir.path().join("elsewhere/d.md");
+        assert_eq!(index.path_of("outside"), Some(outside.to_string_lossy().as_ref()));
+        assert_eq!(index.resolve("outside"), Some(outside));
+
+        // 保存后再次加载不再变化
+        index.save(&index_path).unwrap();
+        let reloaded = LibraryIndex::load(&index_path, &new_root).unwrap();
+        assert_eq!(reloaded.path_of("moved"), Some("work/a.md"));
+        assert_eq!(reloaded.root, index.root);
+    }
+}
@@ -1,6 +1,11 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-fn main() {
-    prompt_manager_lib::run()
+fn main() -> std::process::ExitCode {
+    // prompt-manager mcp：以 MCP 服务器模式运行（stdio），不启动界面
+    if std::env::args().nth(1).as_deref() == Some("mcp") {
+        return prompt_manager_lib::run_mcp();
+    }
+    prompt_manager_lib::run();
+    std::process::ExitCode::SUCCESS
 }
@@ -0,0 +1,502 @@
+// MCP 服务器 - 通过 Model Context Protocol（stdio 传输）向 LLM 智能体提供提示词库
+// 启动方式：prompt-manager mcp（不启动界面，与桌面应用共用配置和提示词库）
+//
+//   prompts/list / prompts/get       提示词，模板中的 {{input:...}}、{{selection}}、{{clipboard}} 映射为参数
+//   resources/list / resources/read  提示词文件原文（含 front matter），URI 为 prompt-manager:///相对路径
+//
+// 提示词名称为带文件夹的主助记词（如 work/sum），没有助记词时为相对路径（不含扩展名）
+// stdout 只用于协议消息，日志写到 stderr
+use crate::library::{self, ScannedFile};
+use crate::search_index::SearchIndex;
+use crate::{crypto, mnemonic, template};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, Write};
+use std::process::ExitCode;
+
+// 支持的协议版本（第一个为最新）
+const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+const URI_PREFIX: &str = "prompt-manager:///";
+// 资源 URI 中路径各段保留的字符
+const SEGMENT: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'_').remove(b'.').remove(b'~');
+
+// 映射为参数的特殊占位符
+const SELECTION_ARG: &str = "selection";
+const CLIPBOARD_ARG: &str = "clipboard";
+
+// JSON-RPC 错误
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn invalid_request(message: impl Into<String>) -> Self {
+        Self { code: -32600, message: message.into() }
+    }
+
+    fn method_not_found(method: &str) -> Self {
+        Self { code: -32601, message: format!("不支持的方法: {}", method) }
+    }
+
+    fn invalid_params(message: impl Into<String>) -> Self {
+        Self { code: -32602, message: message.into() }
+    }
+
+    fn resource_not_found(uri: &str) -> Self {
+        Self { code: -32002, message: format!("资源不存在: {}", uri) }
+    }
+}
+
+impl From<String> for RpcError {
+    fn from(message: String) -> Self {
+        Self { code: -32603, message }
+    }
+}
+
+// MCP 服务器入口：逐行读取 JSON-RPC 消息，直到 stdin 关闭
+pub fn run() -> ExitCode {
+    if let Err(e) = crypto::unlock_from_env(&crate::get_key_path()) {
+        eprintln!("解锁加密的提示词失败: {}", e);
+    }
+
+    let mut server = McpServer::default();
+    let mut stdout = std::io::stdout().lock();
+    for line in std::io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = server.handle_message(&line) {
+            if writeln!(stdout, "{}", response).and_then(|_| stdout.flush()).is_err() {
+                break;
+            }
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+// 一个提示词及其在 MCP 中的名称
+struct NamedPrompt {
+    name: String,
+    file: ScannedFile,
+}
+
+#[derive(Default)]
+struct McpServer {
+    // 只读的搜索索引（不写入 ID 和缓存文件，避免与运行中的应用冲突）
+    index: Option<SearchIndex>,
+}
+
+impl McpServer {
+    // 处理一条消息，通知（没有 id）不返回响应
+    fn handle_message(&mut self, line: &str) -> Option<Value> {
+        let message: Value = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(e) => return Some(error_response(Value::Null, -32700, &format!("解析消息失败: {}", e))),
+        };
+        let id = message.get("id").cloned();
+        let result = match message.get("method").and_then(Value::as_str) {
+            Some(method) => self.dispatch(method, message.get("params").unwrap_or(&Value::Null)),
+            None => Err(RpcError::invalid_request("缺少 method")),
+        };
+
+        let id = id?;
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(e) => error_response(id, e.code, &e.message),
+        })
+    }
+
+    fn dispatch(&mut self, method: &str, params: &Value) -> Result<Value, RpcError> {
+        match method {
+            "initialize" => Ok(initialize(params)),
+            "ping" => Ok(json!({})),
+            "prompts/list" => self.list_prompts(),
+            "prompts/get" => self.get_prompt(params),
+            "resources/list" => self.list_resources(),
+            "resources/read" => self.read_resource(params),
+            "resources/templates/list" => Ok(json!({ "resourceTemplates": [] })),
+            method if method.starts_with("notifications/") => Ok(Value::Null),
+            method => Err(RpcError::method_not_found(method)),
+        }
+    }
+
+    // 读取提示词库（每次请求前按 mtime/size 刷新）
+    fn files(&mut self) -> Result<Vec<ScannedFile>, RpcError> {
+        let root = crate::library_root()?;
+        match self.index.as_mut() {
+            Some(index) if index.root() == root => {
+                index.refresh();
+            }
+            _ => self.index = Some(SearchIndex::load(&crate::get_search_cache_path(), &root)),
+        }
+        Ok(self.index.as_ref().map(SearchIndex::files).unwrap_or_default())
+    }
+
+    fn named_prompts(&mut self) -> Result<Vec<NamedPrompt>, RpcError> {
+        Ok(name_prompts(self.files()?))
+    }
+
+    fn list_prompts(&mut self) -> Result<Value, RpcError> {
+        let named = self.named_prompts()?;
+        let files: Vec<ScannedFile> = named.iter().map(|p| p.file.clone()).collect();
+        let prompts = named.iter()
+            .map(|p| {
+                let segments = parse(&p.file, &files)?;
+                let mut prompt = json!({
+                    "name": p.name,
+                    "title": library::file_title(&p.file.path),
+                    "arguments": arguments(&segments),
+                    "_meta": prompt_meta(&p.file),
+                });
+                if let Some(description) = description(&p.file) {
+                    prompt["description"] = json!(description);
+                }
+                Ok(prompt)
+            })
+            .collect::<Result<Vec<_>, RpcError>>()?;
+        Ok(json!({ "prompts": prompts }))
+    }
+
+    fn get_prompt(&mut self, params: &Value) -> Result<Value, RpcError> {
+        let name = params.get("name")
+            .and_then(Value::as_str)
+            .ok_or_else(|| RpcError::invalid_params("缺少 name"))?;
+        let mut args: HashMap<String, String> = match params.get("arguments") {
+            None | Some(Value::Null) => HashMap::new(),
+            Some(args) => serde_json::from_value(args.clone())
+                .map_err(|_| RpcError::invalid_params("arguments 的值应为字符串"))?,
+        };
+
+        let named = self.named_prompts()?;
+        let files: Vec<ScannedFile> = named.iter().map(|p| p.file.clone()).collect();
+        // 除了列表中的名称，也接受 ID、其他助记词和相对路径
+        let file = match named.iter().find(|p| p.name == name) {
+            Some(p) => &p.file,
+            None => mnemonic::find_prompt(&files, name).map_err(RpcError::invalid_params)?,
+        };
+
+        let segments = parse(file, &files)?;
+        let ctx = template::RenderContext {
+            selection: args.remove(SELECTION_ARG),
+            clipboard: args.remove(CLIPBOARD_ARG),
+            inputs: args,
+        };
+        let missing = template::missing_inputs(&segments, &ctx.inputs);
+        if !missing.is_empty() {
+            return Err(RpcError::invalid_params(format!("缺少参数: {}", missing.join(", "))));
+        }
+        let rendered = template::render(&segments, &ctx).map_err(RpcError::invalid_params)?;
+        crate::record_usage(Some(file.id()));
+
+        let mut result = json!({
+            "messages": [{
+                "role": "user",
+                "content": { "type": "text", "text": rendered.text },
+            }],
+        });
+        if let Some(description) = description(file) {
+            result["description"] = json!(description);
+        }
+        Ok(result)
+    }
+
+    fn list_resources(&mut self) -> Result<Value, RpcError> {
+        let resources: Vec<Value> = self.files()?
+            .iter()
+            .map(|file| {
+                let mut resource = json!({
+                    "uri": resource_uri(&file.rel_path),
+                    "name": file.rel_path,
+                    "title": library::file_title(&file.path),
+                    "mimeType": mime_type(&file.rel_path),
+                });
+                if let Some(description) = description(file) {
+                    resource["description"] = json!(description);
+                }
+                resource
+            })
+            .collect();
+        Ok(json!({ "resources": resources }))
+    }
+
+    fn read_resource(&mut self, params: &Value) -> Result<Value, RpcError> {
+        let uri = params.get("uri")
+            .and_then(Value::as_str)
+            .ok_or_else(|| RpcError::invalid_params("缺少 uri"))?;
+        let rel_path = uri.strip_prefix(URI_PREFIX)
+            .map(|path| percent_decode_str(path).decode_utf8_lossy().to_string())
+            .ok_or_else(|| RpcError::resource_not_found(uri))?;
+        let files = self.files()?;
+        let file = files.iter()
+            .find(|f| f.rel_path == rel_path)
+            .ok_or_else(|| RpcError::resource_not_found(uri))?;
+        resource_contents(uri, file)
+    }
+}
+
+// 为提示词分配唯一名称：优先使用带文件夹的主助记词，重名时使用相对路径
+fn name_prompts(files: Vec<ScannedFile>) -> Vec<NamedPrompt> {
+    let path_names: HashSet<String> = files.iter().map(|f| template::partial_key(&f.rel_path)).collect();
+    let mut used = HashSet::new();
+    let mut prompts: Vec<NamedPrompt> = files.into_iter()
+        .map(|file| {
+            let path_name = template::partial_key(&file.rel_path);
+            let name = file.metadata.mnemonics()
+                .first()
+                .map(|m| mnemonic::qualified(&mnemonic::namespace_of(&file.rel_path), m))
+                .filter(|name| *name == path_name || !path_names.contains(name))
+                .unwrap_or(path_name);
+            let name = if used.insert(name.clone()) { name } else { file.rel_path.clone() };
+            NamedPrompt { name, file }
+        })
+        .collect();
+    prompts.sort_by(|a, b| a.name.cmp(&b.name));
+    prompts
+}
+
+// 资源内容为文件原文（加密的提示词解密后返回），不重新生成 front matter，保留注释和字段顺序
+fn resource_contents(uri: &str, file: &ScannedFile) -> Result<Value, RpcError> {
+    let (text, _) = crypto::read_text(&file.path)?;
+    Ok(json!({
+        "contents": [{
+            "uri": uri,
+            "mimeType": mime_type(&file.rel_path),
+            "text": text,
+        }],
+    }))
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+// 协商协议版本：客户端请求的版本受支持时使用该版本，否则使用最新版本
+fn initialize(params: &Value) -> Value {
+    let requested = params.get("protocolVersion").and_then(Value::as_str);
+    let version = requested
+        .filter(|v| PROTOCOL_VERSIONS.contains(v))
+        .unwrap_or(PROTOCOL_VERSIONS[0]);
+    json!({
+        "protocolVersion": version,
+        "capabilities": {
+            "prompts": { "listChanged": false },
+            "resources": { "subscribe": false, "listChanged": false },
+        },
+        "serverInfo": {
+            "name": "prompt-manager",
+            "title": "提示词管理器",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+    })
+}
+
+// 展开片段后解析模板
+fn parse(file: &ScannedFile, files: &[ScannedFile]) -> Result<Vec<template::Segment>, RpcError> {
+    let text = crate::expand_partials(&file.body, || Ok(files.to_vec()))?;
+    Ok(template::parse(&text))
+}
+
+// 模板参数：输入项（没有默认值的为必填），以及用到的选中文本和剪贴板（可选）
+fn arguments(segments: &[template::Segment]) -> Vec<Value> {
+    let mut args: Vec<Value> = template::required_inputs(segments)
+        .into_iter()
+        .map(|input| {
+            let mut arg = json!({ "name": input.name, "required": input.default.is_none() });
+            let description = match (input.label, input.default) {
+                (Some(label), Some(default)) => Some(format!("{}（默认: {}）", label, default)),
+                (Some(label), None) => Some(label),
+                (None, Some(default)) => Some(format!("默认: {}", default)),
+                (None, None) => None,
+            };
+            if let Some(description) = description {
+                arg["description"] = json!(description);
+            }
+            arg
+        })
+        .collect();
+    if template::uses_selection(segments) {
+        args.push(json!({ "name": SELECTION_ARG, "description": "选中的文本", "required": false }));
+    }
+    if template::uses_clipboard(segments) {
+        args.push(json!({ "name": CLIPBOARD_ARG, "description": "剪贴板内容", "required": false }));
+    }
+    args
+}
+
+// 提示词说明：front matter 中的 description，没有时为正文第一行
+fn description(file: &ScannedFile) -> Option<String> {
+    file.metadata.description.clone()
+        .filter(|d| !d.trim().is_empty())
+        .or_else(|| {
+            file.body.lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+                .map(|line| line.chars().take(100).collect())
+        })
+}
+
+// 助记词、标签等元数据
+fn prompt_meta(file: &ScannedFile) -> Value {
+    let namespace = mnemonic::namespace_of(&file.rel_path);
+    let mnemonics: Vec<String> = file.metadata.mnemonics()
+        .iter()
+        .map(|m| mnemonic::qualified(&namespace, m))
+        .collect();
+    json!({
+        "id": file.id(),
+        "path": file.rel_path,
+        "mnemonics": mnemonics,
+        "tags": file.metadata.tags,
+    })
+}
+
+fn resource_uri(rel_path: &str) -> String {
+    let encoded: Vec<String> = rel_path.split('/')
+        .map(|segment| utf8_percent_encode(segment, SEGMENT).to_string())
+        .collect();
+    format!("{}{}", URI_PREFIX, encoded.join("/"))
+}
+
+fn mime_type(rel_path: &str) -> &'static str {
+    if rel_path.ends_with(".md") {
+        "text/markdown"
+    } else {
+        "text/plain"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::{self, PromptMetadata};
+    use std::path::PathBuf;
+
+    fn file(rel_path: &str, mnemonics: &[&str]) -> ScannedFile {
+        let mut metadata = PromptMetadata { id: Some(format!("id-{}", rel_path)), ..Default::default() };
+        metadata.set_mnemonics(mnemonics.iter().map(|m| m.to_string()).collect());
+        ScannedFile {
+            path: PathBuf::from("/library").join(rel_path),
+            rel_path: rel_path.to_string(),
+            metadata,
+            body: String::new(),
+        }
+    }
+
+    fn request(server: &mut McpServer, message: Value) -> Option<Value> {
+        server.handle_message(&message.to_string())
+    }
+
+    #[test]
+    fn handles_protocol_errors_and_notifications() {
+        let mut server = McpServer::default();
+        // 通知没有 id，不返回响应（即使方法不存在）
+        assert_eq!(request(&mut server, json!({"jsonrpc": "2.0", "method": "notifications/initialized"})), None);
+        assert_eq!(request(&mut server, json!({"jsonrpc": "2.0", "method": "no/such"})), None);
+
+        let response = server.handle_message("{not json").unwrap();
+        assert_eq!(response["id"], Value::Null);
+        assert_eq!(response["error"]["code"], -32700);
+
+        let response = request(&mut server, json!({"jsonrpc": "2.0", "id": 7, "method": "no/such"})).unwrap();
+        assert_eq!(response["id"], 7);
+        assert_eq!(response["error"]["code"], -32601);
+        assert_eq!(response["error"]["message"], "不支持的方法: no/such");
+
+        let response = request(&mut server, json!({"jsonrpc": "2.0", "id": "a"})).unwrap();
+        assert_eq!(response["error"]["code"], -32600);
+
+        let response = request(&mut server, json!({"jsonrpc": "2.0", "id": 1, "method": "ping"})).unwrap();
+        assert_eq!(response, json!({"jsonrpc": "2.0", "id": 1, "result": {}}));
+
+        let response = request(&mut server, json!({"jsonrpc": "2.0", "id": 2, "method": "prompts/get", "params": {}})).unwrap();
+        assert_eq!(response["error"]["code"], -32602);
+    }
+
+    #[test]
+    fn initialize_negotiates_protocol_version() {
+        let mut server = McpServer::default();
+        let cases = [
+            (json!({"protocolVersion": "2024-11-05"}), "2024-11-05"),
+            (json!({"protocolVersion": "2025-03-26"}), "2025-03-26"),
+            // 不支持的版本和缺少版本时使用最新版本
+            (json!({"protocolVersion": "2099-01-01"}), PROTOCOL_VERSIONS[0]),
+            (json!({"protocolVersion": 1}), PROTOCOL_VERSIONS[0]),
+            (json!({}), PROTOCOL_VERSIONS[0]),
+        ];
+        for (params, expected) in cases {
+            let message = json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": params});
+            let response = request(&mut server, message).unwrap();
+            assert_eq!(response["result"]["protocolVersion"], expected, "{}", params);
+            assert_eq!(response["result"]["serverInfo"]["name"], "prompt-manager");
+        }
+    }
+
+    #[test]
+    fn names_prompts_without_collisions() {
+        let files = vec![
+            file("a.md", &[]),
+            file("a.txt", &[]),
+            file("personal/sum.md", &["sum"]),
+            file("work/daily.md", &["sum"]),
+            file("work/report.md", &["sum"]),
+            // 助记词与其他文件的路径名相同时使用自己的路径名
+            file("work/s.md", &[]),
+            file("work/x.md", &["s"]),
+            file("work/y.md", &["y"]),
+        ];
+        let names: Vec<(String, String)> = name_prompts(files).into_iter()
+            .map(|p| (p.name, p.file.rel_path))
+            .collect();
+        let expected = [
+            ("a", "a.md"),
+            ("a.txt", "a.txt"),
+            ("personal/sum", "personal/sum.md"),
+            ("work/report.md", "work/report.md"),
+            ("work/s", "work/s.md"),
+            ("work/sum", "work/daily.md"),
+            ("work/x", "work/x.md"),
+            ("work/y", "work/y.md"),
+        ];
+        let expected: Vec<(String, String)> = expected.iter().map(|(n, p)| (n.to_string(), p.to_string())).collect();
+        assert_eq!(names, expected);
+    }
+
+    #[test]
+    fn maps_special_placeholders_to_arguments() {
+        let segments = template::parse(
+            "{{input:lang|label=语言|default=Rust}} {{input:topic}} {{input:lang}} {{selection}} {{clipboard}} {{date}}",
+        );
+        assert_eq!(arguments(&segments), [
+            json!({"name": "lang", "required": false, "description": "语言（默认: Rust）"}),
+            json!({"name": "topic", "required": true}),
+            json!({"name": "selection", "description": "选中的文本", "required": false}),
+            json!({"name": "clipboard", "description": "剪贴板内容", "required": false}),
+        ]);
+        assert!(arguments(&template::parse("纯文本 {{date}} {{cursor}}")).is_empty());
+        assert_eq!(
+            arguments(&template::parse("{{input:n|default=1}}")),
+            [json!({"name": "n", "required": false, "description": "默认: 1"})],
+        );
+    }
+
+    #[test]
+    fn resource_is_raw_file_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let raw = "---\n# 注释\ntags: [b, a]\nid: abc\nmnemonic: sum\n---\n正文\n";
+        let path = dir.path().join("sum.md");
+        std::fs::write(&path, raw).unwrap();
+        let (metadata, body) = metadata::parse(raw);
+        let file = ScannedFile { path, rel_path: "work/sum.md".to_string(), metadata, body };
+
+        let uri = resource_uri(&file.rel_path);
+        let result = resource_contents(&uri, &file).ok().unwrap();
+        assert_eq!(result["contents"][0]["text"], raw);
+        assert_eq!(result["contents"][0]["mimeType"], "text/markdown");
+        assert_eq!(uri, "prompt-manager:///work/sum.md");
+        assert_eq!(resource_uri("写作/a b.txt"), "prompt-manager:///%E5%86%99%E4%BD%9C/a%20b.txt");
+    }
+}
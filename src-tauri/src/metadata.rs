@@ -0,0 +1,283 @@
+// 提示词元数据 - 读写 .md 文件头部的 YAML front matter
+use crate::crypto;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// front matter 中的元数据
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct PromptMetadata {
+    // 稳定 ID，文件重命名或移动后保持不变
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    // 主助记词
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mnemonic: Option<String>,
+    // 其他别名
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated: Option<String>,
+    // 其他自定义字段，原样保留
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_yaml::Value>,
+}
+
+impl PromptMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.id.is_none()
+            && self.mnemonic.is_none()
+            && self.aliases.is_empty()
+            && self.tags.is_empty()
+            && self.description.is_none()
+            && self.created.is_none()
+            && self.updated.is_none()
+            && self.extra.is_empty()
+    }
+
+    // 主助记词和所有别名（已规范化、去重）
+    pub fn mnemonics(&self) -> Vec<String> {
+        let mut result: Vec<String> = Vec::new();
+        for m in self.mnemonic.iter().chain(self.aliases.iter()) {
+            let m = crate::mnemonic::normalize(m);
+            if !m.is_empty() && !result.contains(&m) {
+                result.push(m);
+            }
+        }
+        result
+    }
+
+    // 设置助记词列表：第一个为主助记词，其余为别名
+    pub fn set_mnemonics(&mut self, mnemonics: Vec<String>) {
+        let mut iter = mnemonics.into_iter();
+        self.mnemonic = iter.next();
+        self.aliases = iter.collect();
+    }
+}
+
+// 当前时间（RFC 3339，本地时区）
+pub fn now_timestamp() -> String {
+    chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false)
+}
+
+// 拆分 front matter，返回 (yaml, 正文)；没有 front matter 时返回 None
+fn split_front_matter(raw: &str) -> Option<(&str, &str)> {
+    let text = raw.strip_prefix('\u{feff}').unwrap_or(raw);
+    let rest = text
+        .strip_prefix("---\r\n")
+        .or_else(|| text.strip_prefix("---\n"))?;
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        let trimmed = line.trim_end_matches(['\r', '\n']);
+        if trimmed == "---" || trimmed == "..." {
+            return Some((&rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+// 解析文件内容为 (元数据, 正文)
+// front matter 无法解析时整份内容都作为正文，保证不丢数据
+pub fn parse(raw: &str) -> (PromptMetadata, String) {
+    if let Some((yaml, body)) = split_front_matter(raw) {
+        if yaml.trim().is_empty() {
+            return (PromptMetadata::default(), body.to_string());
+        }
+        if let Ok(meta) = serde_yaml::from_str::<PromptMetadata>(yaml) {
+            return (meta, body.to_string());
+        }
+    }
+    (PromptMetadata::default(), raw.to_string())
+}
+
+// 严格解析：front matter 存在但无法解析时返回错误（用于决定能否安全地改写文件）
+pub fn parse_strict(raw: &str) -> Result<(PromptMetadata, String), String> {
+    match split_front_matter(raw) {
+        Some((yaml, body)) if !yaml.trim().is_empty() => serde_yaml::from_str::<PromptMetadata>(yaml)
+            .map(|meta| (meta, body.to_string()))
+            .map_err(|e| format!("解析 front matter 失败: {}", e)),
+        _ => Ok(parse(raw)),
+    }
+}
+
+// 将元数据和正文组合为文件内容（元数据为空时不写 front matter）
+pub fn compose(meta: &PromptMetadata, body: &str) -> Result<String, String> {
+    if meta.is_empty() {
+        return Ok(body.to_string());
+    }
+    let yaml = serde_yaml::to_string(meta)
+        .map_err(|e| format!("序列化元数据失败: {}", e))?;
+    Ok(format!("---\n{}---\n{}", yaml, body))
+}
+
+// 读取提示词文件（加密的提示词需已解锁）
+pub fn read_prompt_file(path: &Path) -> Result<(PromptMetadata, String), String> {
+    let (raw, _) = crypto::read_text(path)?;
+    Ok(parse(&raw))
+}
+
+// 写入提示词文件（位于加密文件夹中或原本已加密时加密保存）
+pub fn write_prompt_file(path: &Path, meta: &PromptMetadata, body: &str) -> Result<(), String> {
+    let mut content = compose(meta, body)?;
+    if crypto::should_encrypt(path) {
+        content = crypto::encrypt_text(&content)?;
+    }
+    fs::write(path, content).map_err(|e| format!("保存文件失败: {}", e))
+}
+
+// ====== 旧版 prompts-meta.json 迁移 ======
+
+// 旧版助记词映射: mnemonic -> file_path
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct LegacyPromptsMeta {
+    #[serde(default)]
+    mnemonics: BTreeMap<String, String>,
+}
+
+// 旧版元数据迁移结果
+#[derive(Debug, Default, PartialEq)]
+pub struct LegacyMigration {
+    pub migrated: usize,
+    // 未能迁移的条目数（文件不存在、无法读写或已有不同的助记词），保存在 unmigrated_path 中
+    pub unmigrated: usize,
+    pub unmigrated_path: Option<PathBuf>,
+}
+
+// 将一个助记词写入文件，返回 false 表示未迁移
+fn migrate_entry(path: &Path, mnemonic: &str) -> Result<bool, String> {
+    if !path.is_file() {
+        return Ok(false);
+    }
+    // front matter 无法解析时不写入，避免覆盖其中的内容
+    let (raw, _) = crypto::read_text(path)?;
+    let (mut meta, body) = parse_strict(&raw)?;
+    match meta.mnemonic.as_deref() {
+        Some(existing) => Ok(existing == mnemonic),
+        None => {
+            meta.mnemonic = Some(mnemonic.to_string());
+            write_prompt_file(path, &meta, &body)?;
+            Ok(true)
+        }
+    }
+}
+
+// 将旧版 prompts-meta.json 中的助记词写入各文件的 front matter
+// 迁移完成后原文件重命名为 prompts-meta.json.migrated；
+// 未能迁移的条目以相同格式写入 prompts-meta.json.unmigrated，便于手动处理
+pub fn migrate_legacy_meta(legacy_path: &Path) -> Result<LegacyMigration, String> {
+    let mut result = LegacyMigration::default();
+    if !legacy_path.exists() {
+        return Ok(result);
+    }
+
+    let content = fs::read_to_string(legacy_path)
+        .map_err(|e| format!("读取旧版元数据失败: {}", e))?;
+    let legacy: LegacyPromptsMeta = serde_json::from_str(&content)
+        .map_err(|e| format!("解析旧版元数据失败: {}", e))?;
+
+    let mut unmigrated = LegacyPromptsMeta::default();
+    for (mnemonic, file_path) in legacy.mnemonics {
+        match migrate_entry(Path::new(&file_path), &mnemonic) {
+            Ok(true) => result.migrated += 1,
+            Ok(false) => {
+                unmigrated.mnemonics.insert(mnemonic, file_path);
+            }
+            Err(e) => {
+                eprintln!("迁移助记词 {} 失败: {}", mnemonic, e);
+                unmigrated.mnemonics.insert(mnemonic, file_path);
+            }
+        }
+    }
+
+    if !unmigrated.mnemonics.is_empty() {
+        let path = legacy_path.with_extension("json.unmigrated");
+        let content = serde_json::to_string_pretty(&unmigrated)
+            .map_err(|e| format!("序列化未迁移的助记词失败: {}", e))?;
+        fs::write(&path, content).map_err(|e| format!("保存未迁移的助记词失败: {}", e))?;
+        result.unmigrated = unmigrated.mnemonics.len();
+        result.unmigrated_path = Some(path);
+    }
+
+    let backup = legacy_path.with_extension("json.migrated");
+    fs::rename(legacy_path, &backup)
+        .map_err(|e| format!("备份旧版元数据失败: {}", e))?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_legacy_meta_and_keeps_unmigrated_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = |name: &str, content: &str| {
+            let path = dir.path().join(name);
+            fs::write(&path, content).unwrap();
+            path.to_string_lossy().to_string()
+        };
+        let plain = file("plain.md", "正文");
+        let same = file("same.md", "---\nmnemonic: same\n---\n正文");
+        let other = file("other.md", "---\nmnemonic: keep\n---\n正文");
+        let broken = file("broken.md", "---\nmnemonic: [unclosed\n---\n正文");
+        let missing = dir.path().join("missing.md").to_string_lossy().to_string();
+
+        let legacy_path = dir.path().join("prompts-meta.json");
+        let legacy = serde_json::json!({"mnemonics": {
+            "plain": plain, "same": same, "other": other, "broken": broken, "missing": missing,
+        }});
+        fs::write(&legacy_path, legacy.to_string()).unwrap();
+
+        let result = migrate_legacy_meta(&legacy_path).unwrap();
+        let sidecar = dir.path().join("prompts-meta.json.unmigrated");
+        assert_eq!(result, LegacyMigration { migrated: 2, unmigrated: 3, unmigrated_path: Some(sidecar.clone()) });
+
+        assert_eq!(read_prompt_file(Path::new(&plain)).unwrap().0.mnemonic.as_deref(), Some("plain"));
+        // 已有其他助记词和无法解析的文件不修改
+        assert_eq!(fs::read_to_string(&other).unwrap(), "---\nmnemonic: keep\n---\n正文");
+        assert_eq!(fs::read_to_string(&broken).unwrap(), "---\nmnemonic: [unclosed\n---\n正文");
+
+        let saved: LegacyPromptsMeta = serde_json::from_str(&fs::read_to_string(&sidecar).unwrap()).unwrap();
+        assert_eq!(saved.mnemonics, BTreeMap::from([
+            ("broken".to_string(), broken),
+            ("missing".to_string(), missing),
+            ("other".to_string(), other),
+        ]));
+        assert!(!legacy_path.exists());
+        assert!(dir.path().join("prompts-meta.json.migrated").exists());
+
+        // 再次运行时没有可迁移的内容
+        assert_eq!(migrate_legacy_meta(&legacy_path).unwrap(), LegacyMigration::default());
+    }
+
+    #[test]
+    fn fully_migrated_legacy_meta_writes_no_sidecar() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.md");
+        fs::write(&path, "正文").unwrap();
+        let legacy_path = dir.path().join("prompts-meta.json");
+        fs::write(&legacy_path, serde_json::json!({"mnemonics": {"a": path}}).to_string()).unwrap();
+
+        let result = migrate_legacy_meta(&legacy_path).unwrap();
+        assert_eq!(result, LegacyMigration { migrated: 1, unmigrated: 0, unmigrated_path: None });
+        assert!(!dir.path().join("prompts-meta.json.unmigrated").exists());
+    }
+
+    #[test]
+    fn invalid_legacy_meta_is_left_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let legacy_path = dir.path().join("prompts-meta.json");
+        fs::write(&legacy_path, "{ not json").unwrap();
+        assert!(migrate_legacy_meta(&legacy_path).is_err());
+        assert!(legacy_path.exists());
+    }
+}
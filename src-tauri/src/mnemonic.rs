@@ -0,0 +1,269 @@
+// 助记词 - 多别名与按文件夹划分的命名空间
+// 每个文件夹是一个命名空间，同一命名空间内助记词唯一，不同文件夹可以重名（如 work/sum 与 personal/sum）
+use crate::library::ScannedFile;
+use serde::{Deserialize, Serialize};
+
+pub fn normalize(mnemonic: &str) -> String {
+    mnemonic.trim().to_lowercase()
+}
+
+// 规范化并校验助记词（/ 用于书写带命名空间的完整助记词，不能出现在助记词中）
+pub fn validate(mnemonic: &str) -> Result<String, String> {
+    let mnemonic = normalize(mnemonic);
+    if mnemonic.is_empty() {
+        return Err("助记词不能为空".to_string());
+    }
+    if mnemonic.contains(['/', '\\']) {
+        return Err(format!("助记词 '{}' 不能包含 /", mnemonic));
+    }
+    Ok(mnemonic)
+}
+
+// 提示词所在的命名空间：相对路径的父文件夹（根目录为空字符串）
+pub fn namespace_of(rel_path: &str) -> String {
+    match rel_path.rfind('/') {
+        Some(pos) => rel_path[..pos].to_string(),
+        None => String::new(),
+    }
+}
+
+// 带命名空间的完整助记词，如 work/sum
+pub fn qualified(namespace: &str, mnemonic: &str) -> String {
+    if namespace.is_empty() {
+        mnemonic.to_string()
+    } else {
+        format!("{}/{}", namespace, mnemonic)
+    }
+}
+
+// 助记词匹配项
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MnemonicMatch {
+    pub id: String,
+    pub qualified: String,
+    pub title: String,
+}
+
+// 助记词查找结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum MnemonicLookup {
+    Found(MnemonicMatch),
+    Ambiguous { candidates: Vec<MnemonicMatch> },
+    NotFound,
+}
+
+fn has_mnemonic(file: &ScannedFile, mnemonic: &str) -> bool {
+    file.metadata.mnemonics().iter().any(|m| m == mnemonic)
+}
+
+fn matches_in<'a>(files: &'a [ScannedFile], namespace: &str, mnemonic: &str) -> Vec<&'a ScannedFile> {
+    files.iter()
+        .filter(|f| namespace_of(&f.rel_path) == namespace && has_mnemonic(f, mnemonic))
+        .collect()
+}
+
+fn to_match(file: &ScannedFile, mnemonic: &str) -> MnemonicMatch {
+    MnemonicMatch {
+        id: file.id().to_string(),
+        qualified: qualified(&namespace_of(&file.rel_path), mnemonic),
+        title: crate::library::file_title(&file.path),
+    }
+}
+
+fn lookup_result(found: Vec<&ScannedFile>, mnemonic: &str) -> MnemonicLookup {
+    match found.as_slice() {
+        [] => MnemonicLookup::NotFound,
+        [file] => MnemonicLookup::Found(to_match(file, mnemonic)),
+        _ => MnemonicLookup::Ambiguous {
+            candidates: found.iter().map(|f| to_match(f, mnemonic)).collect(),
+        },
+    }
+}
+
+// 查找助记词
+// - 带命名空间（work/sum）时只在该文件夹中查找
+// - 指定 scope 时从该文件夹开始逐级向上查找，最近的命中优先
+// - 否则在整个提示词库中查找，多个文件夹命中时返回歧义
+pub fn resolve(files: &[ScannedFile], query: &str, scope: Option<&str>) -> MnemonicLookup {
+    let query = normalize(query).replace('\\', "/");
+    if let Some(pos) = query.rfind('/') {
+        let (namespace, mnemonic) = (&query[..pos], &query[pos + 1..]);
+        return lookup_result(matches_in(files, namespace, mnemonic), mnemonic);
+    }
+
+    if let Some(scope) = scope {
+        let mut namespace = scope.trim_matches('/').replace('\\', "/");
+        loop {
+            let found = matches_in(files, &namespace, &query);
+            if !found.is_empty() {
+                return lookup_result(found, &query);
+            }
+            if namespace.is_empty() {
+                return MnemonicLookup::NotFound;
+            }
+            namespace = namespace_of(&namespace);
+        }
+    }
+
+    let found = files.iter().filter(|f| has_mnemonic(f, &query)).collect();
+    lookup_result(found, &query)
+}
+
+// 查找同一命名空间内已使用该助记词的其他提示词
+pub fn find_conflict<'a>(
+    files: &'a [ScannedFile],
+    namespace: &str,
+    mnemonic: &str,
+    exclude_id: &str,
+) -> Option<&'a ScannedFile> {
+    matches_in(files, namespace, mnemonic)
+        .into_iter()
+        .find(|f| f.id() != exclude_id)
+}
+
+// 按 ID、相对路径（可省略扩展名）或助记词查找提示词（命令行工具和本地接口使用）
+pub fn find_prompt<'a>(files: &'a [ScannedFile], query: &str) -> Result<&'a ScannedFile, String> {
+    let query = query.replace('\\', "/");
+    let by_path = |f: &&ScannedFile| {
+        f.id() == query
+            || f.rel_path == query
+            || f.rel_path.rsplit_once('.').is_some_and(|(stem, _)| stem == query)
+    };
+    if let Some(file) = files.iter().find(by_path) {
+        return Ok(file);
+    }
+
+    match resolve(files, &query, None) {
+        MnemonicLookup::Found(m) => files.iter()
+            .find(|f| f.id() == m.id)
+            .ok_or_else(|| format!("找不到提示词: {}", query)),
+        MnemonicLookup::Ambiguous { candidates } => {
+            let names: Vec<String> = candidates.into_iter().map(|c| c.qualified).collect();
+            Err(format!("助记词 '{}' 在多个文件夹中存在，请指定文件夹: {}", query, names.join(", ")))
+        }
+        MnemonicLookup::NotFound => Err(format!("找不到提示词: {}", query)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::PromptMetadata;
+    use std::path::PathBuf;
+
+    fn file(rel_path: &str, id: &str, mnemonics: &[&str]) -> ScannedFile {
+        let mut metadata = PromptMetadata { id: Some(id.to_string()), ..Default::default() };
+        metadata.set_mnemonics(mnemonics.iter().map(|m| m.to_string()).collect());
+        ScannedFile {
+            path: PathBuf::from("/library").join(rel_path),
+            rel_path: rel_path.to_string(),
+            metadata,
+            body: String::new(),
+        }
+    }
+
+    fn library() -> Vec<ScannedFile> {
+        vec![
+            file("top.md", "top", &["top", "sum"]),
+            file("work/weekly.md", "work-sum", &["sum", "ws"]),
+            file("work/daily/standup.md", "standup", &["su"]),
+            file("personal/summary.md", "personal-sum", &["sum"]),
+            file("personal/notes.txt", "notes", &["notes"]),
+        ]
+    }
+
+    fn found_id(lookup: MnemonicLookup) -> Option<String> {
+        match lookup {
+            MnemonicLookup::Found(m) => Some(m.id),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn validates_and_qualifies() {
+        assert_eq!(validate("  SuM ").unwrap(), "sum");
+        assert!(validate("   ").is_err());
+        assert!(validate("work/sum").is_err());
+        assert!(validate("work\\sum").is_err());
+        assert_eq!(namespace_of("a.md"), "");
+        assert_eq!(namespace_of("work/daily/a.md"), "work/daily");
+        assert_eq!(qualified("", "sum"), "sum");
+        assert_eq!(qualified("work/daily", "sum"), "work/daily/sum");
+    }
+
+    #[test]
+    fn qualified_query_only_searches_its_folder() {
+        let files = library();
+        assert_eq!(found_id(resolve(&files, "work/sum", None)).as_deref(), Some("work-sum"));
+        assert_eq!(found_id(resolve(&files, "Personal/SUM", None)).as_deref(), Some("personal-sum"));
+        assert_eq!(found_id(resolve(&files, "work\\daily\\su", None)).as_deref(), Some("standup"));
+        assert!(matches!(resolve(&files, "work/notes", None), MnemonicLookup::NotFound));
+        assert!(matches!(resolve(&files, "work/su", None), MnemonicLookup::NotFound));
+        let MnemonicLookup::Found(m) = resolve(&files, "personal/sum", Some("work")) else { panic!() };
+        assert_eq!((m.qualified.as_str(), m.title.as_str()), ("personal/sum", "summary"));
+    }
+
+    #[test]
+    fn scope_walks_up_to_the_nearest_folder() {
+        let files = library();
+        let cases = [
+            ("work/daily", "su", Some("standup")),
+            ("work/daily", "sum", Some("work-sum")),
+            ("/work/daily/", "ws", Some("work-sum")),
+            ("work", "sum", Some("work-sum")),
+            ("personal", "sum", Some("personal-sum")),
+            ("personal", "top", Some("top")),
+            ("other/deep", "sum", Some("top")),
+            ("", "sum", Some("top")),
+            // 向上查找不会进入兄弟文件夹
+            ("work", "notes", None),
+            ("work", "su", None),
+        ];
+        for (scope, query, expected) in cases {
+            assert_eq!(found_id(resolve(&files, query, Some(scope))).as_deref(), expected, "{} in {}", query, scope);
+        }
+    }
+
+    #[test]
+    fn unqualified_query_reports_ambiguity() {
+        let files = library();
+        let MnemonicLookup::Ambiguous { candidates } = resolve(&files, "sum", None) else { panic!() };
+        let names: Vec<_> = candidates.iter().map(|c| c.qualified.as_str()).collect();
+        assert_eq!(names, ["sum", "work/sum", "personal/sum"]);
+        assert_eq!(found_id(resolve(&files, " NOTES ", None)).as_deref(), Some("notes"));
+        assert!(matches!(resolve(&files, "missing", None), MnemonicLookup::NotFound));
+
+        let err = find_prompt(&files, "sum").unwrap_err();
+        assert!(err.contains("work/sum") && err.contains("personal/sum"), "{}", err);
+    }
+
+    #[test]
+    fn finds_prompt_by_id_path_or_mnemonic() {
+        let files = library();
+        let cases = [
+            ("standup", "work/daily/standup.md"),
+            ("work-sum", "work/weekly.md"),
+            ("personal/notes.txt", "personal/notes.txt"),
+            ("personal/notes", "personal/notes.txt"),
+            ("work\\weekly", "work/weekly.md"),
+            ("ws", "work/weekly.md"),
+            ("work/daily/su", "work/daily/standup.md"),
+            ("personal/sum", "personal/summary.md"),
+        ];
+        for (query, rel_path) in cases {
+            assert_eq!(find_prompt(&files, query).unwrap().rel_path, rel_path, "{}", query);
+        }
+        assert_eq!(find_prompt(&files, "nothing").unwrap_err(), "找不到提示词: nothing");
+    }
+
+    #[test]
+    fn conflicts_are_per_folder_and_ignore_the_prompt_itself() {
+        let files = library();
+        assert_eq!(find_conflict(&files, "work", "sum", "other").map(|f| f.id()), Some("work-sum"));
+        assert!(find_conflict(&files, "work", "sum", "work-sum").is_none());
+        assert_eq!(find_conflict(&files, "", "sum", "work-sum").map(|f| f.id()), Some("top"));
+        assert!(find_conflict(&files, "work/daily", "sum", "other").is_none());
+        assert!(find_conflict(&files, "work", "notes", "other").is_none());
+    }
+}
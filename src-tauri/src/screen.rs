@@ -0,0 +1,229 @@
+// 屏幕 - 光标位置和显示器工作区，用于把快速插入弹窗定位到光标附近
+// 光标位置：Windows 使用 GetCursorPos，X11 查询根窗口指针；
+// Wayland 不允许普通客户端获取全局光标位置，能通过合成器 IPC 获取时使用（Hyprland），否则返回 None
+// 显示器工作区由调用方提供（Tauri 的显示器信息，Linux 下来自 GDK，X11 和 Wayland 均可用）
+// 位置计算为纯函数，不依赖当前桌面环境
+
+// 弹窗尺寸常量（考虑 DPI 缩放，使用较大的估算值）
+pub const POPUP_WIDTH: i32 = 450; // 300 * 1.5 DPI
+pub const POPUP_HEIGHT: i32 = 600; // 400 * 1.5 DPI
+const MARGIN: i32 = 10; // 边距
+
+// 矩形区域（物理像素，right / bottom 不含）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+// 找不到显示器时使用的默认工作区
+pub const DEFAULT_WORK_AREA: Rect = Rect { left: 0, top: 0, right: 1920, bottom: 1080 };
+
+impl Rect {
+    pub fn new(left: i32, top: i32, width: i32, height: i32) -> Self {
+        Self { left, top, right: left + width, bottom: top + height }
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.left && x < self.right && y >= self.top && y < self.bottom
+    }
+
+    // 点到矩形的距离平方（在矩形内为 0）
+    fn distance_sq(&self, x: i32, y: i32) -> i64 {
+        let dx = (self.left - x).max(x - (self.right - 1)).max(0) as i64;
+        let dy = (self.top - y).max(y - (self.bottom - 1)).max(0) as i64;
+        dx * dx + dy * dy
+    }
+}
+
+// 光标所在显示器的工作区：包含光标的优先，否则取最近的（与 MONITOR_DEFAULTTONEAREST 相同）
+pub fn work_area_at(areas: &[Rect], x: i32, y: i32) -> Rect {
+    areas.iter()
+        .find(|area| area.contains(x, y))
+        .or_else(|| areas.iter().min_by_key(|area| area.distance_sq(x, y)))
+        .copied()
+        .unwrap_or(DEFAULT_WORK_AREA)
+}
+
+// 计算弹窗位置（智能判断向上或向下弹出）
+pub fn calculate_popup_position(cursor_x: i32, cursor_y: i32, work_area: Rect) -> (i32, i32) {
+    let mut x = cursor_x;
+    let mut y = cursor_y;
+
+    // 判断是否在屏幕下半部分（如果光标下方空间不足以显示弹窗）
+    if cursor_y + POPUP_HEIGHT + MARGIN > work_area.bottom {
+        // 向上弹出（光标上方）
+        y = cursor_y - POPUP_HEIGHT - MARGIN;
+    }
+
+    // 确保不超出左右边界
+    if x + POPUP_WIDTH > work_area.right - MARGIN {
+        x = work_area.right - POPUP_WIDTH - MARGIN;
+    }
+    if x < work_area.left + MARGIN {
+        x = work_area.left + MARGIN;
+    }
+
+    // 确保不超出上边界
+    if y < work_area.top + MARGIN {
+        y = work_area.top + MARGIN;
+    }
+
+    (x, y)
+}
+
+// 无法获取光标位置时，弹窗显示在工作区中央
+pub fn centered_position(work_area: Rect) -> (i32, i32) {
+    let x = work_area.left + (work_area.right - work_area.left - POPUP_WIDTH) / 2;
+    let y = work_area.top + (work_area.bottom - work_area.top - POPUP_HEIGHT) / 2;
+    (x.max(work_area.left), y.max(work_area.top))
+}
+
+// 获取鼠标光标位置（物理像素），无法获取时返回 None
+pub fn cursor_position() -> Option<(i32, i32)> {
+    #[cfg(target_os = "windows")]
+    {
+        use windows::Win32::Foundation::POINT;
+        use windows::Win32::UI::WindowsAndMessaging::GetCursorPos;
+        let mut point = POINT { x: 0, y: 0 };
+        unsafe { GetCursorPos(&mut point) }.ok()?;
+        Some((point.x, point.y))
+    }
+    #[cfg(target_os = "linux")]
+    {
+        if is_wayland() {
+            // XWayland 中的指针位置只在光标经过 X11 窗口时更新，不可靠
+            hyprland_cursor_position()
+        } else {
+            x11_cursor_position()
+        }
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    {
+        None
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn is_wayland() -> bool {
+    std::env::var_os("WAYLAND_DISPLAY").is_some()
+        || std::env::var("XDG_SESSION_TYPE").is_ok_and(|t| t == "wayland")
+}
+
+// X11：查询根窗口上的指针位置
+#[cfg(target_os = "linux")]
+fn x11_cursor_position() -> Option<(i32, i32)> {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::ConnectionExt;
+
+    let (conn, screen_num) = x11rb::connect(None).ok()?;
+    let root = conn.setup().roots.get(screen_num)?.root;
+    let pointer = conn.query_pointer(root).ok()?.reply().ok()?;
+    Some((pointer.root_x as i32, pointer.root_y as i32))
+}
+
+// Hyprland：通过 hyprctl cursorpos 获取（输出形如 "1280, 720"）
+#[cfg(target_os = "linux")]
+fn hyprland_cursor_position() -> Option<(i32, i32)> {
+    std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE")?;
+    let output = std::process::Command::new("hyprctl").arg("cursorpos").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_point(&String::from_utf8_lossy(&output.stdout))
+}
+
+// 解析 "x, y" 格式的坐标
+#[cfg(target_os = "linux")]
+pub fn parse_point(text: &str) -> Option<(i32, i32)> {
+    let (x, y) = text.trim().split_once(',')?;
+    Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 左侧副屏原点为负，主屏下方有任务栏，右侧副屏与主屏之间有 80 像素间隙
+    fn layout() -> Vec<Rect> {
+        vec![
+            Rect::new(0, 0, 1920, 1040),
+            Rect::new(-2560, -200, 2560, 1440),
+            Rect::new(2000, 0, 1920, 1080),
+        ]
+    }
+
+    #[test]
+    fn work_area_at_picks_containing_or_nearest_monitor() {
+        let areas = layout();
+        let cases = [
+            // (光标 x, 光标 y, 期望的显示器序号)
+            (100, 100, 0),
+            (-1, 0, 1),
+            (-2560, -200, 1),
+            (-100, 1200, 1),
+            (2500, 500, 2),
+            // 间隙中：离主屏右边缘 31，离右侧副屏 50
+            (1950, 500, 0),
+            // 间隙中靠近右侧副屏
+            (1990, 500, 2),
+            // 主屏任务栏区域（不在任何工作区内），最近的是主屏
+            (500, 1060, 0),
+        ];
+        for (x, y, expected) in cases {
+            assert_eq!(work_area_at(&areas, x, y), areas[expected], "光标 ({}, {})", x, y);
+        }
+        assert_eq!(work_area_at(&[], 10, 10), DEFAULT_WORK_AREA);
+    }
+
+    #[test]
+    fn popup_position_flips_and_clamps() {
+        let primary = Rect::new(0, 0, 1920, 1040);
+        let left = Rect::new(-2560, -200, 2560, 1440);
+        let small = Rect::new(0, 0, 800, 600);
+        let cases = [
+            // (光标 x, 光标 y, 工作区, 期望位置)
+            (500, 100, primary, (500, 100)),
+            // 下方空间不足，弹到光标上方
+            (500, 900, primary, (500, 900 - POPUP_HEIGHT - MARGIN)),
+            // 右边缘，左移到工作区内
+            (1800, 100, primary, (1920 - POPUP_WIDTH - MARGIN, 100)),
+            // 右下角同时翻转和左移
+            (1900, 1000, primary, (1920 - POPUP_WIDTH - MARGIN, 1000 - POPUP_HEIGHT - MARGIN)),
+            // 负原点的显示器：右边缘为 0
+            (-100, -150, left, (-POPUP_WIDTH - MARGIN, -150)),
+            (-2560, 1200, left, (-2560 + MARGIN, 1200 - POPUP_HEIGHT - MARGIN)),
+            // 工作区太矮，翻转后超出上边界时贴住上边界
+            (10, 300, small, (MARGIN, MARGIN)),
+        ];
+        for (x, y, area, expected) in cases {
+            assert_eq!(calculate_popup_position(x, y, area), expected, "光标 ({}, {})", x, y);
+        }
+    }
+
+    #[test]
+    fn centered_position_respects_origin() {
+        assert_eq!(
+            centered_position(Rect::new(0, 0, 1920, 1080)),
+            ((1920 - POPUP_WIDTH) / 2, (1080 - POPUP_HEIGHT) / 2)
+        );
+        assert_eq!(
+            centered_position(Rect::new(-2560, -200, 2560, 1440)),
+            (-2560 + (2560 - POPUP_WIDTH) / 2, -200 + (1440 - POPUP_HEIGHT) / 2)
+        );
+        // 工作区比弹窗小时不越过左上角
+        assert_eq!(centered_position(Rect::new(100, 50, 300, 200)), (100, 50));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn parse_point_handles_hyprctl_output() {
+        assert_eq!(parse_point("1280, 720\n"), Some((1280, 720)));
+        assert_eq!(parse_point("  -5,10 "), Some((-5, 10)));
+        for malformed in ["", "1280", "1280,", ", 720", "a, b", "1.5, 2", "1280 720", "HyprCtl error: no instance"] {
+            assert_eq!(parse_point(malformed), None, "{:?}", malformed);
+        }
+    }
+}
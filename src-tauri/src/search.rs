@@ -0,0 +1,503 @@
+// 快速插入搜索 - 模糊匹配与排序
+//
+// 排序优先级：助记词完全匹配 > 助记词前缀 > 标题（包含 / 拼音首字母 / 模糊） > 正文包含
+// 同一档内置顶的在前，其余按使用频率（frecency）排序
+// 匹配位置以字符下标返回，前端据此高亮显示匹配原因
+use crate::library::{self, ScannedFile};
+use crate::mnemonic;
+use crate::usage::UsageLog;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+// 正文预览长度（字符数）及匹配位置之前保留的字符数
+const PREVIEW_CHARS: usize = 80;
+const PREVIEW_CONTEXT: usize = 20;
+
+// 各匹配方式的基础分，同一档内再按匹配质量加分（加分小于 100）
+const SCORE_MNEMONIC_EXACT: u32 = 1000;
+const SCORE_MNEMONIC_PREFIX: u32 = 800;
+const SCORE_TITLE_EXACT: u32 = 700;
+const SCORE_TITLE_CONTAINS: u32 = 600;
+const SCORE_TITLE_PINYIN: u32 = 500;
+const SCORE_TITLE_FUZZY: u32 = 400;
+const SCORE_CONTENT: u32 = 200;
+const SCORE_TIER: u32 = 100;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchField {
+    Mnemonic,
+    Title,
+    Content,
+}
+
+// 高亮范围 [start, end)，字符下标
+// field 为 mnemonic 时对应 matched_mnemonic，content 时对应 preview
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Highlight {
+    pub field: MatchField,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SearchResult {
+    pub id: String,
+    pub title: String,
+    pub mnemonic: String,
+    pub aliases: Vec<String>,
+    pub namespace: String,
+    pub rel_path: String,
+    pub content: String,
+    // 正文预览（正文命中时截取匹配位置附近的内容）
+    pub preview: String,
+    // 命中的助记词（可能是别名）
+    pub matched_mnemonic: Option<String>,
+    pub matched: Option<MatchField>,
+    pub score: u32,
+    pub highlights: Vec<Highlight>,
+    pub pinned: bool,
+    pub frecency: f64,
+}
+
+// 先按匹配档位，再按置顶、frecency、匹配质量、标题排序
+fn compare(a: &SearchResult, b: &SearchResult) -> Ordering {
+    (b.score / SCORE_TIER).cmp(&(a.score / SCORE_TIER))
+        .then_with(|| b.pinned.cmp(&a.pinned))
+        .then_with(|| b.frecency.total_cmp(&a.frecency))
+        .then_with(|| b.score.cmp(&a.score))
+        .then_with(|| a.title.cmp(&b.title))
+}
+
+struct Match {
+    field: MatchField,
+    score: u32,
+    ranges: Vec<(usize, usize)>,
+    mnemonic: Option<String>,
+}
+
+// 逐字符转小写，保持字符下标不变
+fn fold(text: &str) -> Vec<char> {
+    text.chars()
+        .map(|c| c.to_lowercase().next().unwrap_or(c))
+        .collect()
+}
+
+fn find(haystack: &[char], needle: &[char]) -> Option<usize> {
+    if needle.is_empty() || needle.len() > haystack.len() {
+        return None;
+    }
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+// 将连续的下标合并为范围
+fn to_ranges(indices: &[usize]) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &i in indices {
+        match ranges.last_mut() {
+            Some(last) if last.1 == i => last.1 = i + 1,
+            _ => ranges.push((i, i + 1)),
+        }
+    }
+    ranges
+}
+
+// 长度越接近查询词加分越多
+fn closeness_bonus(len: usize, query_len: usize) -> u32 {
+    99u32.saturating_sub(len.saturating_sub(query_len) as u32)
+}
+
+// ====== 拼音首字母 ======
+
+// GB2312 一级汉字按拼音排序，可由编码区间得到首字母（二级汉字按部首排序，不支持）
+const PINYIN_RANGES: [(u16, char); 23] = [
+    (0xB0A1, 'a'), (0xB0C5, 'b'), (0xB2C1, 'c'), (0xB4EE, 'd'), (0xB6EA, 'e'),
+    (0xB7A2, 'f'), (0xB8C1, 'g'), (0xB9FE, 'h'), (0xBBF7, 'j'), (0xBFA6, 'k'),
+    (0xC0AC, 'l'), (0xC2E8, 'm'), (0xC4C3, 'n'), (0xC5B6, 'o'), (0xC5BE, 'p'),
+    (0xC6DA, 'q'), (0xC8BB, 'r'), (0xC8F6, 's'), (0xCBFA, 't'), (0xCDDA, 'w'),
+    (0xCEF4, 'x'), (0xD1B9, 'y'), (0xD4D1, 'z'),
+];
+const PINYIN_LAST: u16 = 0xD7F9;
+
+fn is_cjk(c: char) -> bool {
+    ('\u{4e00}'..='\u{9fff}').contains(&c)
+}
+
+fn pinyin_initial(c: char) -> Option<char> {
+    let mut buf = [0u8; 4];
+    let (bytes, _, had_errors) = encoding_rs::GBK.encode(c.encode_utf8(&mut buf));
+    if had_errors || bytes.len() != 2 {
+        return None;
+    }
+    let code = u16::from_be_bytes([bytes[0], bytes[1]]);
+    if !(PINYIN_RANGES[0].0..=PINYIN_LAST).contains(&code) {
+        return None;
+    }
+    PINYIN_RANGES.iter()
+        .rev()
+        .find(|(start, _)| code >= *start)
+        .map(|(_, initial)| *initial)
+}
+
+// 标题的首字母序列：汉字取拼音首字母，字母数字保留，其余字符跳过
+// 返回 (首字母, 在标题中的字符下标)
+fn initials(title: &[char]) -> Vec<(char, usize)> {
+    title.iter()
+        .enumerate()
+        .filter_map(|(i, &c)| {
+            if is_cjk(c) {
+                pinyin_initial(c).map(|p| (p, i))
+            } else if c.is_alphanumeric() {
+                Some((c, i))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+// ====== 各字段匹配 ======
+
+fn match_mnemonic(file: &ScannedFile, query: &str) -> Option<Match> {
+    let namespace = mnemonic::namespace_of(&file.rel_path);
+    let query_len = query.chars().count();
+    let mut best: Option<Match> = None;
+
+    for m in file.metadata.mnemonics() {
+        let qualified = mnemonic::qualified(&namespace, &m);
+        let (score, shown) = if m == query || qualified == query {
+            (SCORE_MNEMONIC_EXACT, if m == query { m.clone() } else { qualified })
+        } else if m.starts_with(query) {
+            (SCORE_MNEMONIC_PREFIX + closeness_bonus(m.chars().count(), query_len), m.clone())
+        } else if qualified.starts_with(query) {
+            (SCORE_MNEMONIC_PREFIX + closeness_bonus(qualified.chars().count(), query_len), qualified)
+        } else {
+            continue;
+        };
+        if best.as_ref().is_none_or(|b| score > b.score) {
+            best = Some(Match {
+                field: MatchField::Mnemonic,
+                score,
+                ranges: vec![(0, query_len.min(shown.chars().count()))],
+                mnemonic: Some(shown),
+            });
+        }
+    }
+    best
+}
+
+fn title_match(score: u32, ranges: Vec<(usize, usize)>) -> Option<Match> {
+    Some(Match { field: MatchField::Title, score, ranges, mnemonic: None })
+}
+
+fn match_title(title: &str, query: &[char]) -> Option<Match> {
+    let folded = fold(title);
+    let bonus = closeness_bonus(folded.len(), query.len());
+
+    if folded == query {
+        return title_match(SCORE_TITLE_EXACT, vec![(0, folded.len())]);
+    }
+    if let Some(pos) = find(&folded, query) {
+        let bonus = if pos == 0 { bonus } else { bonus / 2 };
+        return title_match(SCORE_TITLE_CONTAINS + bonus, vec![(pos, pos + query.len())]);
+    }
+
+    if folded.iter().any(|&c| is_cjk(c)) {
+        let initials = initials(&folded);
+        let letters: Vec<char> = initials.iter().map(|(c, _)| *c).collect();
+        if let Some(pos) = find(&letters, query) {
+            let indices: Vec<usize> = initials[pos..pos + query.len()].iter().map(|(_, i)| *i).collect();
+            let bonus = closeness_bonus(letters.len(), query.len());
+            let bonus = if pos == 0 { bonus } else { bonus / 2 };
+            return title_match(SCORE_TITLE_PINYIN + bonus, to_ranges(&indices));
+        }
+    }
+
+    // 模糊匹配：查询词的字符按顺序出现在标题中，越紧凑分越高
+    let mut indices = Vec::with_capacity(query.len());
+    let mut chars = folded.iter().enumerate();
+    for q in query {
+        let (i, _) = chars.by_ref().find(|(_, c)| *c == q)?;
+        indices.push(i);
+    }
+    let span = indices.last()? - indices.first()? + 1;
+    let bonus = (query.len() * 99 / span) as u32;
+    title_match(SCORE_TITLE_FUZZY + bonus, to_ranges(&indices))
+}
+
+fn match_content(content: &str, query: &[char]) -> Option<Match> {
+    let folded = fold(content);
+    let pos = find(&folded, query)?;
+    // 越靠前分越高
+    let bonus = 99u32.saturating_sub((pos / 20) as u32);
+    Some(Match {
+        field: MatchField::Content,
+        score: SCORE_CONTENT + bonus,
+        ranges: vec![(pos, pos + query.len())],
+        mnemonic: None,
+    })
+}
+
+// 截取正文预览；content 命中时以匹配位置为中心，并把高亮范围换算为预览中的位置
+fn preview(content: &str, hit: Option<&Match>) -> (String, Vec<(usize, usize)>) {
+    let chars: Vec<char> = content.chars().collect();
+    let Some(&(start, end)) = hit.and_then(|m| m.ranges.first()) else {
+        return (chars.iter().take(PREVIEW_CHARS).collect(), Vec::new());
+    };
+
+    let from = start.saturating_sub(PREVIEW_CONTEXT);
+    let mut text: String = chars[from..].iter().take(PREVIEW_CHARS).collect();
+    let mut offset = from;
+    if from > 0 {
+        text.insert(0, '…');
+        offset -= 1;
+    }
+    let end = end.min(from + PREVIEW_CHARS);
+    (text, vec![(start - offset, end - offset)])
+}
+
+fn best_match(file: &ScannedFile, title: &str, query: &str) -> Option<Match> {
+    let folded = fold(query);
+    match_mnemonic(file, query)
+        .or_else(|| match_title(title, &folded))
+        .or_else(|| match_content(&file.body, &folded))
+}
+
+// 搜索提示词；查询为空时按置顶和 frecency 排序返回全部
+pub fn search(files: &[ScannedFile], query: &str, limit: usize, usage: &UsageLog) -> Vec<SearchResult> {
+    let query = mnemonic::normalize(query).replace('\\', "/");
+    let now = crate::usage::now();
+
+    let mut results: Vec<SearchResult> = files.iter()
+        .filter_map(|file| {
+            let title = library::file_title(&file.path);
+            let hit = if query.is_empty() {
+                None
+            } else {
+                Some(best_match(file, &title, &query)?)
+            };
+
+            let content_hit = hit.as_ref().filter(|m| m.field == MatchField::Content);
+            let (preview, preview_ranges) = preview(&file.body, content_hit);
+            let highlights = match &hit {
+                Some(m) if m.field == MatchField::Content => preview_ranges,
+                Some(m) => m.ranges.clone(),
+                None => Vec::new(),
+            };
+
+            let mut mnemonics = file.metadata.mnemonics().into_iter();
+            Some(SearchResult {
+                id: file.id().to_string(),
+                mnemonic: mnemonics.next().unwrap_or_default(),
+                aliases: mnemonics.collect(),
+                namespace: mnemonic::namespace_of(&file.rel_path),
+                rel_path: file.rel_path.clone(),
+                content: file.body.clone(),
+                preview,
+                matched_mnemonic: hit.as_ref().and_then(|m| m.mnemonic.clone()),
+                matched: hit.as_ref().map(|m| m.field),
+                score: hit.as_ref().map_or(0, |m| m.score),
+                highlights: highlights.into_iter()
+                    .map(|(start, end)| Highlight {
+                        field: hit.as_ref().map_or(MatchField::Title, |m| m.field),
+                        start,
+                        end,
+                    })
+                    .collect(),
+                pinned: usage.is_pinned(file.id()),
+                frecency: usage.frecency(file.id(), now),
+                title,
+            })
+        })
+        .collect();
+
+    results.sort_by(compare);
+    results.truncate(limit);
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::PromptMetadata;
+    use std::path::PathBuf;
+
+    fn file(rel: &str, mnemonics: &[&str], body: &str) -> ScannedFile {
+        let mut metadata = PromptMetadata { id: Some(rel.to_string()), ..Default::default() };
+        metadata.set_mnemonics(mnemonics.iter().map(|m| m.to_string()).collect());
+        ScannedFile {
+            path: PathBuf::from("/library").join(rel),
+            rel_path: rel.to_string(),
+            metadata,
+            body: body.to_string(),
+        }
+    }
+
+    fn titles(results: &[SearchResult]) -> Vec<&str> {
+        results.iter().map(|r| r.title.as_str()).collect()
+    }
+
+    #[test]
+    fn fold_keeps_char_indices() {
+        assert_eq!(fold("Hello ÄÖ 中文"), "hello äö 中文".chars().collect::<Vec<_>>());
+        // İ 转小写为两个字符，只取第一个，下标不变
+        assert_eq!(fold("İx").len(), 2);
+        assert_eq!(fold(""), Vec::<char>::new());
+    }
+
+    #[test]
+    fn pinyin_initial_follows_gbk_ranges() {
+        // 每个区间的第一个字和前一个区间的最后一个字
+        let cases = [
+            ('啊', 'a'), ('澳', 'a'), ('芭', 'b'), ('怖', 'b'), ('擦', 'c'), ('错', 'c'),
+            ('搭', 'd'), ('堕', 'd'), ('蛾', 'e'), ('贰', 'e'), ('发', 'f'), ('咐', 'f'),
+            ('噶', 'g'), ('过', 'g'), ('哈', 'h'), ('祸', 'h'), ('击', 'j'), ('骏', 'j'),
+            ('喀', 'k'), ('阔', 'k'), ('垃', 'l'), ('络', 'l'), ('妈', 'm'), ('穆', 'm'),
+            ('拿', 'n'), ('诺', 'n'), ('哦', 'o'), ('沤', 'o'), ('啪', 'p'), ('瀑', 'p'),
+            ('期', 'q'), ('群', 'q'), ('然', 'r'), ('弱', 'r'), ('撒', 's'), ('所', 's'),
+            ('塌', 't'), ('唾', 't'), ('挖', 'w'), ('误', 'w'), ('昔', 'x'), ('迅', 'x'),
+            ('压', 'y'), ('孕', 'y'), ('匝', 'z'), ('座', 'z'), ('中', 'z'), ('文', 'w'),
+        ];
+        for (c, initial) in cases {
+            assert_eq!(pinyin_initial(c), Some(initial), "{}", c);
+        }
+        // 二级汉字（按部首排序）、GB2312 区间外的字符和单字节字符
+        for c in ['亍', '丌', 'é', 'a', '€', '😀', '，'] {
+            assert_eq!(pinyin_initial(c), None, "{}", c);
+        }
+    }
+
+    #[test]
+    fn initials_of_mixed_text() {
+        assert_eq!(
+            initials(&fold("Git提交 v2·规范")),
+            vec![('g', 0), ('i', 1), ('t', 2), ('t', 3), ('j', 4), ('v', 6), ('2', 7), ('g', 9), ('f', 10)]
+        );
+        // 无法得到首字母的汉字跳过
+        assert_eq!(initials(&fold("亍A")), vec![('a', 1)]);
+    }
+
+    #[test]
+    fn title_matches_pinyin_initials() {
+        let hit = match_title("Git提交规范", &fold("tjgf")).unwrap();
+        assert_eq!(hit.score / SCORE_TIER, SCORE_TITLE_PINYIN / SCORE_TIER);
+        assert_eq!(hit.ranges, vec![(3, 7)]);
+        // 拼音首字母与英文字母连在一起匹配
+        let hit = match_title("Git提交规范", &fold("gittj")).unwrap();
+        assert_eq!(hit.score / SCORE_TIER, SCORE_TITLE_PINYIN / SCORE_TIER);
+        assert_eq!(hit.ranges, vec![(0, 5)]);
+        // 跨过标点和空格的首字母，高亮范围不连续
+        let hit = match_title("代码 审查", &fold("dmsc")).unwrap();
+        assert_eq!(hit.ranges, vec![(0, 2), (3, 5)]);
+        // 开头匹配比中间匹配分高
+        let head = match_title("代码审查", &fold("dm")).unwrap();
+        let middle = match_title("代码审查", &fold("sc")).unwrap();
+        assert!(head.score > middle.score);
+    }
+
+    #[test]
+    fn fuzzy_title_prefers_compact_matches() {
+        let query = fold("abc");
+        let compact = match_title("a-b-c", &query).unwrap();
+        let loose = match_title("a--b--c", &query).unwrap();
+        let sparse = match_title("axxxxxxbxxxxxxxc", &query).unwrap();
+        assert_eq!(compact.score / SCORE_TIER, SCORE_TITLE_FUZZY / SCORE_TIER);
+        assert!(compact.score > loose.score && loose.score > sparse.score);
+        assert_eq!(compact.ranges, vec![(0, 1), (2, 3), (4, 5)]);
+        // 顺序不对时不匹配
+        assert!(match_title("acb", &query).is_none());
+    }
+
+    #[test]
+    fn search_ranks_by_tier() {
+        let files = vec![
+            file("other.md", &[], "正文中提到 sum 的提示词"),
+            file("s-u-m x.md", &[], ""),
+            file("my sum.md", &[], ""),
+            file("summary.md", &[], ""),
+            file("sum.md", &[], ""),
+            file("x2.md", &["summ"], ""),
+            file("x1.md", &["sum"], ""),
+            file("nothing.md", &[], "nothing"),
+        ];
+        let results = search(&files, "SUM", 10, &UsageLog::default());
+        assert_eq!(titles(&results), ["x1", "x2", "sum", "summary", "my sum", "s-u-m x", "other"]);
+        let matched: Vec<Option<MatchField>> = results.iter().map(|r| r.matched).collect();
+        use MatchField::*;
+        assert_eq!(matched, [Some(Mnemonic), Some(Mnemonic), Some(Title), Some(Title), Some(Title), Some(Title), Some(Content)]);
+        assert_eq!(results[1].matched_mnemonic.as_deref(), Some("summ"));
+
+        assert_eq!(search(&files, "sum", 2, &UsageLog::default()).len(), 2);
+    }
+
+    #[test]
+    fn pinned_and_frecency_order_within_tier() {
+        let files = vec![file("a-b-c.md", &[], ""), file("a--b--c.md", &[], ""), file("axbxc.md", &[], "")];
+        let mut usage = UsageLog::default();
+        usage.record("a--b--c.md", crate::usage::now());
+        usage.set_pinned("axbxc.md", true);
+        let results = search(&files, "abc", 10, &usage);
+        assert_eq!(titles(&results), ["axbxc", "a--b--c", "a-b-c"]);
+        // 查询为空时全部返回，只按置顶和 frecency 排序
+        let results = search(&files, "  ", 10, &usage);
+        assert_eq!(titles(&results), ["axbxc", "a--b--c", "a-b-c"]);
+        assert!(results.iter().all(|r| r.matched.is_none() && r.highlights.is_empty()));
+    }
+
+    fn content_hit(content: &str, query: &str) -> Match {
+        match_content(content, &fold(query)).unwrap()
+    }
+
+    fn highlighted(text: &str, (start, end): (usize, usize)) -> String {
+        text.chars().skip(start).take(end - start).collect()
+    }
+
+    #[test]
+    fn preview_without_hit_truncates() {
+        let content = "字".repeat(100);
+        let (text, ranges) = preview(&content, None);
+        assert_eq!(text.chars().count(), PREVIEW_CHARS);
+        assert!(ranges.is_empty());
+    }
+
+    #[test]
+    fn preview_near_start_has_no_ellipsis() {
+        let content = format!("开头{}{}", "Target", "尾".repeat(200));
+        let (text, ranges) = preview(&content, Some(&content_hit(&content, "target")));
+        assert!(text.starts_with("开头Target"));
+        assert_eq!(ranges, vec![(2, 8)]);
+        assert_eq!(highlighted(&text, ranges[0]), "Target");
+    }
+
+    #[test]
+    fn preview_offsets_highlight_after_ellipsis() {
+        let content = format!("{}Target{}", "一".repeat(50), "二".repeat(100));
+        let (text, ranges) = preview(&content, Some(&content_hit(&content, "target")));
+        assert!(text.starts_with('…'));
+        assert_eq!(text.chars().count(), PREVIEW_CHARS + 1);
+        // 匹配前保留 PREVIEW_CONTEXT 个字符，再加上开头的 …
+        assert_eq!(ranges, vec![(PREVIEW_CONTEXT + 1, PREVIEW_CONTEXT + 7)]);
+        assert_eq!(highlighted(&text, ranges[0]), "Target");
+    }
+
+    #[test]
+    fn preview_clamps_highlight_to_preview_end() {
+        let query = "长".repeat(100);
+        let content = format!("{}{}", "前".repeat(30), query);
+        let (text, ranges) = preview(&content, Some(&content_hit(&content, &query)));
+        let (start, end) = ranges[0];
+        assert_eq!(start, PREVIEW_CONTEXT + 1);
+        assert_eq!(end, text.chars().count());
+        assert_eq!(highlighted(&text, ranges[0]), "长".repeat(PREVIEW_CHARS - PREVIEW_CONTEXT));
+    }
+
+    #[test]
+    fn search_returns_content_highlights_in_preview() {
+        let files = vec![file("notes.md", &[], &format!("{}关键词{}", "填充".repeat(30), "。".repeat(100)))];
+        let results = search(&files, "关键词", 10, &UsageLog::default());
+        let result = &results[0];
+        assert_eq!(result.matched, Some(MatchField::Content));
+        assert_eq!(result.highlights, vec![Highlight { field: MatchField::Content, start: 21, end: 24 }]);
+        assert_eq!(highlighted(&result.preview, (21, 24)), "关键词");
+    }
+}
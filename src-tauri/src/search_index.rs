@@ -0,0 +1,339 @@
+// 搜索索引 - 常驻内存的提示词缓存，供快速插入弹窗和助记词查找使用
+// 启动时从缓存文件加载，只重新读取 mtime/size 发生变化的文件；之后由文件监听增量更新
+// 加密的提示词解锁后只保存在内存中，不写入缓存文件
+use crate::crypto;
+use crate::library::{self, ScannedFile};
+use crate::metadata::{self, PromptMetadata};
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+// 文件修改时间（纳秒）和大小，用于判断缓存是否过期
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+struct FileStamp {
+    modified: u64,
+    size: u64,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Option<Self> {
+        let meta = fs::metadata(path).ok()?;
+        let modified = meta.modified().ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+        Some(Self { modified, size: meta.len() })
+    }
+}
+
+// 缓存的提示词
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IndexedPrompt {
+    #[serde(default)]
+    pub metadata: PromptMetadata,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default)]
+    stamp: FileStamp,
+    #[serde(skip)]
+    encrypted: bool,
+    // front matter 无法解析，整份内容作为正文；不能补充 ID（改写会再加一段 front matter）
+    #[serde(default)]
+    malformed: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SearchIndex {
+    // 建立缓存时的提示词库根目录
+    #[serde(default)]
+    root: String,
+    // 相对路径 -> 提示词
+    #[serde(default, serialize_with = "serialize_unencrypted")]
+    prompts: BTreeMap<String, IndexedPrompt>,
+    #[serde(skip)]
+    loaded: bool,
+    // 有未写入缓存文件的修改
+    #[serde(skip)]
+    dirty: bool,
+}
+
+// 增量更新产生的变化
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Created,
+    Changed,
+    Removed,
+}
+
+#[derive(Debug, Clone)]
+pub struct PromptChange {
+    pub kind: ChangeKind,
+    pub rel_path: String,
+    pub id: Option<String>,
+}
+
+impl PromptChange {
+    fn new(kind: ChangeKind, rel_path: &str, entry: &IndexedPrompt) -> Self {
+        Self { kind, rel_path: rel_path.to_string(), id: entry.metadata.id.clone() }
+    }
+}
+
+fn is_prompt_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "md" || ext == "txt")
+}
+
+// 未解锁的加密提示词读取失败，不加入索引
+fn read_entry(path: &Path, stamp: FileStamp) -> Option<IndexedPrompt> {
+    let (raw, encrypted) = crypto::read_text(path).ok()?;
+    let malformed = metadata::parse_strict(&raw).is_err();
+    let (metadata, body) = metadata::parse(&raw);
+    Some(IndexedPrompt { metadata, body, stamp, encrypted, malformed })
+}
+
+fn serialize_unencrypted<S: Serializer>(
+    prompts: &BTreeMap<String, IndexedPrompt>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(prompts.iter().filter(|(_, p)| !p.encrypted))
+}
+
+impl SearchIndex {
+    // 从缓存文件加载并与磁盘同步（缓存损坏或根目录不同时重新建立）
+    pub fn load(cache_path: &Path, root: &Path) -> Self {
+        let root_str = root.to_string_lossy().to_string();
+        let mut index = fs::read_to_string(cache_path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Self>(&content).ok())
+            .filter(|index| index.root == root_str)
+            .unwrap_or_default();
+        index.root = root_str;
+        index.loaded = true;
+        index.refresh();
+        index
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.loaded
+    }
+
+    pub fn root(&self) -> &Path {
+        Path::new(&self.root)
+    }
+
+    pub fn save(&mut self, cache_path: &Path) -> Result<(), String> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let content = serde_json::to_string(self)
+            .map_err(|e| format!("序列化搜索缓存失败: {}", e))?;
+        fs::write(cache_path, content).map_err(|e| format!("保存搜索缓存失败: {}", e))?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    // 遍历提示词库，只读取新增或 mtime/size 变化的文件，返回是否有变化
+    pub fn refresh(&mut self) -> bool {
+        let root = PathBuf::from(&self.root);
+        let mut old = std::mem::take(&mut self.prompts);
+        let mut changed = false;
+
+        for path in library::walk_prompt_files(&root) {
+            let (Some(rel), Some(stamp)) = (library::to_relative(&root, &path), FileStamp::of(&path)) else {
+                continue;
+            };
+            let entry = match old.remove(&rel) {
+                Some(entry) if entry.stamp == stamp => Some(entry),
+                _ => {
+                    changed = true;
+                    read_entry(&path, stamp)
+                }
+            };
+            if let Some(entry) = entry {
+                self.prompts.insert(rel, entry);
+            }
+        }
+
+        changed |= !old.is_empty();
+        self.dirty |= changed;
+        changed
+    }
+
+    // 同步单个路径（文件监听事件）：文件重新读取，文件夹重新遍历，不存在时移除其下所有条目
+    pub fn update_path(&mut self, path: &Path) -> Vec<PromptChange> {
+        let root = PathBuf::from(&self.root);
+        let Some(rel) = library::to_relative(&root, path).filter(|rel| !library::is_hidden(rel)) else {
+            return Vec::new();
+        };
+
+        let changes = if path.is_dir() {
+            self.update_dir(&root, path, &rel)
+        } else if path.is_file() {
+            if is_prompt_file(path) {
+                self.update_file(path, rel).into_iter().collect()
+            } else {
+                Vec::new()
+            }
+        } else {
+            self.remove_where(|p| is_under(p, &rel))
+        };
+        self.dirty |= !changes.is_empty();
+        changes
+    }
+
+    fn update_file(&mut self, path: &Path, rel: String) -> Option<PromptChange> {
+        let stamp = FileStamp::of(path)?;
+        let old = self.prompts.get(&rel);
+        if old.is_some_and(|e| e.stamp == stamp) {
+            return None;
+        }
+        let kind = if old.is_some() { ChangeKind::Changed } else { ChangeKind::Created };
+        match read_entry(path, stamp) {
+            Some(entry) => {
+                let change = PromptChange::new(kind, &rel, &entry);
+                self.prompts.insert(rel, entry);
+                Some(change)
+            }
+            None => self.prompts.remove(&rel)
+                .map(|entry| PromptChange::new(ChangeKind::Removed, &rel, &entry)),
+        }
+    }
+
+    fn update_dir(&mut self, root: &Path, dir: &Path, rel: &str) -> Vec<PromptChange> {
+        let mut changes = Vec::new();
+        let mut present = HashSet::new();
+        for path in library::walk_prompt_files(dir) {
+            if let Some(file_rel) = library::to_relative(root, &path) {
+                present.insert(file_rel.clone());
+                changes.extend(self.update_file(&path, file_rel));
+            }
+        }
+
+        changes.extend(self.remove_where(|p| is_under(p, rel) && !present.contains(p)));
+        changes
+    }
+
+    fn remove_where(&mut self, f: impl Fn(&str) -> bool) -> Vec<PromptChange> {
+        let removed: Vec<String> = self.prompts.keys().filter(|p| f(p)).cloned().collect();
+        removed.into_iter()
+            .filter_map(|rel| {
+                let entry = self.prompts.remove(&rel)?;
+                Some(PromptChange::new(ChangeKind::Removed, &rel, &entry))
+            })
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.prompts.len()
+    }
+
+    fn scanned(&self, rel: &str, entry: &IndexedPrompt) -> ScannedFile {
+        ScannedFile {
+            path: library::to_absolute(self.root(), rel),
+            rel_path: rel.to_string(),
+            metadata: entry.metadata.clone(),
+            body: entry.body.clone(),
+        }
+    }
+
+    // 转换为扫描结果，供助记词查找等复用
+    pub fn files(&self) -> Vec<ScannedFile> {
+        self.prompts.iter().map(|(rel, entry)| self.scanned(rel, entry)).collect()
+    }
+
+    // 缺少 ID 的提示词（相对路径），不含 front matter 无法解析的文件
+    pub fn without_id(&self) -> Vec<String> {
+        self.prompts.iter()
+            .filter(|(_, e)| !e.malformed && e.metadata.id.as_deref().unwrap_or_default().is_empty())
+            .map(|(rel, _)| rel.clone())
+            .collect()
+    }
+
+    // rels 中的提示词及与它们 ID 相同的其他提示词，供补充 ID 时检测复制产生的重复 ID
+    pub fn files_sharing_ids(&self, rels: &[String]) -> Vec<ScannedFile> {
+        let rels: HashSet<&str> = rels.iter().map(String::as_str).collect();
+        let ids: HashSet<&str> = rels.iter()
+            .filter_map(|rel| self.prompts.get(*rel)?.metadata.id.as_deref())
+            .filter(|id| !id.is_empty())
+            .collect();
+        self.prompts.iter()
+            .filter(|(_, e)| !e.malformed)
+            .filter(|(rel, e)| {
+                rels.contains(rel.as_str()) || e.metadata.id.as_deref().is_some_and(|id| ids.contains(id))
+            })
+            .map(|(rel, entry)| self.scanned(rel, entry))
+            .collect()
+    }
+
+    pub fn id_of(&self, rel: &str) -> Option<String> {
+        self.prompts.get(rel)?.metadata.id.clone()
+    }
+
+    // 写入 ID 后更新条目的元数据（文件随后的修改事件会重新读取）
+    pub fn set_metadata(&mut self, rel: &str, metadata: PromptMetadata) {
+        if let Some(entry) = self.prompts.get_mut(rel).filter(|e| e.metadata != metadata) {
+            entry.metadata = metadata;
+            self.dirty = true;
+        }
+    }
+}
+
+// rel 是否为 prefix 本身或位于 prefix 文件夹下（prefix 为空表示根目录）
+fn is_under(rel: &str, prefix: &str) -> bool {
+    prefix.is_empty()
+        || rel == prefix
+        || rel.strip_prefix(prefix).is_some_and(|rest| rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::LibraryIndex;
+
+    #[test]
+    fn external_files_get_ids_when_indexed() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("library");
+        fs::create_dir(&root).unwrap();
+        fs::write(root.join("a.md"), "---\nid: id-a\n---\n正文\n").unwrap();
+        let mut library_index = LibraryIndex::load(&dir.path().join("index.json"), &root).unwrap();
+        library::scan(&root, &mut library_index, true);
+        let mut index = SearchIndex::load(&dir.path().join("cache.json"), &root);
+        assert!(index.without_id().is_empty());
+
+        // 应用运行时外部新增和复制的文件
+        fs::write(root.join("new.md"), "新文件\n").unwrap();
+        fs::copy(root.join("a.md"), root.join("copy.md")).unwrap();
+        let mut rels = Vec::new();
+        for rel in ["new.md", "copy.md"] {
+            rels.extend(index.update_path(&root.join(rel)).into_iter().map(|c| c.rel_path));
+        }
+        assert_eq!(index.without_id(), ["new.md"]);
+        assert_eq!(index.id_of("copy.md").as_deref(), Some("id-a"));
+
+        let mut files = index.files_sharing_ids(&rels);
+        let mut checked: Vec<&str> = files.iter().map(|f| f.rel_path.as_str()).collect();
+        checked.sort();
+        assert_eq!(checked, ["a.md", "copy.md", "new.md"]);
+
+        assert!(library::index_files(&mut library_index, &mut files));
+        for file in files {
+            index.set_metadata(&file.rel_path, file.metadata);
+        }
+        assert!(index.without_id().is_empty());
+        assert_eq!(index.id_of("a.md").as_deref(), Some("id-a"));
+        let copy_id = index.id_of("copy.md").unwrap();
+        assert_ne!(copy_id, "id-a");
+        assert_eq!(library_index.path_of(&copy_id), Some("copy.md"));
+        let new_id = index.id_of("new.md").unwrap();
+        assert_eq!(library_index.path_of(&new_id), Some("new.md"));
+
+        // 写入 ID 后的文件重新读取时内容一致，不产生新的 ID
+        for rel in ["new.md", "copy.md"] {
+            index.update_path(&root.join(rel));
+        }
+        assert_eq!(index.id_of("new.md"), Some(new_id));
+        assert_eq!(index.id_of("copy.md"), Some(copy_id));
+    }
+}
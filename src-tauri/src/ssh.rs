@@ -0,0 +1,475 @@
+// SSH 远程 - 通过系统的 ssh / scp 命令读写远程文件夹，供双向同步使用
+// 同步开始时把远程文件夹整体下载到临时目录作为快照，之后的比较和读取都在快照上进行；
+// 上传时只把需要更新的文件放进暂存目录一次性 scp，删除则通过一次 ssh 执行
+// 服务器地址和远程路径在使用前校验，传给远程 shell 的路径一律加引号，主机名前加 -- 避免被解析为选项
+use crate::library;
+use crate::sync::{self, RemoteFile, SyncBackend};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+// 主机密钥检查方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HostKeyPolicy {
+    // 只接受 known_hosts 中已有的主机密钥
+    #[default]
+    Strict,
+    // 首次连接时自动记录主机密钥，之后密钥变化时拒绝连接
+    AcceptNew,
+}
+
+// 远程同步配置
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RemoteSyncConfig {
+    pub enabled: bool,
+    pub server: String,      // user@host
+    pub remote_path: String, // ~/prompts
+    pub port: Option<u16>,   // SSH端口，默认22
+    // 私钥文件，为空时使用 ssh 的默认配置
+    #[serde(default)]
+    pub identity_file: String,
+    // known_hosts 文件，为空时使用 ~/.ssh/known_hosts
+    #[serde(default)]
+    pub known_hosts_file: String,
+    #[serde(default)]
+    pub host_key_policy: HostKeyPolicy,
+}
+
+// SSH 错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SshError {
+    // 配置不合法（服务器地址、远程路径等）
+    InvalidConfig(String),
+    // 本机没有安装 ssh / scp
+    NotInstalled,
+    // 认证失败（密钥未授权、私钥不可用等）
+    AuthFailed,
+    // 主机密钥未知或与 known_hosts 中记录的不一致
+    HostKeyRejected,
+    // 无法连接服务器（无法解析、连接被拒绝、超时等）
+    HostUnreachable(String),
+    // 远程路径不存在或不是文件夹
+    RemotePathMissing(String),
+    // 其他错误（远程命令执行失败等）
+    Failed(String),
+}
+
+impl SshError {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::InvalidConfig(_) => "invalid_config",
+            Self::NotInstalled => "not_installed",
+            Self::AuthFailed => "auth_failed",
+            Self::HostKeyRejected => "host_key_rejected",
+            Self::HostUnreachable(_) => "host_unreachable",
+            Self::RemotePathMissing(_) => "remote_path_missing",
+            Self::Failed(_) => "failed",
+        }
+    }
+
+    // 根据 ssh / scp 的错误输出判断错误类型
+    fn classify(stderr: &str) -> Self {
+        let has = |patterns: &[&str]| patterns.iter().any(|p| stderr.contains(p));
+        if has(&["Host key verification failed", "REMOTE HOST IDENTIFICATION HAS CHANGED", "No matching host key"]) {
+            Self::HostKeyRejected
+        } else if has(&["Permission denied (", "Too many authentication failures", "Load key"]) {
+            Self::AuthFailed
+        } else if has(&[
+            "Could not resolve",
+            "Connection refused",
+            "timed out",
+            "No route to host",
+            "Network is unreachable",
+            "Connection reset",
+            "Connection closed",
+        ]) {
+            Self::HostUnreachable(stderr.to_string())
+        } else if has(&["No such file or directory", "Not a directory"]) {
+            Self::RemotePathMissing(stderr.to_string())
+        } else {
+            Self::Failed(stderr.to_string())
+        }
+    }
+}
+
+impl fmt::Display for SshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidConfig(msg) => write!(f, "{}", msg),
+            Self::NotInstalled => write!(f, "未检测到 SSH/SCP，请安装 OpenSSH"),
+            Self::AuthFailed => write!(f, "认证失败，请检查SSH密钥配置"),
+            Self::HostKeyRejected => write!(f, "主机密钥验证失败，请检查 known_hosts 或主机密钥检查方式"),
+            Self::HostUnreachable(detail) => write!(f, "无法连接服务器，请检查服务器地址和端口: {}", detail),
+            Self::RemotePathMissing(detail) => write!(f, "远程路径不存在: {}", detail),
+            Self::Failed(detail) => write!(f, "{}", detail),
+        }
+    }
+}
+
+// 前端收到 { kind, message }，可按 kind 区分错误类型
+impl Serialize for SshError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("SshError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+impl From<SshError> for String {
+    fn from(e: SshError) -> Self {
+        e.to_string()
+    }
+}
+
+// 用户名和主机名允许的字符（主机名也可以是 ~/.ssh/config 中的别名）
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-')
+}
+
+// 校验服务器地址：[user@]host 或 [user@][IPv6 地址]
+fn validate_server(server: &str) -> Result<(), SshError> {
+    let invalid = |msg: &str| Err(SshError::InvalidConfig(msg.to_string()));
+    let (user, host) = match server.rsplit_once('@') {
+        Some((user, host)) => (Some(user), host),
+        None => (None, server),
+    };
+    if let Some(user) = user {
+        if user.is_empty() || user.starts_with('-') || !user.chars().all(is_name_char) {
+            return invalid("服务器地址中的用户名不合法");
+        }
+    }
+    let host_ok = match host.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+        Some(ipv6) => !ipv6.is_empty() && ipv6.chars().all(|c| c.is_ascii_hexdigit() || c == ':' || c == '.'),
+        None => !host.is_empty() && !host.starts_with('-') && host.chars().all(is_name_char),
+    };
+    if !host_ok {
+        return invalid("服务器地址不合法，应为 user@host 形式");
+    }
+    Ok(())
+}
+
+// 校验远程路径：scp 在不同版本中对远程路径的解析方式不同（远程 shell 或 SFTP），
+// 只允许不需要转义的字符，保证两种方式下都指向同一个路径
+fn validate_remote_path(path: &str) -> Result<(), SshError> {
+    let invalid = |msg: &str| Err(SshError::InvalidConfig(msg.to_string()));
+    if path.is_empty() {
+        return invalid("远程路径不能为空");
+    }
+    if path.starts_with('-') {
+        return invalid("远程路径不能以 - 开头");
+    }
+    let rest = if path == "~" { "" } else { path.strip_prefix("~/").unwrap_or(path) };
+    if !rest.chars().all(|c| c.is_alphanumeric() || matches!(c, '/' | '.' | '_' | '-')) {
+        return invalid("远程路径只能包含字母、数字、中文和 / . _ -（可用 ~/ 开头）");
+    }
+    Ok(())
+}
+
+// 本机文件路径（私钥、known_hosts）：ssh 选项值中不能包含双引号和换行
+fn validate_local_file(path: &str, label: &str) -> Result<(), SshError> {
+    if path.chars().any(|c| c == '"' || c.is_control()) {
+        return Err(SshError::InvalidConfig(format!("{}路径包含不支持的字符", label)));
+    }
+    if !Path::new(path).is_file() {
+        return Err(SshError::InvalidConfig(format!("{}不存在: {}", label, path)));
+    }
+    Ok(())
+}
+
+impl RemoteSyncConfig {
+    fn port(&self) -> String {
+        self.port.unwrap_or(22).to_string()
+    }
+
+    // 校验配置，在执行任何 ssh / scp 命令之前调用
+    pub fn validate(&self) -> Result<(), SshError> {
+        validate_server(&self.server)?;
+        validate_remote_path(&self.remote_path)?;
+        if !self.identity_file.is_empty() {
+            validate_local_file(&self.identity_file, "私钥文件")?;
+        }
+        if !self.known_hosts_file.is_empty() {
+            validate_local_file(&self.known_hosts_file, "known_hosts 文件")?;
+        }
+        Ok(())
+    }
+
+    // ssh 和 scp 共用的选项（端口参数名不同：ssh 为 -p，scp 为 -P）
+    fn options(&self, port_flag: &str, connect_timeout: u32) -> Vec<String> {
+        let policy = match self.host_key_policy {
+            HostKeyPolicy::Strict => "yes",
+            HostKeyPolicy::AcceptNew => "accept-new",
+        };
+        let mut args = vec![
+            port_flag.to_string(), self.port(),
+            "-o".to_string(), "BatchMode=yes".to_string(),
+            "-o".to_string(), format!("ConnectTimeout={}", connect_timeout),
+            "-o".to_string(), format!("StrictHostKeyChecking={}", policy),
+        ];
+        if !self.known_hosts_file.is_empty() {
+            // 选项值中的空格会被当作多个文件的分隔符，因此加引号
+            args.extend(["-o".to_string(), format!("UserKnownHostsFile=\"{}\"", self.known_hosts_file)]);
+        }
+        if !self.identity_file.is_empty() {
+            args.extend(["-i".to_string(), self.identity_file.clone()]);
+            args.extend(["-o".to_string(), "IdentitiesOnly=yes".to_string()]);
+        }
+        args
+    }
+
+    // 在远程执行命令；主机名前加 -- 结束选项解析
+    fn ssh(&self, command: &str, connect_timeout: u32) -> Result<Output, SshError> {
+        run(Command::new("ssh")
+            .args(self.options("-p", connect_timeout))
+            .arg("--")
+            .arg(&self.server)
+            .arg(command))
+    }
+
+    fn scp(&self, from: &str, to: &str) -> Result<Output, SshError> {
+        run(Command::new("scp")
+            .arg("-r")
+            .args(self.options("-P", 10))
+            .arg("--")
+            .args([from, to]))
+    }
+
+    // scp 使用的远程地址 server:path
+    fn remote_spec(&self, suffix: &str) -> String {
+        format!("{}:{}{}", self.server, self.remote_path, suffix)
+    }
+}
+
+fn run(command: &mut Command) -> Result<Output, SshError> {
+    command.output().map_err(|e| match e.kind() {
+        ErrorKind::NotFound => SshError::NotInstalled,
+        _ => SshError::Failed(e.to_string()),
+    })
+}
+
+// 检测 SSH/SCP 是否可用
+pub fn check_available() -> bool {
+    match Command::new("scp").arg("--version").output() {
+        Ok(o) => o.status.success() || !o.stderr.is_empty(),
+        Err(_) => false,
+    }
+}
+
+// 测试 SSH 连接，并检查远程路径是否为文件夹
+pub fn test_connection(config: &RemoteSyncConfig) -> Result<String, SshError> {
+    config.validate()?;
+    let command = format!("test -d {}", shell_quote(&config.remote_path));
+    let output = config.ssh(&command, 5)?;
+    if output.status.success() {
+        return Ok("连接成功".to_string());
+    }
+    // 连接成功但 test 返回 1：远程路径不存在（ssh 自身的错误退出码为 255）
+    let stderr = stderr_of(&output);
+    if output.status.code() == Some(1) && stderr.is_empty() {
+        return Err(SshError::RemotePathMissing(format!("{}（首次同步时会自动创建）", config.remote_path)));
+    }
+    Err(SshError::classify(&stderr))
+}
+
+// 远程 shell 中的单引号转义；~/ 开头的路径保留 ~ 以便远程展开到用户目录
+fn shell_quote(s: &str) -> String {
+    let quote = |s: &str| format!("'{}'", s.replace('\'', r"'\''"));
+    match s.strip_prefix("~/") {
+        Some(rest) => format!("~/{}", quote(rest)),
+        None if s == "~" => "~".to_string(),
+        None => quote(s),
+    }
+}
+
+fn stderr_of(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).trim().to_string()
+}
+
+// 一次同步中使用的远程连接
+pub struct SshRemote {
+    config: RemoteSyncConfig,
+    // 本次同步的临时目录（快照和上传暂存），结束时删除
+    work_dir: PathBuf,
+}
+
+impl SshRemote {
+    pub fn new(config: RemoteSyncConfig, temp_root: &Path) -> Result<Self, SshError> {
+        config.validate()?;
+        let work_dir = temp_root.join(library::new_id());
+        Ok(Self { config, work_dir })
+    }
+
+    fn snapshot_dir(&self) -> PathBuf {
+        self.work_dir.join("remote")
+    }
+}
+
+impl SyncBackend for SshRemote {
+    fn identity(&self) -> String {
+        format!("ssh://{}:{}/{}", self.config.server, self.config.port(), self.config.remote_path)
+    }
+
+    // 下载远程文件夹快照并列出其中的提示词（远程文件夹不存在时视为空）
+    fn list(&mut self) -> Result<BTreeMap<String, RemoteFile>, String> {
+        fs::create_dir_all(&self.work_dir).map_err(|e| format!("创建临时目录失败: {}", e))?;
+        let snapshot = self.snapshot_dir();
+        let output = self.config.scp(&self.config.remote_spec(""), &snapshot.to_string_lossy())?;
+        if !output.status.success() {
+            match SshError::classify(&stderr_of(&output)) {
+                SshError::RemotePathMissing(_) => {}
+                e => return Err(format!("拉取失败: {}", e)),
+            }
+        }
+
+        let mut files = BTreeMap::new();
+        for path in library::walk_prompt_files(&snapshot) {
+            let Some(rel) = library::to_relative(&snapshot, &path) else { continue };
+            let bytes = fs::read(&path).map_err(|e| format!("读取远程文件失败: {}", e))?;
+            let hash = sync::content_hash(&bytes);
+            files.insert(rel, RemoteFile { token: hash.clone(), hash: Some(hash) });
+        }
+        Ok(files)
+    }
+
+    // 读取快照中的远程文件
+    fn read(&self, rel: &str) -> Result<Vec<u8>, String> {
+        fs::read(library::to_absolute(&self.snapshot_dir(), rel))
+            .map_err(|e| format!("读取远程文件失败: {}", e))
+    }
+
+    // 删除通过一次 ssh 执行，上传的文件放进暂存目录一次性 scp
+    fn write(
+        &mut self,
+        uploads: &[(String, Vec<u8>)],
+        deletes: &[String],
+    ) -> Result<BTreeMap<String, String>, String> {
+        let remote_dir = shell_quote(&self.config.remote_path);
+        let mut command = format!("mkdir -p -- {0} && cd -- {0}", remote_dir);
+        if !deletes.is_empty() {
+            let paths: Vec<String> = deletes.iter().map(|p| shell_quote(p)).collect();
+            command.push_str(&format!(" && rm -f -- {}", paths.join(" ")));
+        }
+        let output = self.config.ssh(&command, 10)?;
+        if !output.status.success() {
+            return Err(format!("更新远程文件夹失败: {}", SshError::classify(&stderr_of(&output))));
+        }
+
+        let mut tokens = BTreeMap::new();
+        if uploads.is_empty() {
+            return Ok(tokens);
+        }
+
+        // 只把需要上传的文件按相对路径放进暂存目录，再整体复制到远程
+        let staging = self.work_dir.join("upload");
+        for (rel, bytes) in uploads {
+            let path = library::to_absolute(&staging, rel);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|e| format!("创建临时目录失败: {}", e))?;
+            }
+            fs::write(&path, bytes).map_err(|e| format!("写入临时文件失败: {}", e))?;
+            tokens.insert(rel.clone(), sync::content_hash(bytes));
+        }
+
+        let local = format!("{}/.", staging.to_string_lossy());
+        let output = self.config.scp(&local, &self.config.remote_spec("/"))?;
+        if !output.status.success() {
+            return Err(format!("推送失败: {}", SshError::classify(&stderr_of(&output))));
+        }
+        Ok(tokens)
+    }
+}
+
+impl Drop for SshRemote {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.work_dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_server_accepts_plain_hosts() {
+        for server in ["host", "me@host", "deploy_1@git.example.com", "me@192.168.1.10", "me@[::1]", "[fe80::1]", "a-b@c-d"] {
+            assert_eq!(validate_server(server), Ok(()), "{:?}", server);
+        }
+    }
+
+    #[test]
+    fn validate_server_rejects_injection() {
+        for server in [
+            // 空主机或用户名
+            "", "me@", "@host", "me@[]",
+            // 以 - 开头会被 ssh 当作选项
+            "-oProxyCommand=sh", "me@-oProxyCommand=sh", "-me@host",
+            // shell 元字符
+            "me@host;rm -rf ~", "me@host$(id)", "me@`id`", "me$(id)@host", "me@host|id", "me@host&id",
+            // 引号、空格和换行
+            "me@ho'st", "o'neil@host", "me@host name", "me @host", "me@host\nid", "me@host\r", "me@host\t",
+            // 其他不合法的写法
+            "me@[::1];id", "me@[::1", "me@host:22", "me@host/path",
+        ] {
+            assert!(
+                matches!(validate_server(server), Err(SshError::InvalidConfig(_))),
+                "{:?} 应被拒绝", server
+            );
+        }
+    }
+
+    #[test]
+    fn validate_remote_path_accepts_safe_paths() {
+        for path in ["~", "~/prompts", "~/提示词/备份", "/srv/prompts", "prompts/a.b_c-d", "./prompts"] {
+            assert_eq!(validate_remote_path(path), Ok(()), "{:?}", path);
+        }
+    }
+
+    #[test]
+    fn validate_remote_path_rejects_injection() {
+        for path in [
+            "", "-rf", "-oProxyCommand=sh",
+            "~/a;id", "~/$(id)", "~/`id`", "a|b", "a&b", "a>b", "$HOME/prompts",
+            "~/it's", "~/a\"b", "~/my prompts", "~/a\nb", "~/a\tb",
+            // ~user 会被展开为其他用户的家目录
+            "~root/prompts", "~/*", "~/a?", "a\\b",
+        ] {
+            assert!(
+                matches!(validate_remote_path(path), Err(SshError::InvalidConfig(_))),
+                "{:?} 应被拒绝", path
+            );
+        }
+    }
+
+    // 在本机 sh 中展开引用结果，应得到原字符串
+    #[cfg(unix)]
+    fn sh_echo(quoted: &str) -> String {
+        let output = Command::new("sh").arg("-c").arg(format!("printf %s {}", quoted)).output().unwrap();
+        assert!(output.status.success(), "{}", stderr_of(&output));
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn shell_quote_survives_shell_metacharacters() {
+        for s in ["plain", "it's", "''", "a b", "a;id", "$(id)", "`id`", "a\nb", "-rf", "*", "$HOME", "\\", "中文 路径"] {
+            assert_eq!(sh_echo(&shell_quote(s)), s, "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn shell_quote_keeps_home_prefix_unquoted() {
+        assert_eq!(shell_quote("~"), "~");
+        assert_eq!(shell_quote("~/prompts"), "~/'prompts'");
+        assert_eq!(shell_quote("~/it's"), r"~/'it'\''s'");
+        // 只展开开头的 ~/
+        assert_eq!(shell_quote("a/~/b"), "'a/~/b'");
+        assert_eq!(shell_quote("~root"), "'~root'");
+    }
+}
@@ -0,0 +1,421 @@
+// 双向同步 - 记录上次同步时两端每个文件的版本，据此分别算出本机和远程的新增 / 修改 / 删除并合并
+// 两端都修改了同一文件时保留本机版本，远程版本另存为冲突副本（两端都会同步到），不覆盖任何一方
+// 本机被删除的文件移到回收站；远程存储通过 SyncBackend 接入（SSH、WebDAV），可由 crypto::EncryptedBackend 包装加密
+use crate::crypto;
+use crate::library;
+use crate::metadata;
+use crate::trash;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// 文件内容的哈希
+pub fn content_hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+// 远程文件：token 为远程的版本标识（内容变化时改变），能直接得到内容哈希时附带 hash
+#[derive(Debug, Clone)]
+pub struct RemoteFile {
+    pub token: String,
+    pub hash: Option<String>,
+}
+
+// 同步后端：远程存储需要提供的操作
+pub trait SyncBackend {
+    // 用于区分同步记录的远程标识
+    fn identity(&self) -> String;
+
+    // 列出远程的提示词文件（相对路径 -> 远程文件），远程文件夹不存在时返回空
+    fn list(&mut self) -> Result<BTreeMap<String, RemoteFile>, String>;
+
+    fn read(&self, rel: &str) -> Result<Vec<u8>, String>;
+
+    // 上传并删除远程文件，返回上传文件的新版本标识
+    fn write(
+        &mut self,
+        uploads: &[(String, Vec<u8>)],
+        deletes: &[String],
+    ) -> Result<BTreeMap<String, String>, String>;
+
+    // 读取后发现远程文件需要重新上传（如开启同步加密前上传的明文）
+    fn needs_reupload(&self, _rel: &str) -> bool {
+        false
+    }
+}
+
+// 上次同步后两端一致的版本
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SyncedFile {
+    pub hash: String,
+    pub remote: String,
+}
+
+// 同步记录（每个提示词库 + 远程一份，保存在配置目录中）
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Manifest {
+    #[serde(default)]
+    root: String,
+    #[serde(default)]
+    remote: String,
+    #[serde(default)]
+    pub files: BTreeMap<String, SyncedFile>,
+}
+
+impl Manifest {
+    // 加载同步记录；提示词库或远程不同时视为从未同步
+    pub fn load(path: &Path, root: &Path, remote: &str) -> Self {
+        let root = root.to_string_lossy().to_string();
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Self>(&content).ok())
+            .filter(|m| m.root == root && m.remote == remote)
+            .unwrap_or_else(|| Self { root, remote: remote.to_string(), files: BTreeMap::new() })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("序列化同步记录失败: {}", e))?;
+        fs::write(path, content).map_err(|e| format!("保存同步记录失败: {}", e))
+    }
+}
+
+// 同步操作
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKind {
+    // 本机新增或修改，上传
+    Upload,
+    // 远程新增或修改，下载
+    Download,
+    // 远程已删除，删除本机文件（移到回收站）
+    DeleteLocal,
+    // 本机已删除，删除远程文件
+    DeleteRemote,
+    // 两端都修改，保留本机版本并生成冲突副本
+    Conflict,
+    // 两端已一致（如都做了相同修改或都已删除），只更新同步记录
+    Record,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SyncAction {
+    pub rel_path: String,
+    pub kind: ActionKind,
+}
+
+// 同步结果
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct SyncReport {
+    pub message: String,
+    pub uploaded: usize,
+    pub downloaded: usize,
+    pub deleted_local: usize,
+    pub deleted_remote: usize,
+    // 生成的冲突副本（相对路径）
+    pub conflicts: Vec<String>,
+    // 移到回收站的本机文件，供更新 ID 索引
+    #[serde(skip)]
+    pub removed: Vec<PathBuf>,
+}
+
+// 比较上次同步记录与两端当前状态，得出每个文件需要的操作
+// local 为 相对路径 -> 内容哈希，remote 为 相对路径 -> 远程文件
+pub fn plan(
+    base: &BTreeMap<String, SyncedFile>,
+    local: &BTreeMap<String, String>,
+    remote: &BTreeMap<String, RemoteFile>,
+) -> Vec<SyncAction> {
+    let paths: BTreeSet<&String> = base.keys().chain(local.keys()).chain(remote.keys()).collect();
+
+    paths.into_iter()
+        .filter_map(|rel| {
+            let synced = base.get(rel);
+            let local_hash = local.get(rel);
+            let remote_file = remote.get(rel);
+            let local_changed = local_hash != synced.map(|s| &s.hash);
+            let remote_changed = remote_file.map(|r| &r.token) != synced.map(|s| &s.remote);
+
+            let kind = match (local_changed, remote_changed, local_hash, remote_file) {
+                (false, false, _, _) => return None,
+                (true, false, Some(_), _) => ActionKind::Upload,
+                (true, false, None, _) => ActionKind::DeleteRemote,
+                (false, true, _, Some(_)) => ActionKind::Download,
+                (false, true, _, None) => ActionKind::DeleteLocal,
+                // 一端删除、另一端修改时保留修改
+                (true, true, None, Some(_)) => ActionKind::Download,
+                (true, true, Some(_), None) => ActionKind::Upload,
+                (true, true, None, None) => ActionKind::Record,
+                (true, true, Some(hash), Some(file)) if file.hash.as_ref() == Some(hash) => ActionKind::Record,
+                (true, true, Some(_), Some(_)) => ActionKind::Conflict,
+            };
+            Some(SyncAction { rel_path: rel.clone(), kind })
+        })
+        .collect()
+}
+
+// 本机提示词库中的文件及内容哈希
+fn local_files(root: &Path) -> Result<BTreeMap<String, String>, String> {
+    let mut files = BTreeMap::new();
+    for path in library::walk_prompt_files(root) {
+        let Some(rel) = library::to_relative(root, &path) else { continue };
+        let bytes = fs::read(&path).map_err(|e| format!("读取文件失败: {}", e))?;
+        files.insert(rel, content_hash(&bytes));
+    }
+    Ok(files)
+}
+
+// 冲突副本的路径：标题 (冲突副本 日期 时间).md，已存在时追加序号
+fn conflict_copy_path(root: &Path, rel: &str) -> String {
+    let path = Path::new(rel);
+    let stem = library::file_title(path);
+    let ext = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    let parent = rel.rsplit_once('/').map(|(dir, _)| format!("{}/", dir)).unwrap_or_default();
+    let stamp = chrono::Local::now().format("%Y-%m-%d %H%M");
+
+    (1..)
+        .map(|n| match n {
+            1 => format!("{}{} (冲突副本 {}){}", parent, stem, stamp, ext),
+            n => format!("{}{} (冲突副本 {} {}){}", parent, stem, stamp, n, ext),
+        })
+        .find(|candidate| !library::to_absolute(root, candidate).exists())
+        .unwrap_or_default()
+}
+
+// 冲突副本去掉 ID 和助记词，避免与原文件重复（加密的提示词解密后处理，再重新加密）
+fn conflict_copy_content(bytes: &[u8]) -> Vec<u8> {
+    let raw = String::from_utf8_lossy(bytes);
+    let Ok(text) = crypto::decrypt_text(&raw) else {
+        return bytes.to_vec();
+    };
+    let (mut meta, body) = metadata::parse(&text);
+    meta.id = None;
+    meta.set_mnemonics(Vec::new());
+    let content = match metadata::compose(&meta, &body) {
+        Ok(content) if crypto::is_armored(&raw) => crypto::encrypt_text(&content),
+        result => result,
+    };
+    content.map(String::into_bytes).unwrap_or_else(|_| bytes.to_vec())
+}
+
+// 读取远程文件；未开启同步加密时拒绝写入密文，避免覆盖本机内容
+fn read_remote(remote: &dyn SyncBackend, rel: &str) -> Result<Vec<u8>, String> {
+    let bytes = remote.read(rel)?;
+    if crypto::is_sealed(&bytes) {
+        return Err("远程文件已加密，请在设置中开启同步加密并解锁".to_string());
+    }
+    Ok(bytes)
+}
+
+fn write_local(root: &Path, rel: &str, bytes: &[u8]) -> Result<(), String> {
+    let path = library::to_absolute(root, rel);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+    }
+    fs::write(&path, bytes).map_err(|e| format!("写入文件失败: {}", e))
+}
+
+// 预览同步操作（不修改任何文件）
+pub fn preview(root: &Path, manifest_path: &Path, remote: &mut dyn SyncBackend) -> Result<Vec<SyncAction>, String> {
+    let manifest = Manifest::load(manifest_path, root, &remote.identity());
+    let remote_files = remote.list()?;
+    let local = local_files(root)?;
+    Ok(plan(&manifest.files, &local, &remote_files)
+        .into_iter()
+        .filter(|a| a.kind != ActionKind::Record)
+        .collect())
+}
+
+// 执行双向同步：先在本机应用远程的变化，再把本机的变化一次性推送到远程
+// 全部成功后才写入同步记录，中途失败时下次同步会重新比较（已一致的文件只更新记录）
+pub fn run(root: &Path, manifest_path: &Path, remote: &mut dyn SyncBackend) -> Result<SyncReport, String> {
+    let mut manifest = Manifest::load(manifest_path, root, &remote.identity());
+    let remote_files = remote.list()?;
+    let local = local_files(root)?;
+    let actions = plan(&manifest.files, &local, &remote_files);
+
+    let mut report = SyncReport::default();
+    let mut uploads: Vec<(String, Vec<u8>)> = Vec::new();
+    let mut deletes = Vec::new();
+    let read_local = |rel: &str| fs::read(library::to_absolute(root, rel))
+        .map_err(|e| format!("读取文件失败: {}", e));
+
+    for action in &actions {
+        let rel = action.rel_path.as_str();
+        let token = remote_files.get(rel).map(|f| f.token.clone()).unwrap_or_default();
+        match action.kind {
+            ActionKind::Upload => {
+                uploads.push((rel.to_string(), read_local(rel)?));
+                report.uploaded += 1;
+            }
+            ActionKind::DeleteRemote => {
+                deletes.push(rel.to_string());
+                manifest.files.remove(rel);
+            }
+            ActionKind::Download => {
+                let bytes = read_remote(remote, rel)?;
+                write_local(root, rel, &bytes)?;
+                manifest.files.insert(rel.to_string(), SyncedFile { hash: content_hash(&bytes), remote: token });
+                report.downloaded += 1;
+                if remote.needs_reupload(rel) {
+                    uploads.push((rel.to_string(), bytes));
+                    report.uploaded += 1;
+                }
+            }
+            ActionKind::DeleteLocal => {
+                let path = library::to_absolute(root, rel);
+                trash::move_to_trash(root, &path)?;
+                manifest.files.remove(rel);
+                report.removed.push(path);
+            }
+            ActionKind::Record => match local.get(rel) {
+                Some(hash) => {
+                    manifest.files.insert(rel.to_string(), SyncedFile { hash: hash.clone(), remote: token });
+                }
+                None => {
+                    manifest.files.remove(rel);
+                }
+            },
+            ActionKind::Conflict => {
+                let ours = read_local(rel)?;
+                let theirs = read_remote(remote, rel)?;
+                if content_hash(&ours) == content_hash(&theirs) {
+                    if remote.needs_reupload(rel) {
+                        uploads.push((rel.to_string(), ours));
+                        report.uploaded += 1;
+                    } else {
+                        manifest.files.insert(rel.to_string(), SyncedFile { hash: content_hash(&ours), remote: token });
+                    }
+                    continue;
+                }
+                let copy = conflict_copy_path(root, rel);
+                let copy_content = conflict_copy_content(&theirs);
+                write_local(root, &copy, &copy_content)?;
+                uploads.push((rel.to_string(), ours));
+                uploads.push((copy.clone(), copy_content));
+                report.conflicts.push(copy);
+            }
+        }
+    }
+
+    let tokens = remote.write(&uploads, &deletes)?;
+    for (rel, bytes) in &uploads {
+        let remote = tokens.get(rel).cloned().unwrap_or_default();
+        manifest.files.insert(rel.clone(), SyncedFile { hash: content_hash(bytes), remote });
+    }
+    manifest.save(manifest_path)?;
+
+    report.deleted_remote = deletes.len();
+    report.deleted_local = report.removed.len();
+    report.message = summary(&report);
+    Ok(report)
+}
+
+fn summary(report: &SyncReport) -> String {
+    let parts: Vec<String> = [
+        (report.downloaded, "下载"),
+        (report.uploaded, "上传"),
+        (report.deleted_local, "删除本机"),
+        (report.deleted_remote, "删除远程"),
+        (report.conflicts.len(), "冲突"),
+    ]
+    .iter()
+    .filter(|(n, _)| *n > 0)
+    .map(|(n, label)| format!("{} {} 个", label, n))
+    .collect();
+
+    if parts.is_empty() {
+        "已是最新".to_string()
+    } else {
+        format!("同步完成：{}", parts.join("，"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Base = Option<(&'static str, &'static str)>;
+    type Remote = Option<(&'static str, Option<&'static str>)>;
+    // (说明, 上次同步记录, 本机哈希, 远程, 期望的操作)
+    type Case = (&'static str, Base, Option<&'static str>, Remote, Option<ActionKind>);
+
+    // 单个文件的同步计划：上次同步记录（哈希，远程标识）、本机哈希、远程（标识，哈希）
+    fn plan_one(base: Base, local: Option<&str>, remote: Remote) -> Option<ActionKind> {
+        let rel = "写作/a.md".to_string();
+        let base: BTreeMap<_, _> = base.into_iter()
+            .map(|(hash, token)| (rel.clone(), SyncedFile { hash: hash.to_string(), remote: token.to_string() }))
+            .collect();
+        let local: BTreeMap<_, _> = local.into_iter().map(|hash| (rel.clone(), hash.to_string())).collect();
+        let remote: BTreeMap<_, _> = remote.into_iter()
+            .map(|(token, hash)| (rel.clone(), RemoteFile { token: token.to_string(), hash: hash.map(str::to_string) }))
+            .collect();
+        let actions = plan(&base, &local, &remote);
+        assert!(actions.len() <= 1);
+        actions.into_iter().map(|a| a.kind).next()
+    }
+
+    #[test]
+    fn plan_covers_every_action_kind() {
+        use ActionKind::*;
+        let synced = Some(("h1", "t1"));
+        let cases: [Case; 20] = [
+            ("两端未变", synced, Some("h1"), Some(("t1", None)), None),
+            ("两端都没有", None, None, None, None),
+            // Upload
+            ("本机新增", None, Some("h1"), None, Some(Upload)),
+            ("本机修改", synced, Some("h2"), Some(("t1", None)), Some(Upload)),
+            ("本机修改、远程已删除", synced, Some("h2"), None, Some(Upload)),
+            // Download
+            ("远程新增", None, None, Some(("t1", None)), Some(Download)),
+            ("远程修改", synced, Some("h1"), Some(("t2", None)), Some(Download)),
+            ("本机已删除、远程修改", synced, None, Some(("t2", None)), Some(Download)),
+            // DeleteLocal
+            ("远程已删除", synced, Some("h1"), None, Some(DeleteLocal)),
+            // DeleteRemote
+            ("本机已删除", synced, None, Some(("t1", None)), Some(DeleteRemote)),
+            // Conflict
+            ("两端都修改", synced, Some("h2"), Some(("t2", Some("h3"))), Some(Conflict)),
+            ("两端都修改、远程无哈希", synced, Some("h2"), Some(("t2", None)), Some(Conflict)),
+            ("两端都新增且内容不同", None, Some("h1"), Some(("t1", Some("h2"))), Some(Conflict)),
+            ("两端都新增、远程无哈希", None, Some("h1"), Some(("t1", None)), Some(Conflict)),
+            // Record
+            ("两端都修改为相同内容", synced, Some("h2"), Some(("t2", Some("h2"))), Some(Record)),
+            ("两端都新增相同内容", None, Some("h1"), Some(("t1", Some("h1"))), Some(Record)),
+            ("两端都已删除", synced, None, None, Some(Record)),
+            // 远程哈希与上次相同但标识变化（如重新上传），仍按远程修改处理
+            ("远程仅标识变化", synced, Some("h1"), Some(("t2", Some("h1"))), Some(Download)),
+            ("本机修改、远程哈希未变", synced, Some("h2"), Some(("t1", Some("h1"))), Some(Upload)),
+            ("本机已删除、远程哈希变化但标识未变", synced, None, Some(("t1", Some("h9"))), Some(DeleteRemote)),
+        ];
+        for (name, base, local, remote, expected) in cases {
+            assert_eq!(plan_one(base, local, remote), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn plan_handles_many_files_in_path_order() {
+        let base = BTreeMap::from([
+            ("b.md".to_string(), SyncedFile { hash: "hb".into(), remote: "tb".into() }),
+            ("c.md".to_string(), SyncedFile { hash: "hc".into(), remote: "tc".into() }),
+        ]);
+        let local = BTreeMap::from([
+            ("a.md".to_string(), "ha".to_string()),
+            ("b.md".to_string(), "hb".to_string()),
+        ]);
+        let remote = BTreeMap::from([
+            ("b.md".to_string(), RemoteFile { token: "tb".into(), hash: None }),
+            ("d.md".to_string(), RemoteFile { token: "td".into(), hash: None }),
+        ]);
+        let actions: Vec<(String, ActionKind)> = plan(&base, &local, &remote).into_iter()
+            .map(|a| (a.rel_path, a.kind))
+            .collect();
+        assert_eq!(actions, vec![
+            ("a.md".to_string(), ActionKind::Upload),
+            ("c.md".to_string(), ActionKind::Record),
+            ("d.md".to_string(), ActionKind::Download),
+        ]);
+    }
+}
@@ -0,0 +1,458 @@
+// 提示词模板 - 插入前替换 {{...}} 占位符
+//
+// 支持的占位符：
+//   {{selection}}                      插入前目标窗口中选中的文本
+//   {{clipboard}}                      当前剪贴板文本
+//   {{date}} / {{date:%Y-%m-%d}}       当前日期时间（strftime 格式）
+//   {{input:language|default=Rust}}    插入前由用户填写，可带 default / label
+//   {{cursor}}                         插入完成后光标停留的位置
+//   {{> path/to/partial}}              引入提示词库中的其他提示词（相对根目录，可省略扩展名）
+// 无法识别的占位符原样保留，\{{ 输出字面量 {{
+use chrono::format::{Item, StrftimeItems};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+// 片段最大嵌套层数
+pub const MAX_INCLUDE_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Placeholder {
+    Selection,
+    Clipboard,
+    Date(String),
+    Input(TemplateInput),
+    Cursor,
+    // 无法识别的占位符，保存原始文本
+    Unknown(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Text(String),
+    Placeholder(Placeholder),
+}
+
+// 需要用户填写的输入项
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TemplateInput {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+}
+
+// 渲染所需的上下文
+#[derive(Debug, Clone, Default)]
+pub struct RenderContext {
+    pub selection: Option<String>,
+    pub clipboard: Option<String>,
+    pub inputs: HashMap<String, String>,
+}
+
+// 渲染结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Rendered {
+    pub text: String,
+    // {{cursor}} 之后的字符数，插入后按相应次数的左方向键即可把光标移回该处
+    pub cursor_offset: Option<usize>,
+}
+
+// ====== 片段引入 ======
+
+// 片段名规范化：统一 / 分隔，去掉开头的 / 和扩展名
+pub fn partial_key(name: &str) -> String {
+    let name = name.trim().replace('\\', "/");
+    let name = name.trim_start_matches('/');
+    name.strip_suffix(".md")
+        .or_else(|| name.strip_suffix(".txt"))
+        .unwrap_or(name)
+        .to_string()
+}
+
+// 是否包含 {{> ...}}
+pub fn has_includes(text: &str) -> bool {
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        if rest[start + 2..].trim_start().starts_with('>') {
+            return true;
+        }
+        rest = &rest[start + 2..];
+    }
+    false
+}
+
+// 展开 {{> path}}，partials 为 片段名 -> 正文
+// 先于其他占位符展开，片段中的占位符随后统一渲染
+pub fn expand_includes(text: &str, partials: &HashMap<String, String>) -> Result<String, String> {
+    expand(text, partials, &mut Vec::new())
+}
+
+fn expand(text: &str, partials: &HashMap<String, String>, stack: &mut Vec<String>) -> Result<String, String> {
+    let mut out = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        let (before, after) = rest.split_at(start);
+        let escaped = before.ends_with('\\');
+        let include = after.find("}}")
+            .map(|end| (end, after[2..end].trim()))
+            .filter(|(_, inner)| !escaped && inner.starts_with('>'));
+
+        let Some((end, inner)) = include else {
+            out.push_str(&rest[..start + 2]);
+            rest = &rest[start + 2..];
+            continue;
+        };
+
+        let name = inner[1..].trim();
+        let key = partial_key(name);
+        if stack.contains(&key) {
+            return Err(format!("片段循环引用: {} -> {}", stack.join(" -> "), key));
+        }
+        if stack.len() >= MAX_INCLUDE_DEPTH {
+            return Err(format!("片段嵌套超过 {} 层: {}", MAX_INCLUDE_DEPTH, key));
+        }
+        let body = partials.get(&key)
+            .ok_or_else(|| format!("找不到片段: {}", name))?;
+
+        out.push_str(before);
+        stack.push(key);
+        out.push_str(&expand(body, partials, stack)?);
+        stack.pop();
+        rest = &after[end + 2..];
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
+// 解析模板
+pub fn parse(text: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        // \{{ 转义为字面量
+        if rest[..start].ends_with('\\') {
+            literal.push_str(&rest[..start - 1]);
+            literal.push_str("{{");
+            rest = &rest[start + 2..];
+            continue;
+        }
+
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        literal.push_str(&rest[..start]);
+        if !literal.is_empty() {
+            segments.push(Segment::Text(std::mem::take(&mut literal)));
+        }
+
+        let raw = &rest[start..start + 2 + len + 2];
+        let inner = &rest[start + 2..start + 2 + len];
+        segments.push(Segment::Placeholder(parse_placeholder(inner, raw)));
+        rest = &rest[start + 2 + len + 2..];
+    }
+
+    literal.push_str(rest);
+    if !literal.is_empty() {
+        segments.push(Segment::Text(literal));
+    }
+    segments
+}
+
+// 解析单个占位符：name[:arg][|key=value]...
+fn parse_placeholder(inner: &str, raw: &str) -> Placeholder {
+    let mut parts = inner.split('|');
+    let head = parts.next().unwrap_or_default().trim();
+    let (name, arg) = match head.split_once(':') {
+        Some((name, arg)) => (name.trim(), Some(arg.trim())),
+        None => (head, None),
+    };
+
+    let options: HashMap<&str, &str> = parts
+        .filter_map(|p| p.split_once('='))
+        .map(|(k, v)| (k.trim(), v.trim()))
+        .collect();
+
+    match (name, arg) {
+        ("selection", None) => Placeholder::Selection,
+        ("clipboard", None) => Placeholder::Clipboard,
+        ("cursor", None) => Placeholder::Cursor,
+        ("date", format) => Placeholder::Date(
+            format.filter(|f| !f.is_empty()).unwrap_or(DEFAULT_DATE_FORMAT).to_string(),
+        ),
+        ("input", Some(input)) if !input.is_empty() => Placeholder::Input(TemplateInput {
+            name: input.to_string(),
+            label: options.get("label").map(|s| s.to_string()),
+            default: options.get("default").map(|s| s.to_string()),
+        }),
+        _ => Placeholder::Unknown(raw.to_string()),
+    }
+}
+
+// 模板中需要用户填写的输入项（按出现顺序去重）
+pub fn required_inputs(segments: &[Segment]) -> Vec<TemplateInput> {
+    let mut inputs: Vec<TemplateInput> = Vec::new();
+    for segment in segments {
+        if let Segment::Placeholder(Placeholder::Input(input)) = segment {
+            if !inputs.iter().any(|i| i.name == input.name) {
+                inputs.push(input.clone());
+            }
+        }
+    }
+    inputs
+}
+
+// 是否用到了选中文本（需要在插入前从目标窗口获取）
+pub fn uses_selection(segments: &[Segment]) -> bool {
+    segments.iter().any(|s| matches!(s, Segment::Placeholder(Placeholder::Selection)))
+}
+
+// 是否用到了剪贴板
+pub fn uses_clipboard(segments: &[Segment]) -> bool {
+    segments.iter().any(|s| matches!(s, Segment::Placeholder(Placeholder::Clipboard)))
+}
+
+// 既没有填写也没有默认值的输入项
+pub fn missing_inputs(segments: &[Segment], values: &HashMap<String, String>) -> Vec<String> {
+    required_inputs(segments)
+        .into_iter()
+        .filter(|i| i.default.is_none() && !values.contains_key(&i.name))
+        .map(|i| i.name)
+        .collect()
+}
+
+fn format_date(format: &str) -> Result<String, String> {
+    let items: Vec<Item> = StrftimeItems::new(format).collect();
+    if items.iter().any(|i| matches!(i, Item::Error)) {
+        return Err(format!("日期格式错误: {}", format));
+    }
+    Ok(chrono::Local::now().format_with_items(items.into_iter()).to_string())
+}
+
+// 渲染模板
+pub fn render(segments: &[Segment], ctx: &RenderContext) -> Result<Rendered, String> {
+    let mut text = String::new();
+    let mut cursor = None;
+
+    for segment in segments {
+        match segment {
+            Segment::Text(s) => text.push_str(s),
+            Segment::Placeholder(p) => match p {
+                Placeholder::Selection => text.push_str(ctx.selection.as_deref().unwrap_or_default()),
+                Placeholder::Clipboard => text.push_str(ctx.clipboard.as_deref().unwrap_or_default()),
+                Placeholder::Date(format) => text.push_str(&format_date(format)?),
+                Placeholder::Input(input) => {
+                    let value = ctx.inputs.get(&input.name)
+                        .or(input.default.as_ref())
+                        .map(|s| s.as_str())
+                        .unwrap_or_default();
+                    text.push_str(value);
+                }
+                // 只记录第一个 {{cursor}} 的位置
+                Placeholder::Cursor => {
+                    cursor.get_or_insert(text.chars().count());
+                }
+                Placeholder::Unknown(raw) => text.push_str(raw),
+            },
+        }
+    }
+
+    let cursor_offset = cursor.map(|pos| text.chars().count() - pos);
+    Ok(Rendered { text, cursor_offset })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> Segment {
+        Segment::Text(s.to_string())
+    }
+
+    fn placeholder(p: Placeholder) -> Segment {
+        Segment::Placeholder(p)
+    }
+
+    fn input(name: &str, label: Option<&str>, default: Option<&str>) -> Placeholder {
+        Placeholder::Input(TemplateInput {
+            name: name.to_string(),
+            label: label.map(str::to_string),
+            default: default.map(str::to_string),
+        })
+    }
+
+    #[test]
+    fn parses_known_placeholders() {
+        assert_eq!(
+            parse("请翻译：{{selection}}\n参考 {{ clipboard }}，{{cursor}}"),
+            vec![
+                text("请翻译："),
+                placeholder(Placeholder::Selection),
+                text("\n参考 "),
+                placeholder(Placeholder::Clipboard),
+                text("，"),
+                placeholder(Placeholder::Cursor),
+            ]
+        );
+        assert_eq!(
+            parse("{{date}}{{date:}}{{date:%H:%M}}"),
+            vec![
+                placeholder(Placeholder::Date("%Y-%m-%d".to_string())),
+                placeholder(Placeholder::Date("%Y-%m-%d".to_string())),
+                placeholder(Placeholder::Date("%H:%M".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_input_options() {
+        assert_eq!(
+            parse("{{input:language|default=Rust|label=编程语言}}{{input: tone }}{{input:x|default=|bogus}}"),
+            vec![
+                placeholder(input("language", Some("编程语言"), Some("Rust"))),
+                placeholder(input("tone", None, None)),
+                placeholder(input("x", None, Some(""))),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_unknown_and_unclosed_placeholders() {
+        assert_eq!(
+            parse("a {{foo}} {{input:}} {{selection:x}} b"),
+            vec![
+                text("a "),
+                placeholder(Placeholder::Unknown("{{foo}}".to_string())),
+                text(" "),
+                placeholder(Placeholder::Unknown("{{input:}}".to_string())),
+                text(" "),
+                placeholder(Placeholder::Unknown("{{selection:x}}".to_string())),
+                text(" b"),
+            ]
+        );
+        // 没有闭合的 {{ 之后全部按文本处理
+        assert_eq!(parse("x {{selection"), vec![text("x {{selection")]);
+        assert_eq!(parse(""), Vec::<Segment>::new());
+    }
+
+    #[test]
+    fn escaped_braces_are_literal() {
+        assert_eq!(
+            parse(r"\{{selection}} {{selection}}"),
+            vec![text("{{selection}} "), placeholder(Placeholder::Selection)]
+        );
+        assert_eq!(parse(r"代码 \{{ x }}"), vec![text("代码 {{ x }}")]);
+        let rendered = render(&parse(r"\{{cursor}}"), &RenderContext::default()).unwrap();
+        assert_eq!(rendered.text, "{{cursor}}");
+        assert_eq!(rendered.cursor_offset, None);
+    }
+
+    #[test]
+    fn detects_required_and_missing_inputs() {
+        let segments = parse("{{input:a}} {{input:b|default=1}} {{input:a|label=重复}} {{input:c}} {{selection}}");
+        let names: Vec<String> = required_inputs(&segments).into_iter().map(|i| i.name).collect();
+        assert_eq!(names, ["a", "b", "c"]);
+
+        let mut values = HashMap::new();
+        assert_eq!(missing_inputs(&segments, &values), ["a", "c"]);
+        values.insert("a".to_string(), String::new());
+        assert_eq!(missing_inputs(&segments, &values), ["c"]);
+        values.insert("c".to_string(), "x".to_string());
+        assert!(missing_inputs(&segments, &values).is_empty());
+
+        assert!(uses_selection(&segments));
+        assert!(!uses_clipboard(&segments));
+        assert!(!uses_selection(&parse(r"\{{selection}}")));
+    }
+
+    #[test]
+    fn renders_values_and_cursor_offset() {
+        let ctx = RenderContext {
+            selection: Some("选中".to_string()),
+            clipboard: None,
+            inputs: HashMap::from([("lang".to_string(), "中文".to_string())]),
+        };
+        let rendered = render(
+            &parse("译为{{input:lang|default=English}}：{{selection}}{{cursor}}。{{clipboard}}{{input:n|default=好}}{{cursor}}"),
+            &ctx,
+        ).unwrap();
+        assert_eq!(rendered.text, "译为中文：选中。好");
+        // 按字符而不是字节计算，只取第一个 {{cursor}}
+        assert_eq!(rendered.cursor_offset, Some(2));
+    }
+
+    #[test]
+    fn rejects_invalid_date_format() {
+        assert!(render(&parse("{{date:%Q}}"), &RenderContext::default()).is_err());
+        let today = chrono::Local::now().format("%Y").to_string();
+        assert_eq!(render(&parse("{{date:%Y}}"), &RenderContext::default()).unwrap().text, today);
+    }
+
+    fn partial_map(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn expands_nested_partials() {
+        let partials = partial_map(&[
+            ("common/署名", "—— {{input:name|default=我}}"),
+            ("信件", "你好，\n{{> common/署名.md }}"),
+        ]);
+        assert_eq!(
+            expand_includes("{{>信件}}\n{{> /common/署名}}", &partials).unwrap(),
+            "你好，\n—— {{input:name|default=我}}\n—— {{input:name|default=我}}"
+        );
+        // 转义的引入和其他占位符原样保留
+        assert_eq!(expand_includes(r"\{{> 信件}} {{selection}}", &partials).unwrap(), r"\{{> 信件}} {{selection}}");
+        assert!(has_includes("a {{ > b}}"));
+        assert!(!has_includes("a {{selection}} > b"));
+        assert_eq!(partial_key(r" \写作\润色.txt "), "写作/润色");
+    }
+
+    #[test]
+    fn rejects_self_include() {
+        let partials = partial_map(&[("a", "A {{> a}}")]);
+        let err = expand_includes(&partials["a"], &partials).unwrap_err();
+        assert!(err.contains("循环引用") && err.contains("a -> a"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_include_cycle() {
+        let partials = partial_map(&[("a", "A {{> b}}"), ("b", "B {{> a.md}}")]);
+        let err = expand_includes("{{> a}}", &partials).unwrap_err();
+        assert!(err.contains("循环引用") && err.contains("a -> b -> a"), "{}", err);
+        // 同一片段被引用两次但不构成循环时正常展开
+        let partials = partial_map(&[("top", "{{> left}}{{> right}}"), ("left", "[{{> x}}]"), ("right", "[{{> x}}]"), ("x", "x")]);
+        assert_eq!(expand_includes("{{> top}}", &partials).unwrap(), "[x][x]");
+    }
+
+    #[test]
+    fn rejects_missing_partial() {
+        let partials = partial_map(&[("a", "A {{> 不存在}}")]);
+        let err = expand_includes("{{> a}}", &partials).unwrap_err();
+        assert!(err.contains("找不到片段") && err.contains("不存在"), "{}", err);
+    }
+
+    #[test]
+    fn limits_include_depth() {
+        // p1 -> p2 -> ... -> pn，每层输出自己的序号
+        let chain = |n: usize| -> HashMap<String, String> {
+            (1..=n)
+                .map(|i| {
+                    let next = if i < n { format!("{{{{> p{}}}}}", i + 1) } else { String::new() };
+                    (format!("p{}", i), format!("{}{}", i, next))
+                })
+                .collect()
+        };
+        assert_eq!(MAX_INCLUDE_DEPTH, 8);
+        assert_eq!(expand_includes("{{> p1}}", &chain(MAX_INCLUDE_DEPTH)).unwrap(), "12345678");
+        let err = expand_includes("{{> p1}}", &chain(MAX_INCLUDE_DEPTH + 1)).unwrap_err();
+        assert!(err.contains("嵌套超过 8 层") && err.contains("p9"), "{}", err);
+    }
+}
@@ -0,0 +1,352 @@
+// 回收站 - 删除的提示词和文件夹移到提示词库下的 .trash 中，可以恢复或彻底删除
+// 目录结构：.trash/<条目 ID>/<原文件或文件夹名>，trash.json 记录原路径、删除时间和其中的助记词
+// 助记词保存在 front matter 中，随文件一起进出回收站
+use crate::library::{self, ScannedFile};
+use crate::metadata;
+use crate::mnemonic;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const TRASH_DIR: &str = ".trash";
+const MANIFEST: &str = "trash.json";
+
+// 回收站中的提示词
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TrashedPrompt {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    // 删除前相对提示词库根目录的路径
+    pub rel_path: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mnemonics: Vec<String>,
+}
+
+// 回收站条目（一次删除操作）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TrashEntry {
+    pub id: String,
+    // 删除前相对提示词库根目录的路径
+    pub original_path: String,
+    pub deleted_at: String,
+    pub is_dir: bool,
+    #[serde(default)]
+    pub prompts: Vec<TrashedPrompt>,
+}
+
+impl TrashEntry {
+    fn name(&self) -> &str {
+        self.original_path.rsplit('/').next().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct Manifest {
+    #[serde(default)]
+    entries: Vec<TrashEntry>,
+}
+
+fn trash_dir(root: &Path) -> PathBuf {
+    root.join(TRASH_DIR)
+}
+
+impl Manifest {
+    fn load(root: &Path) -> Result<Self, String> {
+        let path = trash_dir(root).join(MANIFEST);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("读取回收站失败: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("解析回收站失败: {}", e))
+    }
+
+    fn save(&self, root: &Path) -> Result<(), String> {
+        let dir = trash_dir(root);
+        fs::create_dir_all(&dir).map_err(|e| format!("创建回收站失败: {}", e))?;
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("序列化回收站失败: {}", e))?;
+        fs::write(dir.join(MANIFEST), content).map_err(|e| format!("保存回收站失败: {}", e))
+    }
+}
+
+// 记录被删除的提示词（用于列表展示）
+fn collect_prompts(root: &Path, path: &Path) -> Vec<TrashedPrompt> {
+    let files = if path.is_dir() {
+        library::walk_prompt_files(path)
+    } else {
+        vec![path.to_path_buf()]
+    };
+    files.iter()
+        .filter_map(|file| {
+            let rel_path = library::to_relative(root, file)?;
+            let meta = metadata::read_prompt_file(file).map(|(meta, _)| meta).unwrap_or_default();
+            Some(TrashedPrompt { mnemonics: meta.mnemonics(), id: meta.id, rel_path })
+        })
+        .collect()
+}
+
+// 将文件或文件夹移到回收站
+pub fn move_to_trash(root: &Path, path: &Path) -> Result<TrashEntry, String> {
+    let original_path = library::to_relative(root, path)
+        .filter(|rel| !rel.is_empty())
+        .ok_or("只能删除提示词库中的文件")?;
+    if library::is_hidden(&original_path) {
+        return Err("无法删除隐藏文件夹中的项目".to_string());
+    }
+    let name = path.file_name().ok_or("无法获取文件名")?;
+
+    let entry = TrashEntry {
+        id: library::new_id(),
+        deleted_at: metadata::now_timestamp(),
+        is_dir: path.is_dir(),
+        prompts: collect_prompts(root, path),
+        original_path,
+    };
+
+    let dir = trash_dir(root).join(&entry.id);
+    fs::create_dir_all(&dir).map_err(|e| format!("创建回收站失败: {}", e))?;
+    if let Err(e) = fs::rename(path, dir.join(name)) {
+        let _ = fs::remove_dir(&dir);
+        return Err(format!("移到回收站失败: {}", e));
+    }
+
+    let mut manifest = Manifest::load(root)?;
+    manifest.entries.push(entry.clone());
+    manifest.save(root)?;
+    Ok(entry)
+}
+
+// 回收站条目（最近删除的在前），内容已丢失的条目不列出
+pub fn list(root: &Path) -> Result<Vec<TrashEntry>, String> {
+    let dir = trash_dir(root);
+    Ok(Manifest::load(root)?
+        .entries
+        .into_iter()
+        .rev()
+        .filter(|e| dir.join(&e.id).join(e.name()).exists())
+        .collect())
+}
+
+// 原位置已被占用时依次尝试 "名称 2"、"名称 3" ...
+fn available_path(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+    let stem = library::file_title(path);
+    let ext = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    (2..)
+        .map(|n| path.with_file_name(format!("{} {}{}", stem, n, ext)))
+        .find(|p| !p.exists())
+        .unwrap_or_default()
+}
+
+// 恢复到原位置（原父文件夹已删除时重新创建），返回恢复后的路径
+pub fn restore(root: &Path, id: &str) -> Result<PathBuf, String> {
+    let mut manifest = Manifest::load(root)?;
+    let pos = manifest.entries.iter().position(|e| e.id == id).ok_or("回收站中不存在该项目")?;
+    let entry = &manifest.entries[pos];
+
+    let stored = trash_dir(root).join(&entry.id).join(entry.name());
+    if !stored.exists() {
+        return Err("回收站中的文件已丢失".to_string());
+    }
+    let target = available_path(&library::to_absolute(root, &entry.original_path));
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+    }
+    fs::rename(&stored, &target).map_err(|e| format!("恢复失败: {}", e))?;
+
+    let _ = fs::remove_dir_all(trash_dir(root).join(&entry.id));
+    manifest.entries.remove(pos);
+    manifest.save(root)?;
+    Ok(target)
+}
+
+// 彻底删除回收站中的条目；id 为 None 时清空回收站，返回删除的条目数
+pub fn empty(root: &Path, id: Option<&str>) -> Result<usize, String> {
+    let mut manifest = Manifest::load(root)?;
+    let dir = trash_dir(root);
+    let before = manifest.entries.len();
+
+    let mut result = Ok(());
+    manifest.entries.retain(|e| {
+        if id.is_some_and(|id| id != e.id) {
+            return true;
+        }
+        let path = dir.join(&e.id);
+        match fs::remove_dir_all(&path) {
+            Ok(()) => false,
+            Err(_) if !path.exists() => false,
+            Err(err) => {
+                result = Err(format!("删除失败: {}", err));
+                true
+            }
+        }
+    });
+    let removed = before - manifest.entries.len();
+    if id.is_some() && removed == 0 && result.is_ok() {
+        return Err("回收站中不存在该项目".to_string());
+    }
+
+    manifest.save(root)?;
+    result.map(|_| removed)
+}
+
+// 恢复的提示词中在删除期间被其他提示词占用的助记词
+// 返回有冲突的提示词、应保留的助记词和被移除的助记词（带命名空间）
+pub fn conflicting_mnemonics<'a>(
+    files: &'a [ScannedFile],
+    restored: &Path,
+) -> Vec<(&'a ScannedFile, Vec<String>, Vec<String>)> {
+    files.iter()
+        .filter(|f| f.path.starts_with(restored))
+        .filter_map(|file| {
+            let namespace = mnemonic::namespace_of(&file.rel_path);
+            let (keep, conflicts): (Vec<String>, Vec<String>) = file.metadata.mnemonics()
+                .into_iter()
+                .partition(|m| mnemonic::find_conflict(files, &namespace, m, file.id()).is_none());
+            if conflicts.is_empty() {
+                return None;
+            }
+            let dropped = conflicts.iter().map(|m| mnemonic::qualified(&namespace, m)).collect();
+            Some((file, keep, dropped))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(root: &Path, rel: &str, mnemonic: Option<&str>) {
+        let path = root.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let meta = metadata::PromptMetadata {
+            id: Some(format!("id-{}", rel)),
+            mnemonic: mnemonic.map(str::to_string),
+            ..Default::default()
+        };
+        metadata::write_prompt_file(&path, &meta, rel).unwrap();
+    }
+
+    fn file(rel_path: &str, id: &str, mnemonics: &[&str]) -> ScannedFile {
+        let mut metadata = metadata::PromptMetadata { id: Some(id.to_string()), ..Default::default() };
+        metadata.set_mnemonics(mnemonics.iter().map(|m| m.to_string()).collect());
+        ScannedFile {
+            path: PathBuf::from("/library").join(rel_path),
+            rel_path: rel_path.to_string(),
+            metadata,
+            body: String::new(),
+        }
+    }
+
+    #[test]
+    fn moves_files_and_folders_to_trash() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, "a.md", Some("a"));
+        write(root, "work/b.md", Some("b"));
+        write(root, "work/c.md", None);
+
+        let first = move_to_trash(root, &root.join("a.md")).unwrap();
+        assert!(!first.is_dir);
+        assert_eq!(first.prompts.len(), 1);
+        assert_eq!(first.prompts[0].id.as_deref(), Some("id-a.md"));
+        assert_eq!(first.prompts[0].mnemonics, ["a"]);
+        assert!(!root.join("a.md").exists());
+        assert!(trash_dir(root).join(&first.id).join("a.md").exists());
+
+        let second = move_to_trash(root, &root.join("work")).unwrap();
+        assert!(second.is_dir);
+        let mut rels: Vec<_> = second.prompts.iter().map(|p| p.rel_path.as_str()).collect();
+        rels.sort();
+        assert_eq!(rels, ["work/b.md", "work/c.md"]);
+
+        // 最近删除的在前，内容已丢失的条目不列出
+        let ids: Vec<_> = list(root).unwrap().into_iter().map(|e| e.id).collect();
+        assert_eq!(ids, [second.id.clone(), first.id.clone()]);
+        fs::remove_dir_all(trash_dir(root).join(&first.id)).unwrap();
+        let ids: Vec<_> = list(root).unwrap().into_iter().map(|e| e.id).collect();
+        assert_eq!(ids, [second.id]);
+    }
+
+    #[test]
+    fn rejects_paths_outside_library_or_hidden() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("library");
+        write(&root, ".hidden/a.md", None);
+        write(dir.path(), "outside.md", None);
+        assert!(move_to_trash(&root, &root).is_err());
+        assert!(move_to_trash(&root, &dir.path().join("outside.md")).is_err());
+        assert!(move_to_trash(&root, &root.join(".hidden/a.md")).is_err());
+        assert!(list(&root).unwrap().is_empty());
+    }
+
+    #[test]
+    fn restores_to_original_or_next_free_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, "work/a.md", None);
+        let entry = move_to_trash(root, &root.join("work/a.md")).unwrap();
+        // 原父文件夹已删除时重新创建
+        fs::remove_dir(root.join("work")).unwrap();
+        assert_eq!(restore(root, &entry.id).unwrap(), root.join("work/a.md"));
+        assert!(list(root).unwrap().is_empty());
+        assert!(!trash_dir(root).join(&entry.id).exists());
+        assert!(restore(root, &entry.id).is_err());
+
+        // 原位置已被占用时改名为 "a 2"、"a 3"
+        let first = move_to_trash(root, &root.join("work/a.md")).unwrap();
+        write(root, "work/a.md", None);
+        let second = move_to_trash(root, &root.join("work/a.md")).unwrap();
+        write(root, "work/a.md", None);
+        assert_eq!(restore(root, &second.id).unwrap(), root.join("work/a 2.md"));
+        assert_eq!(restore(root, &first.id).unwrap(), root.join("work/a 3.md"));
+        let (meta, body) = metadata::read_prompt_file(&root.join("work/a 3.md")).unwrap();
+        assert_eq!((meta.id.as_deref(), body.as_str()), (Some("id-work/a.md"), "work/a.md"));
+    }
+
+    #[test]
+    fn empties_one_entry_or_everything() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for name in ["a.md", "b.md", "c.md"] {
+            write(root, name, None);
+        }
+        let a = move_to_trash(root, &root.join("a.md")).unwrap();
+        move_to_trash(root, &root.join("b.md")).unwrap();
+        move_to_trash(root, &root.join("c.md")).unwrap();
+
+        assert_eq!(empty(root, Some(&a.id)).unwrap(), 1);
+        assert!(!trash_dir(root).join(&a.id).exists());
+        assert_eq!(list(root).unwrap().len(), 2);
+        assert!(empty(root, Some(&a.id)).is_err());
+        assert_eq!(empty(root, None).unwrap(), 2);
+        assert!(list(root).unwrap().is_empty());
+        assert_eq!(empty(root, None).unwrap(), 0);
+    }
+
+    #[test]
+    fn drops_mnemonics_taken_while_trashed() {
+        let files = [
+            file("work/a.md", "a", &["sum", "keep"]),
+            file("work/sub/b.md", "b", &["sum"]),
+            file("work/c.md", "c", &["sum", "other"]),
+            file("personal/d.md", "d", &["keep"]),
+            file("x.md", "x", &["keep"]),
+        ];
+        // 恢复了 work 文件夹后，work 下的 sum 与已有的 c 冲突；其他命名空间中的同名助记词不冲突
+        let conflicts = conflicting_mnemonics(&files[..2], Path::new("/library/work"));
+        assert!(conflicts.is_empty());
+        let conflicts = conflicting_mnemonics(&files, Path::new("/library/work/a.md"));
+        assert_eq!(conflicts.len(), 1);
+        let (restored, keep, dropped) = &conflicts[0];
+        assert_eq!(restored.id(), "a");
+        assert_eq!(keep, &["keep"]);
+        assert_eq!(dropped, &["work/sum"]);
+
+        let conflicts = conflicting_mnemonics(&files, Path::new("/library/work/sub"));
+        assert!(conflicts.is_empty());
+    }
+}
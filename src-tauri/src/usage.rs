@@ -0,0 +1,231 @@
+// 使用记录 - 记录每次插入 / 复制，按 frecency（使用频率 + 最近使用时间）排序快速插入结果
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+// 每个提示词保留的最近使用时间数量
+const MAX_RECENT: usize = 10;
+
+const DAY: i64 = 24 * 60 * 60;
+
+// 按距今天数给每次使用加权（与浏览器地址栏的 frecency 算法类似）
+// 时钟回拨导致使用时间晚于当前时间时按刚刚使用计算
+fn recency_weight(age: i64) -> f64 {
+    match age.max(0) / DAY {
+        0..=3 => 100.0,
+        4..=14 => 70.0,
+        15..=31 => 50.0,
+        32..=90 => 30.0,
+        _ => 10.0,
+    }
+}
+
+// 单个提示词的使用统计
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct UsageStats {
+    #[serde(default)]
+    pub count: u32,
+    // 最近几次使用时间（Unix 时间戳，秒）
+    #[serde(default)]
+    pub recent: Vec<i64>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+}
+
+impl UsageStats {
+    pub fn last_used(&self) -> Option<i64> {
+        self.recent.last().copied()
+    }
+
+    // 最近使用的平均权重 × 总次数
+    pub fn frecency(&self, now: i64) -> f64 {
+        if self.recent.is_empty() {
+            return 0.0;
+        }
+        let total: f64 = self.recent.iter()
+            .map(|&t| recency_weight(now.saturating_sub(t)))
+            .sum();
+        f64::from(self.count) * total / self.recent.len() as f64
+    }
+}
+
+// 使用记录：id -> 统计
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct UsageLog {
+    #[serde(default)]
+    pub prompts: BTreeMap<String, UsageStats>,
+}
+
+impl UsageLog {
+    // 加载使用记录（损坏时从空记录开始）
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("序列化使用记录失败: {}", e))?;
+        fs::write(path, content).map_err(|e| format!("保存使用记录失败: {}", e))
+    }
+
+    pub fn record(&mut self, id: &str, now: i64) {
+        let stats = self.prompts.entry(id.to_string()).or_default();
+        stats.count += 1;
+        stats.recent.push(now);
+        if stats.recent.len() > MAX_RECENT {
+            stats.recent.remove(0);
+        }
+    }
+
+    pub fn set_pinned(&mut self, id: &str, pinned: bool) {
+        let stats = self.prompts.entry(id.to_string()).or_default();
+        stats.pinned = pinned;
+        if !pinned && stats.count == 0 {
+            self.prompts.remove(id);
+        }
+    }
+
+    // 清空使用次数（保留置顶）；id 为 None 时清空全部
+    pub fn reset(&mut self, id: Option<&str>) {
+        self.prompts.retain(|key, stats| {
+            if id.is_some_and(|id| id != key) {
+                return true;
+            }
+            stats.count = 0;
+            stats.recent.clear();
+            stats.pinned
+        });
+    }
+
+    pub fn is_pinned(&self, id: &str) -> bool {
+        self.prompts.get(id).is_some_and(|s| s.pinned)
+    }
+
+    pub fn frecency(&self, id: &str, now: i64) -> f64 {
+        self.prompts.get(id).map_or(0.0, |s| s.frecency(now))
+    }
+}
+
+pub fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 固定的当前时间：2024-06-01 00:00:00 UTC
+    const NOW: i64 = 1_717_200_000;
+
+    fn days_ago(days: i64) -> i64 {
+        NOW - days * DAY
+    }
+
+    #[test]
+    fn recency_weight_buckets() {
+        let cases = [
+            (0, 100.0), (3 * DAY + DAY - 1, 100.0),
+            (4 * DAY, 70.0), (14 * DAY, 70.0),
+            (15 * DAY, 50.0), (31 * DAY, 50.0),
+            (32 * DAY, 30.0), (90 * DAY, 30.0),
+            (91 * DAY, 10.0), (3650 * DAY, 10.0),
+            // 使用时间在未来
+            (-1, 100.0), (-5 * DAY, 100.0),
+        ];
+        for (age, weight) in cases {
+            assert_eq!(recency_weight(age), weight, "age {}", age);
+        }
+    }
+
+    #[test]
+    fn frecency_is_count_times_average_weight() {
+        let mut log = UsageLog::default();
+        assert_eq!(log.frecency("a", NOW), 0.0);
+        log.record("a", days_ago(1));
+        log.record("a", days_ago(10));
+        assert_eq!(log.frecency("a", NOW), 2.0 * (100.0 + 70.0) / 2.0);
+    }
+
+    #[test]
+    fn frecency_decays_over_time() {
+        let mut log = UsageLog::default();
+        log.record("a", NOW);
+        let scores: Vec<f64> = [0, 4, 15, 32, 91].iter()
+            .map(|&days| log.frecency("a", NOW + days * DAY))
+            .collect();
+        assert_eq!(scores, [100.0, 70.0, 50.0, 30.0, 10.0]);
+    }
+
+    #[test]
+    fn recent_use_beats_old_frequent_use() {
+        let mut log = UsageLog::default();
+        for _ in 0..5 {
+            log.record("old", days_ago(100));
+        }
+        log.record("recent", days_ago(1));
+        // 5 × 10 = 50 < 1 × 100
+        assert!(log.frecency("recent", NOW) > log.frecency("old", NOW));
+        log.record("recent", days_ago(200));
+        // 较早的使用会拉低平均权重：2 × (100 + 10) / 2 = 110
+        assert_eq!(log.frecency("recent", NOW), 110.0);
+    }
+
+    #[test]
+    fn record_keeps_only_recent_timestamps() {
+        let mut log = UsageLog::default();
+        for day in (0..12).rev() {
+            log.record("a", days_ago(day));
+        }
+        let stats = &log.prompts["a"];
+        assert_eq!(stats.count, 12);
+        assert_eq!(stats.recent.len(), MAX_RECENT);
+        assert_eq!(stats.recent.first(), Some(&days_ago(9)));
+        assert_eq!(stats.last_used(), Some(NOW));
+        // 被丢弃的旧时间不再参与平均：0..=3 天 4 次为 100，4..=9 天 6 次为 70
+        assert_eq!(log.frecency("a", NOW), 12.0 * (4.0 * 100.0 + 6.0 * 70.0) / 10.0);
+    }
+
+    #[test]
+    fn reset_keeps_pins() {
+        let mut log = UsageLog::default();
+        log.record("a", NOW);
+        log.record("b", NOW);
+        log.set_pinned("b", true);
+        log.set_pinned("c", true);
+
+        log.reset(Some("a"));
+        assert!(!log.prompts.contains_key("a"));
+        assert_eq!(log.frecency("b", NOW), 100.0);
+
+        log.reset(None);
+        assert_eq!(log.prompts.keys().collect::<Vec<_>>(), ["b", "c"]);
+        assert_eq!(log.frecency("b", NOW), 0.0);
+        assert!(log.is_pinned("b"));
+
+        // 取消置顶且没有使用记录时删除
+        log.set_pinned("c", false);
+        assert!(!log.prompts.contains_key("c"));
+    }
+
+    #[test]
+    fn load_tolerates_missing_or_corrupt_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("usage.json");
+        assert!(UsageLog::load(&path).prompts.is_empty());
+
+        let mut log = UsageLog::default();
+        log.record("a", NOW);
+        log.set_pinned("a", true);
+        log.save(&path).unwrap();
+        let loaded = UsageLog::load(&path);
+        assert_eq!(loaded.prompts["a"].recent, [NOW]);
+        assert!(loaded.is_pinned("a"));
+
+        fs::write(&path, "{ not json").unwrap();
+        assert!(UsageLog::load(&path).prompts.is_empty());
+    }
+}
@@ -0,0 +1,356 @@
+// 文件监听 - 递归监听提示词库目录，增量更新内存中的搜索索引，并把变化推送给主窗口和弹窗
+//
+// 前端事件：
+//   prompt-created / prompt-changed / prompt-removed   PromptEvent
+//   folder-renamed                                       FolderRenamedEvent
+use crate::library::{self, LibraryIndex};
+use crate::search_index::{ChangeKind, SearchIndex};
+use notify_debouncer_full::notify::event::{EventKind, ModifyKind, RenameMode};
+use notify_debouncer_full::notify::{Event, RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{new_debouncer, DebounceEventResult, DebouncedEvent, Debouncer, RecommendedCache};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+// 事件去抖时间：编辑器保存时常见的 写临时文件 + 重命名 会合并为一次更新
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+type LibraryWatcher = Debouncer<RecommendedWatcher, RecommendedCache>;
+
+// 提示词文件变化
+#[derive(Debug, Serialize, Clone)]
+pub struct PromptEvent {
+    pub id: Option<String>,
+    // 相对提示词库根目录的路径
+    pub rel_path: String,
+    pub file_path: String,
+}
+
+// 文件夹重命名（相对路径）
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct FolderRenamedEvent {
+    pub from: String,
+    pub to: String,
+}
+
+// 文件监听事件整理后的更新
+#[derive(Debug, PartialEq)]
+enum WatchUpdate {
+    // 重新同步该路径（文件或文件夹），与索引比较后得到新建、修改或删除
+    Sync(PathBuf),
+    // 提示词库中的文件夹被重命名或移动
+    FolderRenamed(FolderRenamedEvent),
+}
+
+// 将去抖后的文件监听事件整理为更新（不访问文件系统，is_dir 判断事件发生后路径是否为文件夹）
+// - 同一路径只同步一次；提示词库外和隐藏的路径（回收站、编辑器的隐藏临时文件）忽略
+// - 文件夹重命名合并为一次 FolderRenamed；移入或移出回收站只同步库中的一端，即删除或新建
+fn plan_updates<'a>(
+    root: &Path,
+    events: impl IntoIterator<Item = &'a Event>,
+    is_dir: impl Fn(&Path) -> bool,
+) -> Vec<WatchUpdate> {
+    let visible = |path: &Path| library::to_relative(root, path).filter(|rel| !library::is_hidden(rel));
+    let mut updates = Vec::new();
+    for event in events {
+        if let (EventKind::Modify(ModifyKind::Name(RenameMode::Both)), [from, to]) =
+            (event.kind, event.paths.as_slice())
+        {
+            if let (true, Some(from), Some(to)) = (is_dir(to), visible(from), visible(to)) {
+                updates.push(WatchUpdate::FolderRenamed(FolderRenamedEvent { from, to }));
+                continue;
+            }
+        }
+        for path in &event.paths {
+            let update = WatchUpdate::Sync(path.clone());
+            if visible(path).is_some() && !updates.contains(&update) {
+                updates.push(update);
+            }
+        }
+    }
+    updates
+}
+
+fn event_name(kind: ChangeKind) -> &'static str {
+    match kind {
+        ChangeKind::Created => "prompt-created",
+        ChangeKind::Changed => "prompt-changed",
+        ChangeKind::Removed => "prompt-removed",
+    }
+}
+
+// 提示词库运行时状态（Tauri managed state）
+#[derive(Default)]
+pub struct LibraryState {
+    index: Mutex<SearchIndex>,
+    watcher: Mutex<Option<LibraryWatcher>>,
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl LibraryState {
+    fn is_watching(&self) -> bool {
+        lock(&self.watcher).is_some()
+    }
+
+    // 访问搜索索引
+    // 首次访问或根目录变化时从缓存加载；文件监听不可用（如部分网络盘）时每次访问前按 mtime/size 刷新
+    // 加载或刷新后为缺少 ID 的提示词（如应用未运行时新增的文件）分配 ID
+    pub fn with_index<T>(&self, root: &Path, f: impl FnOnce(&SearchIndex) -> T) -> T {
+        let watching = self.is_watching();
+        let mut index = lock(&self.index);
+        let changed = if !index.is_loaded() || index.root() != root {
+            *index = SearchIndex::load(&crate::get_search_cache_path(), root);
+            true
+        } else {
+            !watching && index.refresh()
+        };
+        if changed {
+            let rels = index.without_id();
+            assign_ids(&mut index, &rels, &crate::get_index_path());
+        }
+        save_cache(&mut index);
+        f(&index)
+    }
+
+    // 丢弃内存中的索引，下次访问时重新加载（加密的提示词解锁或锁定后）
+    pub fn reload(&self) {
+        *lock(&self.index) = SearchIndex::default();
+    }
+
+    // 应用文件监听事件并通知前端
+    // 文件夹重命名只发送 folder-renamed，不再逐个发送其下文件的变化
+    fn apply(&self, app: &AppHandle, events: &[DebouncedEvent]) {
+        let mut index = lock(&self.index);
+        if !index.is_loaded() {
+            return;
+        }
+        let root = index.root().to_path_buf();
+        let mut changes = Vec::new();
+        let mut renamed = Vec::new();
+        // 重命名的文件夹中的提示词，只需更新 ID 索引中的位置
+        let mut moved = Vec::new();
+
+        for update in plan_updates(&root, events.iter().map(|e| &e.event), Path::is_dir) {
+            match update {
+                WatchUpdate::Sync(path) => changes.extend(index.update_path(&path)),
+                WatchUpdate::FolderRenamed(event) => {
+                    index.update_path(&library::to_absolute(&root, &event.from));
+                    let to = library::to_absolute(&root, &event.to);
+                    moved.extend(index.update_path(&to).into_iter().map(|c| c.rel_path));
+                    renamed.push(event);
+                }
+            }
+        }
+
+        // 外部新增或复制的文件补充 ID 后再通知前端
+        let rels: Vec<String> = changes.iter()
+            .filter(|c| c.kind != ChangeKind::Removed)
+            .map(|c| c.rel_path.clone())
+            .chain(moved)
+            .collect();
+        assign_ids(&mut index, &rels, &crate::get_index_path());
+        for change in changes.iter_mut().filter(|c| c.kind != ChangeKind::Removed) {
+            change.id = index.id_of(&change.rel_path);
+        }
+        save_cache(&mut index);
+        drop(index);
+
+        for change in changes {
+            let payload = PromptEvent {
+                id: change.id,
+                file_path: library::to_absolute(&root, &change.rel_path).to_string_lossy().to_string(),
+                rel_path: change.rel_path,
+            };
+            let _ = app.emit(event_name(change.kind), payload);
+        }
+        for payload in renamed {
+            let _ = app.emit("folder-renamed", payload);
+        }
+    }
+}
+
+// 为提示词补充 ID（缺少或与其他文件重复时），并同步 ID 索引中的位置
+fn assign_ids(index: &mut SearchIndex, rels: &[String], index_path: &Path) {
+    if rels.is_empty() {
+        return;
+    }
+    let mut library_index = match LibraryIndex::load(index_path, index.root()) {
+        Ok(library_index) => library_index,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+    let mut files = index.files_sharing_ids(rels);
+    if library::index_files(&mut library_index, &mut files) {
+        if let Err(e) = library_index.save(index_path) {
+            eprintln!("{}", e);
+        }
+    }
+    for file in files {
+        index.set_metadata(&file.rel_path, file.metadata);
+    }
+}
+
+fn save_cache(index: &mut SearchIndex) {
+    if let Err(e) = index.save(&crate::get_search_cache_path()) {
+        eprintln!("{}", e);
+    }
+}
+
+// 开始递归监听提示词库（替换之前的监听），并在后台预先加载索引
+pub fn watch_library(app: &AppHandle, root: PathBuf) -> Result<(), String> {
+    let state = app.state::<LibraryState>();
+    *lock(&state.watcher) = None;
+    if !root.is_dir() {
+        return Err("文件夹不存在".to_string());
+    }
+
+    let handle = app.clone();
+    let mut debouncer = new_debouncer(DEBOUNCE, None, move |result: DebounceEventResult| {
+        match result {
+            Ok(events) => handle.state::<LibraryState>().apply(&handle, &events),
+            Err(errors) => {
+                for e in errors {
+                    eprintln!("文件监听出错: {}", e);
+                }
+            }
+        }
+    })
+    .map_err(|e| format!("启动文件监听失败: {}", e))?;
+
+    debouncer.watch(&root, RecursiveMode::Recursive)
+        .map_err(|e| format!("启动文件监听失败: {}", e))?;
+    *lock(&state.watcher) = Some(debouncer);
+
+    let handle = app.clone();
+    std::thread::spawn(move || {
+        handle.state::<LibraryState>().with_index(&root, |index| {
+            println!("搜索索引已加载 {} 个提示词", index.len());
+        });
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify_debouncer_full::notify::event::{CreateKind, DataChange, RemoveKind};
+    use std::fs;
+
+    fn event(kind: EventKind, paths: &[&str]) -> Event {
+        paths.iter().fold(Event::new(kind), |e, p| e.add_path(Path::new("/library").join(p)))
+    }
+
+    fn sync(rel: &str) -> WatchUpdate {
+        WatchUpdate::Sync(Path::new("/library").join(rel))
+    }
+
+    fn renamed(from: &str, to: &str) -> WatchUpdate {
+        WatchUpdate::FolderRenamed(FolderRenamedEvent { from: from.to_string(), to: to.to_string() })
+    }
+
+    #[test]
+    fn plans_updates_from_debounced_events() {
+        let create = EventKind::Create(CreateKind::File);
+        let modify = EventKind::Modify(ModifyKind::Data(DataChange::Content));
+        let remove = EventKind::Remove(RemoveKind::File);
+        let rename = EventKind::Modify(ModifyKind::Name(RenameMode::Both));
+        let rename_from = EventKind::Modify(ModifyKind::Name(RenameMode::From));
+        let rename_to = EventKind::Modify(ModifyKind::Name(RenameMode::To));
+        let dirs = ["archive", "archive/work", "personal/work", "work", "work/daily"];
+
+        let cases: Vec<(&str, Vec<Event>, Vec<WatchUpdate>)> = vec![
+            ("修改", vec![event(modify, &["a.md"])], vec![sync("a.md")]),
+            ("新建后写入只同步一次", vec![event(create, &["a.md"]), event(modify, &["a.md"])], vec![sync("a.md")]),
+            ("删除", vec![event(remove, &["work/a.md"])], vec![sync("work/a.md")]),
+            ("重命名文件", vec![event(rename, &["a.md", "b.md"])], vec![sync("a.md"), sync("b.md")]),
+            (
+                "跨文件夹移动文件",
+                vec![event(rename, &["work/a.md", "personal/a.md"])],
+                vec![sync("work/a.md"), sync("personal/a.md")],
+            ),
+            ("重命名文件夹", vec![event(rename, &["old", "work"])], vec![renamed("old", "work")]),
+            (
+                "跨文件夹移动文件夹",
+                vec![event(rename, &["work/daily", "archive/work"])],
+                vec![renamed("work/daily", "archive/work")],
+            ),
+            (
+                "未配对的重命名",
+                vec![event(rename_from, &["work"]), event(rename_to, &["personal/work"])],
+                vec![sync("work"), sync("personal/work")],
+            ),
+            ("文件夹移到回收站", vec![event(rename, &["work", ".trash/1/work"])], vec![sync("work")]),
+            ("从回收站恢复", vec![event(rename, &[".trash/1/work", "work"])], vec![sync("work")]),
+            (
+                "隐藏临时文件重命名保存（gedit 等）",
+                vec![
+                    event(create, &[".goutputstream-X1"]),
+                    event(modify, &[".goutputstream-X1"]),
+                    event(rename, &[".goutputstream-X1", "a.md"]),
+                ],
+                vec![sync("a.md")],
+            ),
+            (
+                "先备份原文件再写入（vim 等）",
+                vec![
+                    event(rename, &["a.md", "a.md~"]),
+                    event(create, &["a.md"]),
+                    event(modify, &["a.md"]),
+                    event(remove, &["a.md~"]),
+                ],
+                vec![sync("a.md"), sync("a.md~")],
+            ),
+            (
+                "写入临时文件后替换（JetBrains 等）",
+                vec![
+                    event(create, &["work/a.md___jb_tmp___"]),
+                    event(rename, &["work/a.md", "work/a.md___jb_old___"]),
+                    event(rename, &["work/a.md___jb_tmp___", "work/a.md"]),
+                    event(remove, &["work/a.md___jb_old___"]),
+                ],
+                vec![sync("work/a.md___jb_tmp___"), sync("work/a.md"), sync("work/a.md___jb_old___")],
+            ),
+        ];
+
+        let is_dir = |path: &Path| dirs.iter().any(|d| path == Path::new("/library").join(d));
+        for (name, events, expected) in cases {
+            assert_eq!(plan_updates(Path::new("/library"), &events, is_dir), expected, "{}", name);
+        }
+
+        // 提示词库外的路径忽略
+        let outside = Event::new(modify).add_path(PathBuf::from("/elsewhere/a.md"));
+        assert!(plan_updates(Path::new("/library"), [&outside], is_dir).is_empty());
+    }
+
+    #[test]
+    fn assign_ids_skips_unparsable_front_matter() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("library");
+        fs::create_dir(&root).unwrap();
+        let broken = "---\nmnemonic: [unclosed\n---\n正文";
+        fs::write(root.join("broken.md"), broken).unwrap();
+        fs::write(root.join("new.md"), "新文件\n").unwrap();
+        let index_path = dir.path().join("index.json");
+
+        let mut index = SearchIndex::load(&dir.path().join("cache.json"), &root);
+        assert_eq!(index.without_id(), ["new.md"]);
+
+        // 文件监听传入的路径包含无法解析的文件时也不改写
+        assign_ids(&mut index, &["broken.md".to_string(), "new.md".to_string()], &index_path);
+        assert_eq!(fs::read_to_string(root.join("broken.md")).unwrap(), broken);
+        assert_eq!(index.id_of("broken.md"), None);
+        let id = index.id_of("new.md").unwrap();
+        let library_index = LibraryIndex::load(&index_path, &root).unwrap();
+        assert_eq!(library_index.path_of(&id), Some("new.md"));
+
+        index.update_path(&root.join("broken.md"));
+        assert!(index.without_id().is_empty());
+    }
+}
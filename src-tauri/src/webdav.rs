@@ -0,0 +1,634 @@
+// WebDAV 远程 - 通过 PROPFIND / GET / PUT / DELETE 读写 WebDAV 文件夹（如 Nextcloud），供双向同步使用
+// 以服务器返回的 ETag 判断远程文件是否变化，不需要下载全部文件
+use crate::library;
+use crate::sync::{RemoteFile, SyncBackend};
+use base64::Engine;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::io::Read;
+use std::time::Duration;
+
+// URL 路径段中需要编码的字符（只保留字母数字和 - _ . ~）
+const SEGMENT: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'_').remove(b'.').remove(b'~');
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:"><d:prop><d:resourcetype/><d:getetag/><d:getlastmodified/><d:getcontentlength/></d:prop></d:propfind>"#;
+
+// WebDAV 同步配置
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct WebDavConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    // 远程文件夹地址，如 https://cloud.example.com/remote.php/dav/files/me/prompts/
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub username: String,
+    // Nextcloud 等建议使用应用专用密码
+    #[serde(default)]
+    pub password: String,
+}
+
+// PROPFIND 返回的一项
+#[derive(Debug)]
+struct DavEntry {
+    // 相对远程文件夹的路径（已解码）
+    rel: String,
+    is_dir: bool,
+    token: String,
+}
+
+pub struct WebDavRemote {
+    config: WebDavConfig,
+    agent: ureq::Agent,
+    // 以 / 结尾的远程文件夹地址
+    base: String,
+    // 远程文件夹地址中解码后的路径部分，用于把 href 转换为相对路径
+    base_path: String,
+}
+
+// 地址中的路径部分（去掉协议和主机）
+fn url_path(url: &str) -> &str {
+    match url.split_once("://") {
+        Some((_, rest)) => rest.find('/').map_or("/", |i| &rest[i..]),
+        None => url,
+    }
+}
+
+fn decode(s: &str) -> String {
+    percent_decode_str(s).decode_utf8_lossy().to_string()
+}
+
+// 统一 ETag 的写法：去掉弱校验前缀 W/ 和引号
+// 部分服务器 PUT 响应头与 PROPFIND 的 getetag 写法不同（如一个带 W/ 或引号，另一个不带）
+fn normalize_etag(etag: &str) -> String {
+    let etag = etag.trim();
+    let etag = etag.strip_prefix("W/").unwrap_or(etag);
+    etag.trim_matches('"').to_string()
+}
+
+fn http_err(action: &str, e: ureq::Error) -> String {
+    match e {
+        ureq::Error::Status(401, _) => "认证失败，请检查用户名和密码".to_string(),
+        ureq::Error::Status(403, _) => format!("{}失败: 没有权限", action),
+        ureq::Error::Status(code, _) => format!("{}失败: HTTP {}", action, code),
+        ureq::Error::Transport(e) => format!("连接 WebDAV 服务器失败: {}", e),
+    }
+}
+
+// 解析 PROPFIND 的 207 Multi-Status 响应
+fn parse_multistatus(xml: &str, base_path: &str) -> Result<Vec<DavEntry>, String> {
+    let doc = roxmltree::Document::parse(xml).map_err(|e| format!("解析 WebDAV 响应失败: {}", e))?;
+    let is = |node: &roxmltree::Node, name: &str| {
+        node.is_element() && node.tag_name().name() == name && node.tag_name().namespace() == Some("DAV:")
+    };
+    let text = |node: &roxmltree::Node, name: &str| {
+        node.descendants().find(|n| is(n, name)).and_then(|n| n.text()).map(|t| t.trim().to_string())
+    };
+
+    let mut entries = Vec::new();
+    for response in doc.descendants().filter(|n| is(n, "response")) {
+        let Some(href) = text(&response, "href") else { continue };
+        let path = decode(url_path(&href));
+        let Some(rel) = path.strip_prefix(base_path) else { continue };
+
+        // 只取成功（200）的属性
+        let ok_props = response.children()
+            .filter(|n| is(n, "propstat"))
+            .filter(|n| text(n, "status").is_none_or(|s| s.contains(" 200")))
+            .filter_map(|n| n.children().find(|c| is(c, "prop")));
+        let mut is_dir = false;
+        let (mut etag, mut modified, mut length) = (None, None, None);
+        for prop in ok_props {
+            is_dir |= prop.descendants().any(|n| is(&n, "collection"));
+            etag = etag.or_else(|| text(&prop, "getetag").map(|t| normalize_etag(&t)).filter(|t| !t.is_empty()));
+            modified = modified.or_else(|| text(&prop, "getlastmodified"));
+            length = length.or_else(|| text(&prop, "getcontentlength"));
+        }
+
+        // 没有 ETag 的服务器用修改时间和大小代替
+        let token = etag.unwrap_or_else(|| {
+            format!("{}|{}", modified.unwrap_or_default(), length.unwrap_or_default())
+        });
+        entries.push(DavEntry { rel: rel.trim_matches('/').to_string(), is_dir, token });
+    }
+    Ok(entries)
+}
+
+impl WebDavRemote {
+    pub fn new(config: WebDavConfig) -> Result<Self, String> {
+        if !config.url.starts_with("http://") && !config.url.starts_with("https://") {
+            return Err("WebDAV 地址需以 http:// 或 https:// 开头".to_string());
+        }
+        let base = format!("{}/", config.url.trim_end_matches('/'));
+        let base_path = decode(url_path(&base));
+        let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+        Ok(Self { config, agent, base, base_path })
+    }
+
+    // 相对路径对应的地址（逐段编码）
+    fn url(&self, rel: &str) -> String {
+        let encoded: Vec<String> = rel.split('/')
+            .filter(|s| !s.is_empty())
+            .map(|s| utf8_percent_encode(s, SEGMENT).to_string())
+            .collect();
+        format!("{}{}", self.base, encoded.join("/"))
+    }
+
+    fn request(&self, method: &str, url: &str) -> ureq::Request {
+        let request = self.agent.request(method, url);
+        if self.config.username.is_empty() {
+            return request;
+        }
+        let credentials = format!("{}:{}", self.config.username, self.config.password);
+        let token = base64::engine::general_purpose::STANDARD.encode(credentials);
+        request.set("Authorization", &format!("Basic {}", token))
+    }
+
+    // 列出属性；地址不存在时返回 None
+    fn propfind(&self, url: &str, depth: &str) -> Result<Option<Vec<DavEntry>>, String> {
+        let response = match self.request("PROPFIND", url)
+            .set("Depth", depth)
+            .set("Content-Type", "application/xml; charset=utf-8")
+            .send_string(PROPFIND_BODY)
+        {
+            Ok(response) => response,
+            Err(ureq::Error::Status(404, _)) => return Ok(None),
+            Err(e) => return Err(http_err("列出远程文件", e)),
+        };
+        let body = response.into_string().map_err(|e| format!("列出远程文件失败: {}", e))?;
+        parse_multistatus(&body, &self.base_path).map(Some)
+    }
+
+    // 文件夹地址以 / 结尾
+    fn dir_url(&self, rel: &str) -> String {
+        format!("{}/", self.url(rel).trim_end_matches('/'))
+    }
+
+    // 单个文件的版本标识
+    fn token_of(&self, rel: &str) -> Result<String, String> {
+        self.propfind(&self.url(rel), "0")?
+            .unwrap_or_default()
+            .into_iter()
+            .find(|e| e.rel == rel)
+            .map(|e| e.token)
+            .ok_or_else(|| format!("读取远程文件信息失败: {}", rel))
+    }
+
+    // 创建文件夹（已存在时忽略）
+    fn mkcol(&self, rel: &str) -> Result<(), String> {
+        match self.request("MKCOL", &self.dir_url(rel)).call() {
+            Ok(_) | Err(ureq::Error::Status(405, _)) => Ok(()),
+            Err(e) => Err(http_err("创建远程文件夹", e)),
+        }
+    }
+
+    // 测试连接：远程文件夹不存在时也视为连接成功（首次同步时创建）
+    pub fn test(&self) -> Result<String, String> {
+        match self.propfind(&self.base, "0")? {
+            Some(_) => Ok("连接成功".to_string()),
+            None => Ok("连接成功，远程文件夹将在首次同步时创建".to_string()),
+        }
+    }
+}
+
+impl SyncBackend for WebDavRemote {
+    fn identity(&self) -> String {
+        format!("{}#{}", self.base, self.config.username)
+    }
+
+    // 逐层 PROPFIND（Depth: 1），部分服务器不支持 Depth: infinity
+    fn list(&mut self) -> Result<BTreeMap<String, RemoteFile>, String> {
+        let mut files = BTreeMap::new();
+        let mut pending = vec![String::new()];
+        while let Some(dir) = pending.pop() {
+            // 远程文件夹不存在时视为空
+            let Some(entries) = self.propfind(&self.dir_url(&dir), "1")? else {
+                continue;
+            };
+            for entry in entries {
+                if entry.rel == dir || entry.rel.is_empty() || library::is_hidden(&entry.rel) {
+                    continue;
+                }
+                if entry.is_dir {
+                    pending.push(entry.rel);
+                } else if entry.rel.ends_with(".md") || entry.rel.ends_with(".txt") {
+                    files.insert(entry.rel, RemoteFile { token: entry.token, hash: None });
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    fn read(&self, rel: &str) -> Result<Vec<u8>, String> {
+        let response = self.request("GET", &self.url(rel))
+            .call()
+            .map_err(|e| http_err("下载", e))?;
+        let mut bytes = Vec::new();
+        response.into_reader()
+            .read_to_end(&mut bytes)
+            .map_err(|e| format!("下载失败: {}", e))?;
+        Ok(bytes)
+    }
+
+    // 上传前逐级创建缺少的文件夹；服务器未在 PUT 响应中返回 ETag 时再单独查询
+    fn write(
+        &mut self,
+        uploads: &[(String, Vec<u8>)],
+        deletes: &[String],
+    ) -> Result<BTreeMap<String, String>, String> {
+        for rel in deletes {
+            match self.request("DELETE", &self.url(rel)).call() {
+                Ok(_) | Err(ureq::Error::Status(404, _)) => {}
+                Err(e) => return Err(http_err("删除远程文件", e)),
+            }
+        }
+
+        let mut created = HashSet::new();
+        let mut tokens = BTreeMap::new();
+        for (rel, bytes) in uploads {
+            let mut dirs = vec![String::new()];
+            let parts: Vec<&str> = rel.split('/').collect();
+            for i in 1..parts.len() {
+                dirs.push(parts[..i].join("/"));
+            }
+            for dir in dirs {
+                if created.insert(dir.clone()) {
+                    self.mkcol(&dir)?;
+                }
+            }
+
+            let response = self.request("PUT", &self.url(rel))
+                .send_bytes(bytes)
+                .map_err(|e| http_err("上传", e))?;
+            let token = match response.header("ETag") {
+                Some(etag) => normalize_etag(etag),
+                None => self.token_of(rel)?,
+            };
+            tokens.insert(rel.clone(), token);
+        }
+        Ok(tokens)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+    use std::sync::{Arc, Mutex};
+    use tiny_http::{Header, Response, Server};
+
+    // 本地 WebDAV 替身：只实现同步用到的 PROPFIND / GET / PUT / MKCOL / DELETE
+    // 路径为解码后的绝对路径（不以 / 结尾），文件的 ETag 为递增的序号
+    #[derive(Default)]
+    struct DavState {
+        dirs: BTreeSet<String>,
+        files: BTreeMap<String, (Vec<u8>, u64)>,
+        next_etag: u64,
+        // PUT 响应是否带 ETag（不带时客户端需要再 PROPFIND）
+        put_etag: bool,
+    }
+
+    const USER: &str = "me";
+    const PASSWORD: &str = "secret";
+
+    fn parent(path: &str) -> &str {
+        path.rsplit_once('/').map_or("", |(dir, _)| dir)
+    }
+
+    fn href(path: &str, is_dir: bool) -> String {
+        let encoded: Vec<String> = path.split('/').map(|s| utf8_percent_encode(s, SEGMENT).to_string()).collect();
+        format!("{}{}", encoded.join("/"), if is_dir { "/" } else { "" })
+    }
+
+    impl DavState {
+        fn propstat(&self, path: &str) -> Option<String> {
+            let (resource, etag) = if self.dirs.contains(path) {
+                ("<d:collection/>".to_string(), String::new())
+            } else {
+                // PROPFIND 返回弱 ETag，PUT 响应头返回强 ETag，客户端需统一写法
+                let (_, etag) = self.files.get(path)?;
+                (String::new(), format!("<d:getetag>W/&quot;{}&quot;</d:getetag>", etag))
+            };
+            Some(format!(
+                "<d:response><d:href>{}</d:href><d:propstat><d:prop><d:resourcetype>{}</d:resourcetype>{}</d:prop>\
+                 <d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>",
+                href(path, self.dirs.contains(path)), resource, etag,
+            ))
+        }
+
+        fn handle(&mut self, method: &str, path: &str, depth: &str, body: Vec<u8>) -> (u16, Vec<u8>, Option<String>) {
+            let exists = self.dirs.contains(path) || self.files.contains_key(path);
+            match method {
+                "PROPFIND" if exists => {
+                    let mut paths = vec![path.to_string()];
+                    if depth == "1" && self.dirs.contains(path) {
+                        paths.extend(self.dirs.iter().chain(self.files.keys()).filter(|p| parent(p) == path).cloned());
+                    }
+                    let responses: String = paths.iter().filter_map(|p| self.propstat(p)).collect();
+                    let xml = format!(r#"<?xml version="1.0"?><d:multistatus xmlns:d="DAV:">{}</d:multistatus>"#, responses);
+                    (207, xml.into_bytes(), None)
+                }
+                "GET" => match self.files.get(path) {
+                    Some((bytes, _)) => (200, bytes.clone(), None),
+                    None => (404, Vec::new(), None),
+                },
+                "PUT" if self.dirs.contains(parent(path)) => {
+                    self.next_etag += 1;
+                    self.files.insert(path.to_string(), (body, self.next_etag));
+                    let etag = self.put_etag.then(|| format!("\"{}\"", self.next_etag));
+                    (201, Vec::new(), etag)
+                }
+                "MKCOL" if exists => (405, Vec::new(), None),
+                "MKCOL" if self.dirs.contains(parent(path)) => {
+                    self.dirs.insert(path.to_string());
+                    (201, Vec::new(), None)
+                }
+                "DELETE" if exists => {
+                    let prefix = format!("{}/", path);
+                    self.dirs.retain(|d| d != path && !d.starts_with(&prefix));
+                    self.files.retain(|f, _| f != path && !f.starts_with(&prefix));
+                    (204, Vec::new(), None)
+                }
+                "PUT" | "MKCOL" => (409, Vec::new(), None),
+                _ => (404, Vec::new(), None),
+            }
+        }
+    }
+
+    // 启动替身服务器，挂载点为 /dav
+    fn start_server(put_etag: bool) -> (u16, Arc<Mutex<DavState>>) {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let port = server.server_addr().to_ip().unwrap().port();
+        let state = Arc::new(Mutex::new(DavState {
+            dirs: BTreeSet::from(["/dav".to_string()]),
+            put_etag,
+            ..Default::default()
+        }));
+        let shared = state.clone();
+        let expected_auth = format!(
+            "Basic {}",
+            base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", USER, PASSWORD)),
+        );
+        std::thread::spawn(move || {
+            for mut request in server.incoming_requests() {
+                let header = |name: &'static str| request.headers().iter()
+                    .find(|h| h.field.equiv(name))
+                    .map(|h| h.value.as_str().to_string());
+                let (authorized, depth) = (header("Authorization") == Some(expected_auth.clone()), header("Depth"));
+                let mut body = Vec::new();
+                request.as_reader().read_to_end(&mut body).unwrap();
+                let path = decode(request.url()).trim_end_matches('/').to_string();
+                let (status, bytes, etag) = if authorized {
+                    let method = request.method().as_str().to_string();
+                    shared.lock().unwrap().handle(&method, &path, &depth.unwrap_or_default(), body)
+                } else {
+                    (401, Vec::new(), None)
+                };
+                let mut response = Response::from_data(bytes).with_status_code(status);
+                if let Some(etag) = etag {
+                    response.add_header(Header::from_bytes("ETag", etag).unwrap());
+                }
+                let _ = request.respond(response);
+            }
+        });
+        (port, state)
+    }
+
+    fn remote(port: u16, password: &str) -> WebDavRemote {
+        WebDavRemote::new(WebDavConfig {
+            enabled: true,
+            url: format!("http://127.0.0.1:{}/dav/My%20Prompts", port),
+            username: USER.to_string(),
+            password: password.to_string(),
+        }).unwrap()
+    }
+
+    fn uploads(files: &[(&str, &str)]) -> Vec<(String, Vec<u8>)> {
+        files.iter().map(|(rel, content)| (rel.to_string(), content.as_bytes().to_vec())).collect()
+    }
+
+    fn tokens(files: &BTreeMap<String, RemoteFile>) -> BTreeMap<String, String> {
+        files.iter().map(|(rel, file)| (rel.clone(), file.token.clone())).collect()
+    }
+
+    #[test]
+    fn round_trip_against_stand_in_server() {
+        for put_etag in [true, false] {
+            let (port, state) = start_server(put_etag);
+            let mut remote = remote(port, PASSWORD);
+            assert_eq!(remote.test().unwrap(), "连接成功，远程文件夹将在首次同步时创建");
+            assert!(remote.list().unwrap().is_empty());
+
+            // 上传时逐级创建远程文件夹（含需要编码的中文和空格）
+            let written = remote.write(&uploads(&[
+                ("a.md", "A"),
+                ("写作/润色 v2.md", "B"),
+                ("写作/深/c.txt", "C"),
+            ]), &[]).unwrap();
+            assert_eq!(remote.test().unwrap(), "连接成功");
+            {
+                let mut state = state.lock().unwrap();
+                assert!(state.dirs.contains("/dav/My Prompts/写作/深"));
+                // 隐藏文件夹和其他类型的文件不列出
+                state.dirs.insert("/dav/My Prompts/.trash".to_string());
+                state.files.insert("/dav/My Prompts/.trash/old.md".to_string(), (Vec::new(), 100));
+                state.files.insert("/dav/My Prompts/image.png".to_string(), (Vec::new(), 101));
+            }
+
+            // PUT 返回的版本标识与 PROPFIND 列出的一致，未修改的文件不会被误判为变化
+            let listed = remote.list().unwrap();
+            assert_eq!(tokens(&listed), written, "put_etag = {}", put_etag);
+            assert_eq!(remote.read("写作/润色 v2.md").unwrap(), b"B");
+
+            let rewritten = remote.write(&uploads(&[("a.md", "A2")]), &[]).unwrap();
+            let relisted = tokens(&remote.list().unwrap());
+            assert_ne!(relisted["a.md"], written["a.md"]);
+            assert_eq!(relisted["a.md"], rewritten["a.md"]);
+            assert_eq!(relisted["写作/深/c.txt"], written["写作/深/c.txt"]);
+            assert_eq!(remote.read("a.md").unwrap(), b"A2");
+
+            // 删除不存在的文件不报错
+            remote.write(&[], &["写作/润色 v2.md".to_string(), "missing.md".to_string()]).unwrap();
+            let remaining: Vec<String> = remote.list().unwrap().into_keys().collect();
+            assert_eq!(remaining, ["a.md", "写作/深/c.txt"]);
+            assert!(remote.read("写作/润色 v2.md").unwrap_err().contains("404"));
+        }
+    }
+
+    #[test]
+    fn reports_authentication_failure() {
+        let (port, _state) = start_server(true);
+        let mut remote = remote(port, "wrong");
+        assert_eq!(remote.list().unwrap_err(), "认证失败，请检查用户名和密码");
+        assert_eq!(remote.test().unwrap_err(), "认证失败，请检查用户名和密码");
+    }
+
+    #[test]
+    fn syncs_library_through_stand_in_server() {
+        let (port, _state) = start_server(true);
+        let dir = tempfile::tempdir().unwrap();
+        let (first, second) = (dir.path().join("first"), dir.path().join("second"));
+        std::fs::create_dir_all(first.join("写作")).unwrap();
+        std::fs::create_dir_all(&second).unwrap();
+        std::fs::write(first.join("写作/润色.md"), "润色").unwrap();
+
+        let report = crate::sync::run(&first, &dir.path().join("first.json"), &mut remote(port, PASSWORD)).unwrap();
+        assert_eq!(report.uploaded, 1);
+        let report = crate::sync::run(&second, &dir.path().join("second.json"), &mut remote(port, PASSWORD)).unwrap();
+        assert_eq!(report.downloaded, 1);
+        assert_eq!(std::fs::read_to_string(second.join("写作/润色.md")).unwrap(), "润色");
+
+        // 两端都没有变化时不再传输
+        let actions = crate::sync::preview(&first, &dir.path().join("first.json"), &mut remote(port, PASSWORD)).unwrap();
+        assert!(actions.is_empty(), "{:?}", actions);
+
+        std::fs::write(second.join("写作/润色.md"), "润色 v2").unwrap();
+        crate::sync::run(&second, &dir.path().join("second.json"), &mut remote(port, PASSWORD)).unwrap();
+        let report = crate::sync::run(&first, &dir.path().join("first.json"), &mut remote(port, PASSWORD)).unwrap();
+        assert_eq!((report.downloaded, report.uploaded), (1, 0));
+        assert_eq!(std::fs::read_to_string(first.join("写作/润色.md")).unwrap(), "润色 v2");
+    }
+
+    // Nextcloud：d: / oc: / nc: 前缀，href 逐段编码，ETag 带引号，文件夹没有 getcontentlength
+    const NEXTCLOUD: &str = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:" xmlns:s="http://sabredav.org/ns" xmlns:oc="http://owncloud.org/ns" xmlns:nc="http://nextcloud.org/ns">
+ <d:response>
+  <d:href>/remote.php/dav/files/me/My%20Prompts/</d:href>
+  <d:propstat>
+   <d:prop>
+    <d:resourcetype><d:collection/></d:resourcetype>
+    <d:getetag>&quot;6530f1a2b7c4d&quot;</d:getetag>
+    <d:getlastmodified>Thu, 19 Oct 2023 09:12:34 GMT</d:getlastmodified>
+   </d:prop>
+   <d:status>HTTP/1.1 200 OK</d:status>
+  </d:propstat>
+  <d:propstat>
+   <d:prop><d:getcontentlength/></d:prop>
+   <d:status>HTTP/1.1 404 Not Found</d:status>
+  </d:propstat>
+ </d:response>
+ <d:response>
+  <d:href>/remote.php/dav/files/me/My%20Prompts/%E5%86%99%E4%BD%9C/</d:href>
+  <d:propstat>
+   <d:prop>
+    <d:resourcetype><d:collection/></d:resourcetype>
+    <d:getetag>&quot;6530f1a2c1e90&quot;</d:getetag>
+   </d:prop>
+   <d:status>HTTP/1.1 200 OK</d:status>
+  </d:propstat>
+ </d:response>
+ <d:response>
+  <d:href>/remote.php/dav/files/me/My%20Prompts/%E5%86%99%E4%BD%9C/%E6%B6%A6%E8%89%B2%20v2.md</d:href>
+  <d:propstat>
+   <d:prop>
+    <d:resourcetype/>
+    <d:getetag>&quot;3f2a9c0e5b1d7a8e4c6f&quot;</d:getetag>
+    <d:getlastmodified>Thu, 19 Oct 2023 09:12:34 GMT</d:getlastmodified>
+    <d:getcontentlength>412</d:getcontentlength>
+   </d:prop>
+   <d:status>HTTP/1.1 200 OK</d:status>
+  </d:propstat>
+ </d:response>
+</d:multistatus>"#;
+
+    // Apache mod_dav：D: 前缀、属性在 lp1: 命名空间前缀下仍属于 DAV:，弱 ETag，href 含主机
+    const APACHE: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<D:multistatus xmlns:D="DAV:" xmlns:ns0="DAV:">
+<D:response xmlns:lp1="DAV:" xmlns:lp2="http://apache.org/dav/props/">
+<D:href>/dav/prompts/</D:href>
+<D:propstat>
+<D:prop>
+<lp1:resourcetype><D:collection/></lp1:resourcetype>
+<lp1:getlastmodified>Mon, 02 Sep 2024 08:00:00 GMT</lp1:getlastmodified>
+<lp1:getetag>"1000-61f1c1a2b3c4d"</lp1:getetag>
+</D:prop>
+<D:status>HTTP/1.1 200 OK</D:status>
+</D:propstat>
+</D:response>
+<D:response xmlns:lp1="DAV:" xmlns:lp2="http://apache.org/dav/props/">
+<D:href>http://dav.example.com/dav/prompts/code%2Breview.md</D:href>
+<D:propstat>
+<D:prop>
+<lp1:resourcetype/>
+<lp1:getcontentlength>128</lp1:getcontentlength>
+<lp1:getlastmodified>Mon, 02 Sep 2024 08:00:00 GMT</lp1:getlastmodified>
+<lp1:getetag>W/"80-61f1c1a2b3c4d"</lp1:getetag>
+</D:prop>
+<D:status>HTTP/1.1 200 OK</D:status>
+</D:propstat>
+<D:propstat>
+<D:prop>
+<ns0:quota-used-bytes/>
+</D:prop>
+<D:status>HTTP/1.1 404 Not Found</D:status>
+</D:propstat>
+</D:response>
+<D:response xmlns:lp1="DAV:">
+<D:href>/dav/prompts/no-etag.md</D:href>
+<D:propstat>
+<D:prop>
+<lp1:resourcetype/>
+<lp1:getcontentlength>7</lp1:getcontentlength>
+<lp1:getlastmodified>Tue, 03 Sep 2024 10:00:00 GMT</lp1:getlastmodified>
+</D:prop>
+<D:status>HTTP/1.1 200 OK</D:status>
+</D:propstat>
+<D:propstat>
+<D:prop><lp1:getetag>"ignored"</lp1:getetag></D:prop>
+<D:status>HTTP/1.1 404 Not Found</D:status>
+</D:propstat>
+</D:response>
+<D:response>
+<D:href>/elsewhere/other.md</D:href>
+<D:propstat><D:prop><lp1:getetag xmlns:lp1="DAV:">"x"</lp1:getetag></D:prop><D:status>HTTP/1.1 200 OK</D:status></D:propstat>
+</D:response>
+</D:multistatus>"#;
+
+    fn summary(entries: &[DavEntry]) -> Vec<(&str, bool, &str)> {
+        entries.iter().map(|e| (e.rel.as_str(), e.is_dir, e.token.as_str())).collect()
+    }
+
+    #[test]
+    fn parses_nextcloud_multistatus() {
+        let entries = parse_multistatus(NEXTCLOUD, "/remote.php/dav/files/me/My Prompts/").unwrap();
+        assert_eq!(summary(&entries), vec![
+            ("", true, "6530f1a2b7c4d"),
+            ("写作", true, "6530f1a2c1e90"),
+            ("写作/润色 v2.md", false, "3f2a9c0e5b1d7a8e4c6f"),
+        ]);
+    }
+
+    #[test]
+    fn parses_apache_multistatus() {
+        let entries = parse_multistatus(APACHE, "/dav/prompts/").unwrap();
+        assert_eq!(summary(&entries), vec![
+            ("", true, "1000-61f1c1a2b3c4d"),
+            // %2B 解码为 +，带主机的 href 只取路径
+            ("code+review.md", false, "80-61f1c1a2b3c4d"),
+            // 404 中的 getetag 不采用，改用修改时间和大小
+            ("no-etag.md", false, "Tue, 03 Sep 2024 10:00:00 GMT|7"),
+        ]);
+    }
+
+    #[test]
+    fn rejects_malformed_xml() {
+        assert!(parse_multistatus("<d:multistatus xmlns:d=\"DAV:\">", "/").is_err());
+        // 其他命名空间中的同名元素不算
+        let foreign = r#"<multistatus xmlns="urn:other"><response><href>/a.md</href></response></multistatus>"#;
+        assert!(parse_multistatus(foreign, "/").unwrap().is_empty());
+    }
+
+    #[test]
+    fn normalize_etag_strips_weak_prefix_and_quotes() {
+        for raw in ["\"abc\"", "W/\"abc\"", "abc", " \"abc\" ", "W/abc"] {
+            assert_eq!(normalize_etag(raw), "abc", "{:?}", raw);
+        }
+        // PUT 响应头与 PROPFIND 的写法不同时仍视为同一版本
+        assert_eq!(normalize_etag("W/\"80-61f1\""), normalize_etag("\"80-61f1\""));
+        assert_eq!(normalize_etag("Tue, 03 Sep 2024 10:00:00 GMT|7"), "Tue, 03 Sep 2024 10:00:00 GMT|7");
+    }
+}
@@ -1,10 +1,11 @@
 // 提示词管理器 - 简约版
 const { invoke } = window.__TAURI__.core;
 const { ask } = window.__TAURI__.dialog;
+const { listen } = window.__TAURI__.event;
 
 let prompts = [];
 let selectedPrompt = null;
-let config = { prompts_folder: '', hotkey: 'Alt+Space', theme: 'dark', autostart: false, remote_sync: { enabled: false, server: '', remote_path: '', port: 22 } };
+let config = { prompts_folder: '', hotkey: 'Alt+Space', theme: 'dark', autostart: false, remote_sync: { enabled: false, server: '', remote_path: '', port: 22 }, git: { enabled: false, remote: '' }, webdav: { enabled: false, url: '', username: '', password: '' }, sync_encryption: false, api: { enabled: false, port: 7373 }, simulate: { backend: 'auto', char_delay_ms: 2, abort_key: 'escape' }, clipboard: { restore: true, paste_delay_ms: 50, restore_delay_ms: 300 }, insert: { paste_chord: 'Ctrl+V', profiles: [] } };
 let contextTarget = null;
 let autoSaveTimer = null;
 const AUTO_SAVE_DELAY = 1000;
@@ -58,6 +59,7 @@ window.addEventListener('DOMContentLoaded', async () => {
     $('#menu-new-folder').addEventListener('click', createNewFolderInFolder);
     $('#menu-rename-folder').addEventListener('click', renameFolderFromMenu);
     $('#menu-delete-folder').addEventListener('click', deleteFolderFromMenu);
+    $('#menu-encrypt-folder').addEventListener('click', toggleFolderEncryptionFromMenu);
     $('#menu-open-folder-2').addEventListener('click', openInExplorer);
 
     // 右键菜单 - 空白区域
@@ -80,7 +82,18 @@ window.addEventListener('DOMContentLoaded', async () => {
 
     // 同步设置
     $('#sync-enabled-checkbox').addEventListener('change', toggleSyncSettings);
+    $('#git-enabled-checkbox').addEventListener('change', toggleGitSettings);
     $('#test-ssh-btn').addEventListener('click', testSshConnection);
+    $('#webdav-enabled-checkbox').addEventListener('change', toggleWebdavSettings);
+    $('#test-webdav-btn').addEventListener('click', testWebdavConnection);
+
+    // 加密设置
+    $('#encryption-unlock-btn').addEventListener('click', unlockEncryption);
+    $('#encryption-lock-btn').addEventListener('click', lockEncryption);
+
+    // 本地接口设置
+    $('#api-enabled-checkbox').addEventListener('change', toggleApiSettings);
+    $('#api-regenerate-btn').addEventListener('click', regenerateApiToken);
 
     window.addEventListener('click', (e) => {
         if (e.target === $('#settings-modal')) {
@@ -93,6 +106,8 @@ window.addEventListener('DOMContentLoaded', async () => {
 
     // 加载配置
     await loadConfig();
+    // 订阅提示词库变化（外部编辑器、同步等）
+    listenLibraryChanges();
     // 全局快捷键 Ctrl+Q 已在 Rust 端注册
 });
 
@@ -181,6 +196,11 @@ async function selectFolder() {
 }
 
 let allFolders = []; // 存储所有文件夹路径
+let encryptedFolders = new Set(); // 标记为加密的文件夹（统一为 / 分隔）
+
+function normalizePath(path) {
+    return path.replace(/[\\/]+/g, '/');
+}
 
 async function loadPrompts() {
     if (!config.prompts_folder) return;
@@ -193,6 +213,8 @@ async function loadPrompts() {
         ]);
         prompts = promptsResult;
         allFolders = foldersResult;
+        const encrypted = await invoke('list_encrypted_folders').catch(() => []);
+        encryptedFolders = new Set(encrypted.map(normalizePath));
         renderFileTree();
     } catch (e) {
         console.error('加载失败:', e);
@@ -232,6 +254,61 @@ function renderFileTree() {
     });
 }
 
+// ====== 提示词库变化 ======
+let libraryRefreshTimer = null;
+
+function listenLibraryChanges() {
+    listen('prompt-created', scheduleLibraryRefresh);
+    listen('prompt-changed', scheduleLibraryRefresh);
+    listen('prompt-removed', scheduleLibraryRefresh);
+    listen('folder-renamed', (e) => {
+        renameExpandedFolders(e.payload.from, e.payload.to);
+        scheduleLibraryRefresh();
+    });
+}
+
+// 合并短时间内的多个事件
+function scheduleLibraryRefresh() {
+    if (libraryRefreshTimer) clearTimeout(libraryRefreshTimer);
+    libraryRefreshTimer = setTimeout(refreshLibrary, 200);
+}
+
+// 重新加载列表，当前提示词没有未保存的修改时同步磁盘上的最新内容
+async function refreshLibrary() {
+    const current = selectedPrompt;
+    await loadPrompts();
+    if (!current) return;
+
+    const latest = prompts.find(p => p.id === current.id);
+    if (!latest) {
+        selectedPrompt = null;
+        $('#prompt-title').value = '';
+        $('#prompt-content').value = '';
+        $('#prompt-mnemonic').value = '';
+        showToast('当前提示词已被删除或移出');
+        return;
+    }
+
+    const editing = $('#prompt-title').value !== current.title
+        || $('#prompt-content').value !== current.content;
+    selectedPrompt = latest;
+    if (!editing) {
+        $('#prompt-title').value = latest.title;
+        $('#prompt-content').value = latest.content;
+        loadMnemonic(latest.id);
+    }
+}
+
+// 文件夹重命名后保持展开状态
+function renameExpandedFolders(from, to) {
+    const toFolderPath = (rel) => config.prompts_folder + '\\' + rel.replace(/\//g, '\\');
+    const oldPath = toFolderPath(from);
+    const newPath = toFolderPath(to);
+    expandedFolders = new Set([...expandedFolders].map(p =>
+        p === oldPath || p.startsWith(oldPath + '\\') ? newPath + p.slice(oldPath.length) : p
+    ));
+}
+
 // 构建文件夹树结构
 function buildFolderTree(prompts, rootFolder) {
     const root = { children: {}, files: [] };
@@ -294,7 +371,7 @@ function renderFolderNode(node, depth, currentPath) {
         const folderPath = currentPath + '\\' + folderName;
         const indentClass = depth > 0 ? `indent-${depth}` : '';
         const isExpanded = expandedFolders.has(folderPath);
-        const icon = isExpanded ? '📂' : '📁';
+        const icon = encryptedFolders.has(normalizePath(folderPath)) ? '🔒' : (isExpanded ? '📂' : '📁');
         html += `<div class="tree-item folder ${indentClass}" data-path="${folderPath}" data-expanded="${isExpanded}"><span class="tree-icon">${icon}</span>${escapeHtml(folderName)}</div>`;
 
         // 只有展开时才渲染子内容
@@ -335,33 +412,33 @@ function selectPromptById(id) {
     $('#new-btn').textContent = '新建';
 
     // 加载助记词
-    loadMnemonic(prompt.file_path);
+    loadMnemonic(prompt.id);
 }
 
 // 加载助记词
-async function loadMnemonic(filePath) {
+async function loadMnemonic(id) {
     try {
-        const mnemonic = await invoke('get_mnemonic_for_file', { filePath });
-        $('#prompt-mnemonic').value = mnemonic || '';
+        const mnemonics = await invoke('get_mnemonics', { id });
+        $('#prompt-mnemonic').value = mnemonics.join(', ');
     } catch (e) {
         console.error('加载助记词失败:', e);
         $('#prompt-mnemonic').value = '';
     }
 }
 
-// 保存助记词
+// 保存助记词（多个别名用逗号分隔，第一个为主助记词）
 async function saveMnemonic() {
     if (!selectedPrompt) return;
 
-    const mnemonic = $('#prompt-mnemonic').value.trim();
-    const filePath = selectedPrompt.file_path;
+    const mnemonics = $('#prompt-mnemonic').value.split(/[,，]/).map(m => m.trim()).filter(Boolean);
+    const id = selectedPrompt.id;
 
     try {
-        if (mnemonic) {
-            await invoke('set_mnemonic', { mnemonic, filePath });
+        if (mnemonics.length > 0) {
+            await invoke('set_mnemonics', { mnemonics, id });
         } else {
             // 如果助记词为空，删除它
-            await invoke('remove_mnemonic', { filePath });
+            await invoke('remove_mnemonic', { id });
         }
     } catch (e) {
         showToast('助记词保存失败: ' + e);
@@ -396,6 +473,7 @@ function showFolderContextMenu(e, path) {
     hideAllContextMenus();
 
     contextTarget = { path, id: null, type: 'folder' };
+    $('#menu-encrypt-folder').textContent = encryptedFolders.has(normalizePath(path)) ? '取消加密' : '加密文件夹';
 
     const menu = $('#context-menu-folder');
     menu.style.left = e.clientX + 'px';
@@ -431,7 +509,7 @@ async function deleteFromMenu() {
         // 这里使用 confirm 确保逻辑顺序正确：确认 -> 操作
         // 如果用户反馈顺序反了，可能是 invoke 是异步的，所以这里必须 await
 
-        const yes = await ask(`确定要删除 "${prompt.title}" 吗？`, {
+        const yes = await ask(`确定要将 "${prompt.title}" 移到回收站吗？`, {
             title: '删除确认',
             kind: 'warning'
         });
@@ -445,7 +523,7 @@ async function deleteFromMenu() {
                     $('#prompt-content').value = '';
                 }
                 await loadPrompts();
-                showToast('已删除');
+                showToast('已移到回收站');
             } catch (e) {
                 showToast('删除失败: ' + e);
             }
@@ -557,7 +635,7 @@ async function deleteFolderFromMenu() {
     if (!contextTarget || !contextTarget.path) return;
 
     const folderName = contextTarget.path.split(/[/\\]/).pop();
-    const yes = await ask(`确定要删除文件夹 "${folderName}" 及其所有内容吗？`, {
+    const yes = await ask(`确定要将文件夹 "${folderName}" 及其所有内容移到回收站吗？`, {
         title: '删除确认',
         kind: 'warning'
     });
@@ -566,7 +644,7 @@ async function deleteFolderFromMenu() {
         try {
             await invoke('delete_folder', { folderPath: contextTarget.path });
             await loadPrompts();
-            showToast('已删除文件夹');
+            showToast('已将文件夹移到回收站');
         } catch (e) {
             showToast('删除失败: ' + e);
         }
@@ -574,6 +652,30 @@ async function deleteFolderFromMenu() {
     contextTarget = null;
 }
 
+// 加密或取消加密文件夹
+async function toggleFolderEncryptionFromMenu() {
+    if (!contextTarget || !contextTarget.path) return;
+
+    const path = contextTarget.path;
+    const encrypted = !encryptedFolders.has(normalizePath(path));
+    const folderName = path.split(/[/\\]/).pop();
+    const message = encrypted
+        ? `加密文件夹 "${folderName}" 中的所有提示词？\n加密后需要解锁才能查看和编辑；已提交到 Git 或已同步的旧版本仍为明文。`
+        : `取消加密文件夹 "${folderName}"？其中的提示词将以明文保存。`;
+    const yes = await ask(message, { title: encrypted ? '加密文件夹' : '取消加密', kind: 'warning' });
+
+    if (yes) {
+        try {
+            const count = await invoke('set_folder_encrypted', { folderPath: path, encrypted });
+            await loadPrompts();
+            showToast(encrypted ? `已加密 ${count} 个提示词` : `已解密 ${count} 个提示词`);
+        } catch (e) {
+            showToast('操作失败: ' + e);
+        }
+    }
+    contextTarget = null;
+}
+
 async function copyPrompt() {
     const content = $('#prompt-content').value;
     if (!content) {
@@ -582,10 +684,12 @@ async function copyPrompt() {
     }
 
     try {
-        await invoke('copy_to_clipboard', { app: null, text: content });
+        // 复制前展开片段和占位符（输入项使用默认值）
+        const rendered = await invoke('render_prompt', { text: content });
+        await invoke('copy_to_clipboard', { app: null, text: rendered.text, id: selectedPrompt?.id });
         showToast('已复制');
     } catch (e) {
-        showToast('复制失败');
+        showToast('复制失败: ' + e);
     }
 }
 
@@ -635,8 +739,9 @@ async function savePrompt(silent = false) {
 
         if (isRename || !selectedPrompt) {
             await loadPrompts();
-            // 通过路径查找新 ID 并选中
-            const newPrompt = prompts.find(p => p.file_path === newPath);
+            // ID 在重命名后保持不变，新建时通过路径查找
+            const newPrompt = prompts.find(p => selectedPrompt && p.id === selectedPrompt.id)
+                || prompts.find(p => p.file_path === newPath);
             if (newPrompt) {
                 selectPromptById(newPrompt.id);
             }
@@ -682,10 +787,54 @@ async function openSettings() {
     $('#sync-server').value = syncConfig.server || '';
     $('#sync-remote-path').value = syncConfig.remote_path || '';
     $('#sync-port').value = syncConfig.port || 22;
+    $('#sync-identity-file').value = syncConfig.identity_file || '';
+    $('#sync-known-hosts').value = syncConfig.known_hosts_file || '';
+    $('#sync-host-key-policy').value = syncConfig.host_key_policy || 'strict';
     $('#ssh-test-result').textContent = '';
 
+    // WebDAV 同步设置
+    const webdavConfig = config.webdav || { enabled: false, url: '', username: '', password: '' };
+    $('#webdav-enabled-checkbox').checked = webdavConfig.enabled;
+    $('#webdav-url').value = webdavConfig.url || '';
+    $('#webdav-username').value = webdavConfig.username || '';
+    $('#webdav-password').value = webdavConfig.password || '';
+    $('#webdav-test-result').textContent = '';
+
+    // 加密设置
+    $('#sync-encryption-checkbox').checked = config.sync_encryption || false;
+    $('#encryption-passphrase').value = '';
+    refreshEncryptionStatus();
+
+    // 粘贴插入设置
+    const clipboardConfig = config.clipboard || { restore: true, paste_delay_ms: 50, restore_delay_ms: 300 };
+    $('#clipboard-restore-checkbox').checked = clipboardConfig.restore ?? true;
+    $('#clipboard-paste-delay').value = clipboardConfig.paste_delay_ms ?? 50;
+    $('#clipboard-restore-delay').value = clipboardConfig.restore_delay_ms ?? 300;
+    const insertConfig = config.insert || { paste_chord: 'Ctrl+V', profiles: [] };
+    $('#insert-paste-chord').value = insertConfig.paste_chord || 'Ctrl+V';
+    $('#insert-profiles').value = JSON.stringify(insertConfig.profiles || [], null, 2);
+
+    // 模拟输入设置
+    const simulateConfig = config.simulate || { backend: 'auto', char_delay_ms: 2, abort_key: 'escape' };
+    $('#simulate-backend').value = simulateConfig.backend || 'auto';
+    $('#simulate-delay').value = simulateConfig.char_delay_ms ?? 2;
+    $('#simulate-abort-key').value = simulateConfig.abort_key || 'escape';
+
+    // 本地接口设置
+    const apiConfig = config.api || { enabled: false, port: 7373 };
+    $('#api-enabled-checkbox').checked = apiConfig.enabled;
+    $('#api-port').value = apiConfig.port || 7373;
+    toggleApiSettings();
+
+    // Git 模式设置
+    const gitConfig = config.git || { enabled: false, remote: '' };
+    $('#git-enabled-checkbox').checked = gitConfig.enabled;
+    $('#git-remote').value = gitConfig.remote || '';
+
     // 初始化同步设置状态
     toggleSyncSettings();
+    toggleWebdavSettings();
+    toggleGitSettings();
 
     // 检测 SSH 可用性
     checkSshAvailable();
@@ -760,17 +909,19 @@ async function saveSettings() {
     const newTheme = $('#theme-select').value;
     const newAutostart = $('#autostart-checkbox').checked;
 
-    // 远程同步配置
-    const newSyncEnabled = $('#sync-enabled-checkbox').checked;
-    const newSyncServer = $('#sync-server').value.trim();
-    const newSyncRemotePath = $('#sync-remote-path').value.trim();
-    const newSyncPort = parseInt($('#sync-port').value) || 22;
-
     if (!newHotkey) {
         showToast('快捷键不能为空');
         return;
     }
 
+    let profiles;
+    try {
+        profiles = JSON.parse($('#insert-profiles').value.trim() || '[]');
+    } catch (e) {
+        showToast('按程序插入配置格式错误: ' + e.message);
+        return;
+    }
+
     try {
         // 更新快捷键
         if (newHotkey !== config.hotkey) {
@@ -793,11 +944,33 @@ async function saveSettings() {
         }
 
         // 更新远程同步配置
-        config.remote_sync = {
-            enabled: newSyncEnabled,
-            server: newSyncServer,
-            remote_path: newSyncRemotePath,
-            port: newSyncPort
+        config.remote_sync = readSshSettings();
+        config.webdav = readWebdavSettings();
+        config.sync_encryption = $('#sync-encryption-checkbox').checked;
+        const pasteDelay = parseInt($('#clipboard-paste-delay').value);
+        const restoreDelay = parseInt($('#clipboard-restore-delay').value);
+        config.clipboard = {
+            restore: $('#clipboard-restore-checkbox').checked,
+            paste_delay_ms: Number.isNaN(pasteDelay) ? 50 : Math.max(0, pasteDelay),
+            restore_delay_ms: Number.isNaN(restoreDelay) ? 300 : Math.max(0, restoreDelay)
+        };
+        config.insert = {
+            paste_chord: $('#insert-paste-chord').value.trim() || 'Ctrl+V',
+            profiles
+        };
+        const delay = parseInt($('#simulate-delay').value);
+        config.simulate = {
+            backend: $('#simulate-backend').value,
+            char_delay_ms: Number.isNaN(delay) ? 2 : Math.max(0, delay),
+            abort_key: $('#simulate-abort-key').value
+        };
+        config.api = {
+            enabled: $('#api-enabled-checkbox').checked,
+            port: parseInt($('#api-port').value) || 7373
+        };
+        config.git = {
+            enabled: $('#git-enabled-checkbox').checked,
+            remote: $('#git-remote').value.trim()
         };
         await invoke('save_config', { config });
 
@@ -819,11 +992,18 @@ function escapeHtml(text) {
 
 // 打开同步模态框
 function openSyncModal() {
-    if (!config.remote_sync || !config.remote_sync.enabled) {
+    // Git 模式下通过 git 拉取 / 推送，WebDAV 同步不需要 SSH 配置
+    const gitMode = config.git && config.git.enabled;
+    const webdavMode = config.webdav && config.webdav.enabled;
+    if (!gitMode && !webdavMode && (!config.remote_sync || !config.remote_sync.enabled)) {
         showToast('请先在设置中启用远程同步');
         return;
     }
-    if (!config.remote_sync.server || !config.remote_sync.remote_path) {
+    if (webdavMode && !config.webdav.url) {
+        showToast('请先配置 WebDAV 地址');
+        return;
+    }
+    if (!gitMode && !webdavMode && (!config.remote_sync.server || !config.remote_sync.remote_path)) {
         showToast('请先配置服务器地址和远程路径');
         return;
     }
@@ -834,6 +1014,15 @@ function openSyncModal() {
 
     $('#sync-progress').classList.add('hidden');
     $('#sync-result').classList.add('hidden');
+    // Git 模式为拉取 / 推送，SSH 同步为预览 / 双向同步
+    const labels = gitMode
+        ? [['拉取', '从远程仓库拉取并合并'], ['推送', '推送到远程仓库']]
+        : [['预览', '查看将要进行的修改'], ['同步', '双向合并两端的修改']];
+    $('#sync-pull-btn .sync-label').textContent = labels[0][0];
+    $('#sync-pull-btn small').textContent = labels[0][1];
+    $('#sync-push-btn .sync-label').textContent = labels[1][0];
+    $('#sync-push-btn small').textContent = labels[1][1];
+
     $('#sync-pull-btn').disabled = false;
     $('#sync-push-btn').disabled = false;
     $('#sync-modal').classList.add('active');
@@ -844,9 +1033,18 @@ function closeSyncModal() {
     $('#sync-modal').classList.remove('active');
 }
 
-// 执行同步
+// 同步操作的说明
+const SYNC_ACTION_LABELS = {
+    upload: '上传',
+    download: '下载',
+    delete_local: '删除本机',
+    delete_remote: '删除远程',
+    conflict: '冲突'
+};
+
+// 执行同步（direction 为 pull 时 Git 模式拉取、SSH 同步预览；为 push 时推送或双向同步）
 async function doSync(direction) {
-    const { server, remote_path, port } = config.remote_sync;
+    const gitMode = config.git && config.git.enabled;
 
     $('#sync-pull-btn').disabled = true;
     $('#sync-push-btn').disabled = true;
@@ -855,20 +1053,20 @@ async function doSync(direction) {
 
     try {
         let result;
-        if (direction === 'pull') {
-            result = await invoke('sync_pull', {
-                localFolder: config.prompts_folder,
-                server,
-                remotePath: remote_path,
-                port: port || 22
-            });
+        if (gitMode) {
+            result = await invoke(direction === 'pull' ? 'git_pull' : 'git_push');
+        } else if (direction === 'pull') {
+            const actions = await invoke('sync_preview');
+            result = {
+                message: actions.length === 0
+                    ? '已是最新'
+                    : actions.map(a => `${SYNC_ACTION_LABELS[a.kind]}  ${a.rel_path}`).join('\n')
+            };
         } else {
-            result = await invoke('sync_push', {
-                localFolder: config.prompts_folder,
-                server,
-                remotePath: remote_path,
-                port: port || 22
-            });
+            result = await syncNow();
+            if (result.conflicts.length > 0) {
+                result.message += '\n冲突副本：\n' + result.conflicts.join('\n');
+            }
         }
 
         $('#sync-progress').classList.add('hidden');
@@ -887,6 +1085,27 @@ async function doSync(direction) {
     $('#sync-push-btn').disabled = false;
 }
 
+// 双向同步；开启同步加密后远程有未加密的文件时，经用户确认才作为一次性迁移接受
+const PLAINTEXT_REMOTE = '远程存在未加密的文件';
+
+async function syncNow() {
+    try {
+        return await invoke('sync_now');
+    } catch (e) {
+        if (!String(e).startsWith(PLAINTEXT_REMOTE)) {
+            throw e;
+        }
+        const yes = await ask(`${e}\n\n如果这些文件是开启同步加密之前由你上传的，可以下载后加密重新上传。若不确定来源（可能被服务器篡改），请取消。是否继续？`, {
+            title: '迁移未加密的文件',
+            kind: 'warning'
+        });
+        if (!yes) {
+            throw e;
+        }
+        return await invoke('sync_now', { migratePlaintext: true });
+    }
+}
+
 // 切换同步设置的启用状态
 function toggleSyncSettings() {
     const enabled = $('#sync-enabled-checkbox').checked;
@@ -895,13 +1114,163 @@ function toggleSyncSettings() {
     });
 }
 
+// 切换 Git 设置的启用状态
+function toggleGitSettings() {
+    const enabled = $('#git-enabled-checkbox').checked;
+    document.querySelectorAll('.git-settings').forEach(el => {
+        el.classList.toggle('disabled', !enabled);
+    });
+}
+
+// 刷新加密状态（未设置密码时解锁按钮用于设置密码）
+// 本地接口设置
+async function toggleApiSettings() {
+    const enabled = $('#api-enabled-checkbox').checked;
+    document.querySelectorAll('.api-settings').forEach(el => {
+        el.classList.toggle('disabled', !enabled);
+    });
+    if (enabled) {
+        try {
+            showApiToken(await invoke('get_api_token'));
+        } catch (e) {
+            $('#api-token').textContent = '获取失败';
+        }
+    }
+}
+
+function showApiToken(token) {
+    $('#api-token').textContent = token;
+    $('#api-token').onclick = async () => {
+        await invoke('copy_to_clipboard', { text: token });
+        showToast('令牌已复制');
+    };
+}
+
+async function regenerateApiToken() {
+    const yes = await ask('重新生成后，使用旧令牌的编辑器插件和脚本需要更新令牌，确定继续？', {
+        title: '重新生成令牌',
+        kind: 'warning'
+    });
+    if (!yes) return;
+    try {
+        showApiToken(await invoke('regenerate_api_token'));
+        showToast('已生成新令牌');
+    } catch (e) {
+        showToast('生成失败: ' + e);
+    }
+}
+
+async function refreshEncryptionStatus() {
+    const statusEl = $('#encryption-status');
+    try {
+        const status = await invoke('get_encryption_status');
+        if (!status.configured) {
+            statusEl.textContent = '未设置密码';
+            statusEl.className = 'ssh-status';
+        } else if (status.unlocked) {
+            statusEl.textContent = '🔓 已解锁';
+            statusEl.className = 'ssh-status available';
+        } else {
+            statusEl.textContent = '🔒 已锁定';
+            statusEl.className = 'ssh-status unavailable';
+        }
+        $('#encryption-unlock-btn').textContent = status.configured ? '解锁' : '设置密码';
+        $('#encryption-unlock-btn').disabled = status.unlocked;
+        $('#encryption-lock-btn').disabled = !status.unlocked;
+        return status;
+    } catch (e) {
+        statusEl.textContent = '✗ 检测失败';
+        statusEl.className = 'ssh-status unavailable';
+    }
+}
+
+async function unlockEncryption() {
+    const passphrase = $('#encryption-passphrase').value;
+    if (!passphrase) {
+        showToast('请输入密码');
+        return;
+    }
+    try {
+        const status = await invoke('get_encryption_status');
+        await invoke(status.configured ? 'unlock_encryption' : 'setup_encryption', { passphrase });
+        $('#encryption-passphrase').value = '';
+        await refreshEncryptionStatus();
+        await loadPrompts();
+        showToast(status.configured ? '已解锁' : '已设置密码');
+    } catch (e) {
+        showToast(e);
+    }
+}
+
+async function lockEncryption() {
+    try {
+        await invoke('lock_encryption');
+        await refreshEncryptionStatus();
+        await loadPrompts();
+        showToast('已锁定');
+    } catch (e) {
+        showToast('锁定失败: ' + e);
+    }
+}
+
+// 切换 WebDAV 设置的启用状态
+function toggleWebdavSettings() {
+    const enabled = $('#webdav-enabled-checkbox').checked;
+    document.querySelectorAll('.webdav-settings').forEach(el => {
+        el.classList.toggle('disabled', !enabled);
+    });
+}
+
+function readSshSettings() {
+    return {
+        enabled: $('#sync-enabled-checkbox').checked,
+        server: $('#sync-server').value.trim(),
+        remote_path: $('#sync-remote-path').value.trim(),
+        port: parseInt($('#sync-port').value) || 22,
+        identity_file: $('#sync-identity-file').value.trim(),
+        known_hosts_file: $('#sync-known-hosts').value.trim(),
+        host_key_policy: $('#sync-host-key-policy').value
+    };
+}
+
+function readWebdavSettings() {
+    return {
+        enabled: $('#webdav-enabled-checkbox').checked,
+        url: $('#webdav-url').value.trim(),
+        username: $('#webdav-username').value.trim(),
+        password: $('#webdav-password').value
+    };
+}
+
+// 测试 WebDAV 连接
+async function testWebdavConnection() {
+    const webdav = readWebdavSettings();
+    const resultEl = $('#webdav-test-result');
+
+    if (!webdav.url) {
+        resultEl.textContent = '请输入文件夹地址';
+        resultEl.className = 'test-result error';
+        return;
+    }
+
+    resultEl.textContent = '测试中...';
+    resultEl.className = 'test-result';
+
+    try {
+        resultEl.textContent = await invoke('test_webdav_connection', { config: webdav });
+        resultEl.className = 'test-result success';
+    } catch (e) {
+        resultEl.textContent = e;
+        resultEl.className = 'test-result error';
+    }
+}
+
 // 测试 SSH 连接
 async function testSshConnection() {
-    const server = $('#sync-server').value.trim();
-    const port = parseInt($('#sync-port').value) || 22;
+    const sshConfig = readSshSettings();
     const resultEl = $('#ssh-test-result');
 
-    if (!server) {
+    if (!sshConfig.server) {
         resultEl.textContent = '请输入服务器地址';
         resultEl.className = 'test-result error';
         return;
@@ -911,11 +1280,12 @@ async function testSshConnection() {
     resultEl.className = 'test-result';
 
     try {
-        const result = await invoke('test_ssh_connection', { server, port });
+        const result = await invoke('test_ssh_connection', { config: sshConfig });
         resultEl.textContent = result;
         resultEl.className = 'test-result success';
     } catch (e) {
-        resultEl.textContent = e;
+        // 错误为 { kind, message }，kind 区分认证失败、无法连接、远程路径不存在等
+        resultEl.textContent = e.message || e;
         resultEl.className = 'test-result error';
     }
 }
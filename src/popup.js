@@ -1,36 +1,32 @@
 // 快速插入弹窗逻辑
 const { invoke } = window.__TAURI__.core;
+const { listen } = window.__TAURI__.event;
 
 let items = [];
 let selectedIndex = 0;
+let fillingInputs = false; // 正在填写模板输入项
+let searchSeq = 0; // 丢弃过期的搜索结果
+const SEARCH_LIMIT = 50;
 
 async function init() {
     try {
         // 加载配置并应用主题
         const config = await invoke('get_config');
         document.documentElement.setAttribute('data-theme', config.theme || 'dark');
-
-        items = await invoke('get_all_mnemonics');
     } catch (e) {
-        console.error('加载助记词失败:', e);
-        items = [];
+        console.error('加载配置失败:', e);
     }
-    renderList(items);
+    await runSearch('');
 
-    // 搜索过滤（支持标题、助记词、内容全文检索）
+    // 搜索（助记词、标题、拼音首字母、正文，由后端排序）
     document.getElementById('search').addEventListener('input', (e) => {
-        const query = e.target.value.toLowerCase();
-        const filtered = items.filter(item =>
-            item.mnemonic.toLowerCase().includes(query) ||
-            item.title.toLowerCase().includes(query) ||
-            item.content.toLowerCase().includes(query)
-        );
         selectedIndex = 0;
-        renderList(filtered);
+        runSearch(e.target.value);
     });
 
     // 键盘导航
     document.addEventListener('keydown', async (e) => {
+        if (fillingInputs) return;
         const listItems = document.querySelectorAll('.list-item');
 
         if (e.key === 'ArrowDown') {
@@ -45,11 +41,20 @@ async function init() {
             e.preventDefault();
             const selectedItem = listItems[selectedIndex];
             if (selectedItem) {
-                const content = contentMap.get(selectedItem.dataset.index);
-                if (content) {
-                    await insertContent(content);
+                const item = itemMap.get(selectedItem.dataset.index);
+                if (item?.content) {
+                    await insertContent(item.content, item.id);
                 }
             }
+        } else if ((e.ctrlKey || e.metaKey) && e.key.toLowerCase() === 'p') {
+            // Ctrl+P 置顶 / 取消置顶
+            e.preventDefault();
+            const selectedItem = listItems[selectedIndex];
+            const item = selectedItem && itemMap.get(selectedItem.dataset.index);
+            if (item?.id) {
+                await invoke('pin_prompt', { id: item.id, pinned: !item.pinned });
+                await reloadItems();
+            }
         } else if (e.key === 'Escape') {
             await invoke('hide_popup');
         }
@@ -60,19 +65,19 @@ async function init() {
         await invoke('hide_popup');
     });
 
-    // 窗口获得焦点时重新加载数据（确保内容实时）
+    // 窗口获得焦点时清空搜索框并重新加载
     window.addEventListener('focus', async () => {
-        try {
-            items = await invoke('get_all_mnemonics');
-            // 清空搜索框并重新渲染
-            document.getElementById('search').value = '';
-            selectedIndex = 0;
-            renderList(items);
-        } catch (e) {
-            console.error('刷新数据失败:', e);
-        }
+        if (fillingInputs) return;
+        document.getElementById('search').value = '';
+        selectedIndex = 0;
+        await runSearch('');
     });
 
+    // 提示词库变化时刷新列表（保留当前搜索）
+    for (const name of ['prompt-created', 'prompt-changed', 'prompt-removed', 'folder-renamed']) {
+        listen(name, reloadItems);
+    }
+
     // 模拟输入剪切板内容按钮
     document.getElementById('paste-clipboard-btn').addEventListener('click', async () => {
         try {
@@ -80,7 +85,7 @@ async function init() {
             if (clipboardText) {
                 await invoke('hide_popup');
                 await new Promise(r => setTimeout(r, 100));
-                await invoke('type_text_simulate', { text: clipboardText });
+                await invoke('type_text_simulate', { text: clipboardText, raw: true });
             }
         } catch (e) {
             console.error('模拟输入剪切板失败:', e);
@@ -88,40 +93,77 @@ async function init() {
     });
 }
 
-// 存储完整内容的映射
-let contentMap = new Map();
+// 搜索并渲染结果
+async function runSearch(query) {
+    const seq = ++searchSeq;
+    try {
+        const results = await invoke('search_prompts', { query, limit: SEARCH_LIMIT });
+        if (seq !== searchSeq) return;
+        items = results;
+    } catch (e) {
+        console.error('搜索失败:', e);
+        items = [];
+    }
+    renderList(items);
+}
+
+// 提示词库变化后按当前搜索词重新搜索
+async function reloadItems() {
+    if (fillingInputs) return;
+    await runSearch(document.getElementById('search').value);
+}
+
+// 按高亮范围（字符下标）输出 HTML
+function highlight(text, item, field) {
+    const chars = Array.from(text);
+    const ranges = item.highlights.filter(h => h.field === field);
+    let html = '';
+    let pos = 0;
+    for (const { start, end } of ranges) {
+        html += escapeHtml(chars.slice(pos, start).join(''));
+        html += `<mark>${escapeHtml(chars.slice(start, end).join(''))}</mark>`;
+        pos = end;
+    }
+    return html + escapeHtml(chars.slice(pos).join(''));
+}
+
+// 存储列表项的映射
+let itemMap = new Map();
 
 function renderList(data) {
     const list = document.getElementById('list');
-    contentMap.clear();
+    itemMap.clear();
 
     if (data.length === 0) {
         list.innerHTML = '<div class="empty">暂无助记词</div>';
         return;
     }
 
-    // 存储内容到 Map 中，避免 HTML 属性转义问题
+    // 存储到 Map 中，避免 HTML 属性转义问题
     data.forEach((item, index) => {
-        contentMap.set(index.toString(), item.content);
+        itemMap.set(index.toString(), item);
     });
 
     list.innerHTML = data.map((item, index) => `
         <div class="list-item ${index === selectedIndex ? 'selected' : ''}"
              data-index="${index}">
             <div>
-                <span class="mnemonic">${escapeHtml(item.mnemonic)}</span>
-                <span class="title">${escapeHtml(item.title)}</span>
+                ${item.pinned ? '<span class="pin" title="已置顶 (Ctrl+P 取消)">📌</span>' : ''}
+                <span class="mnemonic">${item.matched_mnemonic
+                    ? highlight(item.matched_mnemonic, item, 'mnemonic')
+                    : escapeHtml(item.mnemonic)}</span>
+                <span class="title">${highlight(item.title, item, 'title')}</span>
             </div>
-            <div class="preview">${escapeHtml(item.content.substring(0, 50))}...</div>
+            <div class="preview">${highlight(item.preview, item, 'content')}</div>
         </div>
     `).join('');
 
     // 点击事件
     list.querySelectorAll('.list-item').forEach(el => {
         el.addEventListener('click', async () => {
-            const content = contentMap.get(el.dataset.index);
-            if (content) {
-                await insertContent(content);
+            const item = itemMap.get(el.dataset.index);
+            if (item?.content) {
+                await insertContent(item.content, item.id);
             }
         });
     });
@@ -135,11 +177,60 @@ function updateSelection(listItems) {
     listItems[selectedIndex]?.scrollIntoView({ block: 'nearest' });
 }
 
-async function insertContent(content) {
+// 让用户填写模板中的输入项，取消时返回 null
+function askInputs(inputs) {
+    return new Promise((resolve) => {
+        fillingInputs = true;
+        const list = document.getElementById('list');
+        list.innerHTML = `
+            <div class="input-form">
+                ${inputs.map(input => `
+                    <label class="input-label">${escapeHtml(input.label || input.name)}</label>
+                    <input class="search-input input-field" name="${escapeHtml(input.name)}"
+                           value="${escapeHtml(input.default || '')}" />
+                `).join('')}
+                <div class="input-hint">Enter 插入 · Esc 取消</div>
+            </div>
+        `;
+        const fields = [...list.querySelectorAll('.input-field')];
+        fields[0]?.focus();
+        fields[0]?.select();
+
+        const finish = (values) => {
+            fillingInputs = false;
+            document.removeEventListener('keydown', onKeydown, true);
+            resolve(values);
+        };
+        const onKeydown = (e) => {
+            if (e.key === 'Escape') {
+                e.preventDefault();
+                e.stopPropagation();
+                finish(null);
+                renderList(items);
+            } else if (e.key === 'Enter') {
+                e.preventDefault();
+                e.stopPropagation();
+                finish(Object.fromEntries(fields.map(f => [f.name, f.value])));
+            }
+        };
+        document.addEventListener('keydown', onKeydown, true);
+    });
+}
+
+// id 用于记录使用次数
+async function insertContent(content, id) {
     try {
         // 检查是否使用模拟输入模式
         const useSimulate = document.getElementById('simulate-mode')?.checked || false;
 
+        // 模板中有输入项时先让用户填写
+        const { inputs: required } = await invoke('render_prompt', { text: content });
+        let inputs = {};
+        if (required.length > 0) {
+            inputs = await askInputs(required);
+            if (inputs === null) return;
+        }
+
         // 先隐藏窗口
         await invoke('hide_popup');
 
@@ -148,9 +239,9 @@ async function insertContent(content) {
 
         // 根据模式选择输入方式
         if (useSimulate) {
-            await invoke('type_text_simulate', { text: content });
+            await invoke('type_text_simulate', { text: content, inputs, id });
         } else {
-            await invoke('type_text', { text: content });
+            await invoke('type_text', { text: content, inputs, id });
         }
     } catch (e) {
         console.error('插入失败:', e);